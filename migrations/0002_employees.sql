@@ -0,0 +1,17 @@
+CREATE TABLE employees (
+    id UUID PRIMARY KEY,
+    username VARCHAR NOT NULL UNIQUE,
+    name VARCHAR NOT NULL
+);
+
+-- Spellings that only differ in case or surrounding whitespace belong to the same person.
+INSERT INTO employees (id, username, name)
+SELECT gen_random_uuid(), LOWER(TRIM(employee)), MIN(TRIM(employee))
+FROM hours
+GROUP BY LOWER(TRIM(employee));
+
+UPDATE hours SET employee = LOWER(TRIM(employee));
+
+ALTER TABLE hours
+    ADD CONSTRAINT hours_employee_fkey FOREIGN KEY (employee)
+    REFERENCES employees (username) ON UPDATE CASCADE;
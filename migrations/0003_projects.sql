@@ -0,0 +1,20 @@
+CREATE TABLE projects (
+    id UUID PRIMARY KEY,
+    code VARCHAR NOT NULL UNIQUE,
+    name VARCHAR NOT NULL,
+    start_date DATE,
+    end_date DATE,
+    archived BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+-- Projects that already have hours logged to them stay open-ended.
+INSERT INTO projects (id, code, name)
+SELECT gen_random_uuid(), TRIM(project), TRIM(project)
+FROM hours
+GROUP BY TRIM(project);
+
+UPDATE hours SET project = TRIM(project);
+
+ALTER TABLE hours
+    ADD CONSTRAINT hours_project_fkey FOREIGN KEY (project)
+    REFERENCES projects (code) ON UPDATE CASCADE;
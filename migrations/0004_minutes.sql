@@ -0,0 +1,2 @@
+ALTER TABLE hours RENAME COLUMN hours TO minutes;
+ALTER TABLE hours ALTER COLUMN minutes TYPE INT4 USING minutes * 60;
@@ -0,0 +1,11 @@
+-- A timer only exists while it is running, stopping it turns it into an hours entry.
+CREATE TABLE timers (
+    id UUID PRIMARY KEY,
+    employee VARCHAR NOT NULL UNIQUE
+        REFERENCES employees (username) ON UPDATE CASCADE ON DELETE CASCADE,
+    project VARCHAR NOT NULL
+        REFERENCES projects (code) ON UPDATE CASCADE ON DELETE CASCADE,
+    story_id VARCHAR,
+    description VARCHAR NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL
+);
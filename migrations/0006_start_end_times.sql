@@ -0,0 +1,7 @@
+ALTER TABLE hours
+    ADD COLUMN start_time TIME,
+    ADD COLUMN end_time TIME,
+    ADD CONSTRAINT hours_start_end_time_check
+        CHECK ((start_time IS NULL) = (end_time IS NULL) AND start_time < end_time);
+
+CREATE INDEX hours_employee_date_idx ON hours (employee, date);
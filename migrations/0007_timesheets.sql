@@ -0,0 +1,9 @@
+-- A week without a row is a draft.
+CREATE TABLE timesheets (
+    employee VARCHAR NOT NULL
+        REFERENCES employees (username) ON UPDATE CASCADE ON DELETE CASCADE,
+    week DATE NOT NULL CHECK (EXTRACT(ISODOW FROM week) = 1),
+    state VARCHAR NOT NULL,
+    reason VARCHAR,
+    PRIMARY KEY (employee, week)
+);
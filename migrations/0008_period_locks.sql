@@ -0,0 +1,9 @@
+-- Every change of the cutoff is kept as a record of who closed or reopened what, the latest one
+-- is in effect.
+CREATE TABLE period_locks (
+    id UUID PRIMARY KEY,
+    locked_until DATE,
+    reason VARCHAR,
+    changed_by VARCHAR NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,23 @@
+-- Every insert, update and delete of an hours entry. The entry is not referenced with a foreign
+-- key so that its history outlives it.
+CREATE TABLE hours_audit (
+    id BIGSERIAL PRIMARY KEY,
+    hours_id UUID NOT NULL,
+    operation VARCHAR NOT NULL,
+    actor VARCHAR NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL,
+    before JSONB,
+    after JSONB
+);
+
+CREATE INDEX hours_audit_hours_id_idx ON hours_audit (hours_id);
+
+CREATE FUNCTION hours_audit_append_only() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'hours_audit is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER hours_audit_append_only
+    BEFORE UPDATE OR DELETE ON hours_audit
+    FOR EACH ROW EXECUTE PROCEDURE hours_audit_append_only();
@@ -0,0 +1,4 @@
+-- Deleted entries stay in the trash until they are restored or purged.
+ALTER TABLE hours ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX hours_deleted_at_idx ON hours (deleted_at) WHERE deleted_at IS NOT NULL;
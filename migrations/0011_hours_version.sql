@@ -0,0 +1,2 @@
+-- Incremented on every change, the entity tag of the entry is its version.
+ALTER TABLE hours ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,8 @@
+-- Entries logged with an Idempotency-Key, so that retried requests can be answered with the
+-- original response instead of logging the entry again.
+CREATE TABLE idempotency_keys (
+    key VARCHAR(255) PRIMARY KEY,
+    hours_id UUID NOT NULL,
+    response JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,11 @@
+-- Only the SHA-256 hash of a key is stored, the key itself is shown once when it is created.
+-- Revoked keys are kept so that the list of keys shows what was in use.
+CREATE TABLE api_keys (
+    id UUID PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    prefix VARCHAR NOT NULL,
+    hash VARCHAR NOT NULL UNIQUE,
+    admin BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ
+);
@@ -0,0 +1,9 @@
+-- Keys act as an employee, a manager or an admin. Admin keys stay admins.
+ALTER TABLE api_keys ADD COLUMN role VARCHAR NOT NULL DEFAULT 'employee';
+UPDATE api_keys SET role = 'admin' WHERE admin;
+ALTER TABLE api_keys DROP COLUMN admin;
+
+-- Managers can see the hours of the employees they manage.
+ALTER TABLE employees
+    ADD COLUMN manager VARCHAR REFERENCES employees (username)
+    ON UPDATE CASCADE ON DELETE SET NULL;
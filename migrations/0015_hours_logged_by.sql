@@ -0,0 +1,2 @@
+-- Who logged the entry when it was not the employee themselves.
+ALTER TABLE hours ADD COLUMN logged_by VARCHAR;
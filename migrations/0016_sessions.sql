@@ -0,0 +1,18 @@
+-- Logins to the web UI that have been sent to the identity provider and have not come back yet.
+CREATE TABLE oidc_logins (
+    state VARCHAR PRIMARY KEY,
+    code_verifier VARCHAR NOT NULL,
+    nonce VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+-- Sessions of the web UI. Only the SHA-256 hash of the ID in the session cookie is stored.
+CREATE TABLE sessions (
+    hash VARCHAR PRIMARY KEY,
+    subject VARCHAR NOT NULL,
+    role VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX sessions_expires_at_idx ON sessions (expires_at);
@@ -0,0 +1,9 @@
+-- Idempotency keys are chosen by the clients, so each caller gets keys of their own. Keys that
+-- were used before are kept for whoever logged the entry.
+ALTER TABLE idempotency_keys ADD COLUMN subject VARCHAR;
+UPDATE idempotency_keys SET subject = COALESCE(response->>'logged_by', response->>'employee');
+ALTER TABLE idempotency_keys ALTER COLUMN subject SET NOT NULL;
+ALTER TABLE idempotency_keys DROP CONSTRAINT idempotency_keys_pkey;
+ALTER TABLE idempotency_keys ADD PRIMARY KEY (subject, key);
+
+CREATE INDEX idempotency_keys_created_at_idx ON idempotency_keys (created_at);
@@ -0,0 +1,62 @@
+use actix_web::{
+    web::{self, Data, Path},
+    HttpResponse,
+};
+use uuid::Uuid;
+
+use crate::auth::{Identity, Role};
+use crate::db::{ApiKeyRepo, Database};
+use crate::error::{Error, Validated};
+
+mod model;
+
+pub use model::{hash, ApiKey, CreatedApiKey, NewApiKey};
+
+pub async fn list_api_keys<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    identity.require_admin()?;
+    let mut connection = db.connection().await?;
+    Ok(HttpResponse::Ok().json(connection.list_api_keys().await?))
+}
+
+/// The response is the only place the key itself is ever shown.
+pub async fn create_api_key<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    json: web::Json<NewApiKey>,
+) -> Result<HttpResponse, Error> {
+    identity.require_admin()?;
+    let new_key = json.into_inner();
+    new_key.validate()?;
+    let mut connection = db.connection().await?;
+    let created = ApiKey::generate(new_key);
+    connection.insert_api_key(&created.api_key).await?;
+    Ok(HttpResponse::Created().json(created))
+}
+
+pub async fn revoke_api_key<D: Database>(
+    id: Path<Uuid>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    identity.require_admin()?;
+    let mut connection = db.connection().await?;
+    let id = id.into_inner();
+    Ok(match connection.revoke_api_key(id).await? {
+        Some(_) => HttpResponse::NoContent().finish(),
+        None => HttpResponse::NotFound().json(id),
+    })
+}
+
+/// Creates an admin key outside of the API, for setting up the first one.
+pub async fn create_admin_key<D: Database>(db: &D, name: &str) -> Result<CreatedApiKey, Error> {
+    let mut connection = db.connection().await?;
+    let created = ApiKey::generate(NewApiKey {
+        name: name.to_owned(),
+        role: Role::Admin,
+    });
+    connection.insert_api_key(&created.api_key).await?;
+    Ok(created)
+}
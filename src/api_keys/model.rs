@@ -0,0 +1,140 @@
+use std::convert::TryFrom;
+
+use chrono::{DateTime, SubsecRound, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::auth::Role;
+use crate::error::{FieldValidationError, Validated};
+
+/// Every key starts with this, so that leaked keys are easy to recognize.
+const KEY_PREFIX: &str = "cbk_";
+/// How much of the key is kept in the clear to tell keys apart.
+const SHOWN_LENGTH: usize = KEY_PREFIX.len() + 8;
+
+#[derive(Debug, Deserialize)]
+pub struct NewApiKey {
+    /// What the key is used by, e.g. the name of the client.
+    pub name: String,
+    /// Keys are for employees unless given another role. Admin keys can manage the other keys.
+    #[serde(default)]
+    pub role: Role,
+}
+
+impl Validated for NewApiKey {
+    fn validate(&self) -> Result<(), Vec<FieldValidationError>> {
+        if self.name.trim().is_empty() {
+            Err(vec![FieldValidationError::new(
+                "name".to_owned(),
+                "can not be empty".to_owned(),
+            )])
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    /// The start of the key.
+    pub prefix: String,
+    #[serde(skip)]
+    pub hash: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// A key that has just been created. The key itself is not stored and can not be shown again.
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+impl ApiKey {
+    /// Generates a new random key.
+    pub fn generate(new_key: NewApiKey) -> CreatedApiKey {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        ApiKey::from_key(format!("{}{}", KEY_PREFIX, to_hex(&bytes)), new_key)
+    }
+
+    pub(crate) fn from_key(key: String, new_key: NewApiKey) -> CreatedApiKey {
+        CreatedApiKey {
+            api_key: ApiKey {
+                id: Uuid::new_v4(),
+                name: new_key.name.trim().to_owned(),
+                prefix: key.chars().take(SHOWN_LENGTH).collect(),
+                hash: hash(&key),
+                role: new_key.role,
+                created_at: Utc::now().trunc_subsecs(6),
+                revoked_at: None,
+            },
+            key,
+        }
+    }
+}
+
+/// The keys are long and random, a plain SHA-256 is enough to keep them safe at rest.
+pub fn hash(key: &str) -> String {
+    to_hex(&Sha256::digest(key.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl TryFrom<PgRow> for ApiKey {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(ApiKey {
+            id: value.try_get("id")?,
+            name: value.try_get("name")?,
+            prefix: value.try_get("prefix")?,
+            hash: value.try_get("hash")?,
+            role: value
+                .try_get::<String, _>("role")?
+                .parse()
+                .map_err(|e: String| sqlx::Error::Decode(e.into()))?,
+            created_at: value.try_get("created_at")?,
+            revoked_at: value.try_get("revoked_at")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_keys_are_stored_hashed() {
+        let created = ApiKey::generate(NewApiKey {
+            name: " mobile ".to_owned(),
+            role: Role::Employee,
+        });
+
+        assert!(created.key.starts_with(&created.api_key.prefix));
+        assert_eq!(created.key.len(), KEY_PREFIX.len() + 64);
+        assert_eq!(created.api_key.name, "mobile");
+        assert_eq!(created.api_key.hash, hash(&created.key));
+        assert_ne!(created.api_key.hash, created.key);
+    }
+
+    #[test]
+    fn hash_is_hex_sha256() {
+        assert_eq!(
+            hash("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::error::Error as StdError;
+
+use jsonwebtoken::{
+    decode, decode_header,
+    errors::{Error, ErrorKind},
+    jwk::JwkSet,
+    Algorithm, DecodingKey, Validation,
+};
+use serde::Deserialize;
+
+use super::Role;
+use crate::configuration::AuthConfig;
+
+/// The claims that are needed from a token, the rest are checked by the validation.
+#[derive(Debug, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    /// Tokens without one are for employees.
+    #[serde(default)]
+    pub role: Role,
+    /// Ties the ID tokens of a login to it.
+    pub nonce: Option<String>,
+}
+
+/// Checks the signature, expiry, issuer and audience of bearer tokens against the keys of a
+/// JWKS.
+#[derive(Clone)]
+pub struct JwtVerifier {
+    keys: Vec<Key>,
+    issuer: String,
+    audience: String,
+}
+
+#[derive(Clone)]
+struct Key {
+    id: Option<String>,
+    algorithm: Option<Algorithm>,
+    key: DecodingKey,
+}
+
+impl JwtVerifier {
+    pub fn new(jwks: &JwkSet, issuer: &str, audience: &str) -> Result<Self, Error> {
+        let keys = jwks
+            .keys
+            .iter()
+            .map(|jwk| {
+                Ok(Key {
+                    id: jwk.common.key_id.clone(),
+                    algorithm: jwk.common.algorithm,
+                    key: DecodingKey::from_jwk(jwk)?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(JwtVerifier {
+            keys,
+            issuer: issuer.to_owned(),
+            audience: audience.to_owned(),
+        })
+    }
+
+    /// Loads the JWKS from the file or URL in the configuration, `None` if there is none and
+    /// bearer tokens are not accepted.
+    pub async fn load(
+        config: &AuthConfig,
+    ) -> Result<Option<Self>, Box<dyn StdError + Send + Sync>> {
+        let location = match &config.jwks {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+        if config.issuer.is_empty() || config.audience.is_empty() {
+            return Err("auth.issuer and auth.audience are required with auth.jwks".into());
+        }
+        let jwks: JwkSet = if location.starts_with("http://") || location.starts_with("https://") {
+            reqwest::get(location)
+                .await?
+                .error_for_status()?
+                .json()
+                .await?
+        } else {
+            serde_json::from_slice(&std::fs::read(location)?)?
+        };
+        Ok(Some(JwtVerifier::new(
+            &jwks,
+            &config.issuer,
+            &config.audience,
+        )?))
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Claims, Error> {
+        let header = decode_header(token)?;
+        let key = match &header.kid {
+            Some(kid) => self.keys.iter().find(|key| key.id.as_ref() == Some(kid)),
+            None if self.keys.len() == 1 => self.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| Error::from(ErrorKind::InvalidSignature))?;
+        if key
+            .algorithm
+            .is_some_and(|algorithm| algorithm != header.alg)
+        {
+            return Err(ErrorKind::InvalidAlgorithm.into());
+        }
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        Ok(decode::<Claims>(token, &key.key, &validation)?.claims)
+    }
+}
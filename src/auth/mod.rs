@@ -0,0 +1,288 @@
+use std::fmt;
+use std::future::{ready, Ready};
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use actix_web::{
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    web::Data,
+    FromRequest, HttpRequest,
+};
+use futures::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::api_keys::hash;
+use crate::db::{ApiKeyRepo, Database};
+use crate::error::Error;
+use crate::sessions;
+
+mod jwt;
+mod oidc;
+
+pub use jwt::JwtVerifier;
+pub use oidc::OidcClient;
+
+/// The scheme of the `Authorization` header that API keys are sent with.
+pub const API_KEY_SCHEME: &str = "ApiKey";
+/// The scheme of the `Authorization` header that JWTs are sent with.
+pub const BEARER_SCHEME: &str = "Bearer";
+
+/// What an identity is allowed to do. Employees work with their own hours, managers can also see
+/// the hours of the employees they manage and admins can do everything.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    Employee,
+    Manager,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Employee => "employee",
+            Role::Manager => "manager",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "employee" => Ok(Role::Employee),
+            "manager" => Ok(Role::Manager),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("unknown role {}", other)),
+        }
+    }
+}
+
+/// Who a request has been authenticated as.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity {
+    /// The name of the API key or the `sub` claim of the token the user logged in with. For
+    /// employees and managers it is their username.
+    pub subject: String,
+    pub role: Role,
+}
+
+impl Identity {
+    pub fn require_admin(&self) -> Result<(), Error> {
+        if self.role == Role::Admin {
+            Ok(())
+        } else {
+            Err(Error::Forbidden("The admin role is required".to_owned()))
+        }
+    }
+}
+
+impl FromRequest for Identity {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    /// Only available behind [`Authentication`].
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<Identity>()
+                .cloned()
+                .ok_or_else(unauthorized),
+        )
+    }
+}
+
+/// Rejects requests without a valid API key or bearer token in the `Authorization` header, or
+/// without one a session cookie of a user logged in to the web UI. Keys and sessions are looked
+/// up in the database `D` of the app, tokens are checked by its [`JwtVerifier`].
+pub struct Authentication<D> {
+    database: PhantomData<D>,
+}
+
+impl<D> Authentication<D> {
+    pub fn new() -> Self {
+        Authentication {
+            database: PhantomData,
+        }
+    }
+}
+
+impl<S, B, D> Transform<S, ServiceRequest> for Authentication<D>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+    D: Database,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = AuthenticationMiddleware<S, D>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthenticationMiddleware {
+            service: Rc::new(service),
+            database: PhantomData,
+        }))
+    }
+}
+
+pub struct AuthenticationMiddleware<S, D> {
+    service: Rc<S>,
+    database: PhantomData<D>,
+}
+
+impl<S, B, D> Service<ServiceRequest> for AuthenticationMiddleware<S, D>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+    D: Database,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            let (req, payload) = req.into_parts();
+            let identity = authenticate::<D>(&req).await?;
+            req.extensions_mut().insert(identity);
+            service.call(ServiceRequest::from_parts(req, payload)).await
+        })
+    }
+}
+
+async fn authenticate<D: Database>(req: &HttpRequest) -> Result<Identity, Error> {
+    match credentials(req) {
+        Some((scheme, key)) if scheme.eq_ignore_ascii_case(API_KEY_SCHEME) => {
+            authenticate_api_key::<D>(req, key).await
+        }
+        Some((scheme, token)) if scheme.eq_ignore_ascii_case(BEARER_SCHEME) => {
+            authenticate_token(req, token)
+        }
+        Some(_) => Err(unauthorized()),
+        None => authenticate_session::<D>(req).await,
+    }
+}
+
+async fn authenticate_api_key<D: Database>(
+    req: &HttpRequest,
+    key: &str,
+) -> Result<Identity, Error> {
+    let db = req
+        .app_data::<Data<D>>()
+        .expect("The database is registered as app data");
+    let mut connection = db.connection().await?;
+    match connection.api_key_by_hash(&hash(key)).await? {
+        Some(api_key) => Ok(Identity {
+            subject: api_key.name,
+            role: api_key.role,
+        }),
+        None => Err(unauthorized()),
+    }
+}
+
+fn authenticate_token(req: &HttpRequest, token: &str) -> Result<Identity, Error> {
+    let verifier = req
+        .app_data::<Data<Option<JwtVerifier>>>()
+        .and_then(|verifier| verifier.as_ref().as_ref())
+        .ok_or_else(unauthorized)?;
+    match verifier.verify(token) {
+        Ok(claims) => Ok(Identity {
+            subject: claims.sub,
+            role: claims.role,
+        }),
+        Err(e) => {
+            log::debug!("Rejected a bearer token: {}", e);
+            Err(unauthorized())
+        }
+    }
+}
+
+async fn authenticate_session<D: Database>(req: &HttpRequest) -> Result<Identity, Error> {
+    let db = req
+        .app_data::<Data<D>>()
+        .expect("The database is registered as app data");
+    match sessions::find_session(req, db.get_ref()).await? {
+        Some(session) => Ok(session.identity()),
+        None => Err(unauthorized()),
+    }
+}
+
+/// The scheme and the credentials in the `Authorization` header.
+fn credentials(req: &HttpRequest) -> Option<(&str, &str)> {
+    let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, credentials) = value.trim().split_once(' ')?;
+    Some((scheme, credentials.trim()))
+}
+
+fn unauthorized() -> Error {
+    Error::Unauthorized("A valid API key or bearer token is required".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+    use super::*;
+    use crate::api_keys::{ApiKey, NewApiKey};
+    use crate::db::MemoryDatabase;
+
+    async fn whoami(identity: Identity) -> HttpResponse {
+        HttpResponse::Ok().body(identity.subject)
+    }
+
+    #[actix_rt::test]
+    async fn requests_need_a_valid_key() {
+        let mut db = MemoryDatabase::default();
+        let created = ApiKey::generate(NewApiKey {
+            name: "mobile".to_owned(),
+            role: Role::Employee,
+        });
+        db.insert_api_key(&created.api_key).await.unwrap();
+        let app = test::init_service(
+            App::new().app_data(Data::new(db)).service(
+                web::scope("")
+                    .wrap(Authentication::<MemoryDatabase>::new())
+                    .route("/", web::get().to(whoami)),
+            ),
+        )
+        .await;
+
+        for (authorization, expected) in [
+            (None, StatusCode::UNAUTHORIZED),
+            (Some("ApiKey wrong".to_owned()), StatusCode::UNAUTHORIZED),
+            (
+                Some(format!("Bearer {}", created.key)),
+                StatusCode::UNAUTHORIZED,
+            ),
+            (Some(format!("ApiKey {}", created.key)), StatusCode::OK),
+            (Some(format!("apikey  {} ", created.key)), StatusCode::OK),
+        ] {
+            let mut req = test::TestRequest::get().uri("/");
+            if let Some(authorization) = authorization {
+                req = req.insert_header((header::AUTHORIZATION, authorization));
+            }
+            let status = match app.call(req.to_request()).await {
+                Ok(response) => response.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+            assert_eq!(status, expected);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::error::Error as StdError;
+
+use jsonwebtoken::jwk::JwkSet;
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::jwt::{Claims, JwtVerifier};
+use crate::configuration::OidcConfig;
+use crate::sessions::LoginAttempt;
+
+/// The parts of the discovery document of the provider that the login needs.
+#[derive(Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Logs users of the web UI in with the authorization code flow of an OpenID Connect provider,
+/// protected with PKCE.
+#[derive(Clone)]
+pub struct OidcClient {
+    authorization_endpoint: Url,
+    token_endpoint: String,
+    client_id: String,
+    client_secret: Option<String>,
+    redirect_url: String,
+    /// Checks the ID tokens, which are issued for the client.
+    verifier: JwtVerifier,
+    http: reqwest::Client,
+    pub session_ttl: chrono::Duration,
+}
+
+impl OidcClient {
+    /// Discovers the endpoints and keys of the provider in the configuration, `None` if there is
+    /// none and logging in is not possible.
+    pub async fn discover(
+        config: &OidcConfig,
+    ) -> Result<Option<Self>, Box<dyn StdError + Send + Sync>> {
+        let issuer = match &config.issuer {
+            Some(issuer) => issuer,
+            None => return Ok(None),
+        };
+        if config.client_id.is_empty() || config.redirect_url.is_empty() {
+            return Err(
+                "oidc.client_id and oidc.redirect_url are required with oidc.issuer".into(),
+            );
+        }
+        let http = reqwest::Client::new();
+        let discovery = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = http
+            .get(&discovery)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if &metadata.issuer != issuer {
+            return Err(format!("{} identifies itself as {}", issuer, metadata.issuer).into());
+        }
+        let jwks: JwkSet = http
+            .get(&metadata.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(Some(OidcClient {
+            authorization_endpoint: Url::parse(&metadata.authorization_endpoint)?,
+            token_endpoint: metadata.token_endpoint,
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.clone(),
+            redirect_url: config.redirect_url.clone(),
+            verifier: JwtVerifier::new(&jwks, &metadata.issuer, &config.client_id)?,
+            http,
+            session_ttl: chrono::Duration::hours(config.session_ttl.into()),
+        }))
+    }
+
+    /// Where the user is sent to log in at the provider.
+    pub fn authorization_url(&self, login: &LoginAttempt) -> String {
+        let mut url = self.authorization_endpoint.clone();
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &self.redirect_url)
+            .append_pair("scope", "openid")
+            .append_pair("state", &login.state)
+            .append_pair("nonce", &login.nonce)
+            .append_pair("code_challenge", &code_challenge(&login.code_verifier))
+            .append_pair("code_challenge_method", "S256");
+        url.to_string()
+    }
+
+    /// Redeems the authorization code the user came back with for an ID token and returns its
+    /// claims once the token has been verified.
+    pub async fn exchange(
+        &self,
+        code: &str,
+        login: &LoginAttempt,
+    ) -> Result<Claims, Box<dyn StdError + Send + Sync>> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.redirect_url),
+            ("client_id", &self.client_id),
+            ("code_verifier", &login.code_verifier),
+        ];
+        if let Some(secret) = &self.client_secret {
+            form.push(("client_secret", secret));
+        }
+        let response: TokenResponse = self
+            .http
+            .post(&self.token_endpoint)
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let claims = self.verifier.verify(&response.id_token)?;
+        if claims.nonce.as_deref() != Some(login.nonce.as_str()) {
+            return Err("the nonce of the ID token is not the one of the login".into());
+        }
+        Ok(claims)
+    }
+}
+
+/// The S256 challenge of a PKCE code verifier.
+fn code_challenge(verifier: &str) -> String {
+    base64::encode_config(Sha256::digest(verifier.as_bytes()), base64::URL_SAFE_NO_PAD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_challenge_of_rfc_7636() {
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+}
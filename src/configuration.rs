@@ -4,11 +4,21 @@ use regex::Regex;
 
 use serde::Deserialize;
 
+use crate::{JwtVerifier, OidcClient};
+
 #[derive(Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
     pub database: DatabaseConfig,
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub hours: HoursConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub oidc: OidcConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
 }
 
 #[derive(Deserialize)]
@@ -30,6 +40,189 @@ pub struct LoggingConfig {
     pub level: String,
 }
 
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct HoursConfig {
+    /// Logged time is rounded to the nearest multiple of this many minutes.
+    pub rounding: u16,
+    pub limit: LimitConfig,
+    pub trash: TrashConfig,
+    pub idempotency: IdempotencyConfig,
+}
+
+impl Default for HoursConfig {
+    fn default() -> Self {
+        HoursConfig {
+            rounding: 1,
+            limit: LimitConfig::default(),
+            trash: TrashConfig::default(),
+            idempotency: IdempotencyConfig::default(),
+        }
+    }
+}
+
+/// The most hours an employee can log in total, across all of their entries.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct LimitConfig {
+    pub day: f64,
+    /// Weeks start on Monday.
+    pub week: f64,
+}
+
+impl Default for LimitConfig {
+    fn default() -> Self {
+        LimitConfig {
+            day: 24.0,
+            week: 7.0 * 24.0,
+        }
+    }
+}
+
+/// Deleted hours are kept in the trash, where they can be restored from, for `retention` days.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct TrashConfig {
+    pub retention: u32,
+    /// Minutes between the purges of expired entries, at most a week.
+    pub interval: u64,
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        TrashConfig {
+            retention: 30,
+            interval: 60,
+        }
+    }
+}
+
+/// Retried requests with the same `Idempotency-Key` are answered with the original response for
+/// `window` hours after the key was first used.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct IdempotencyConfig {
+    pub window: u32,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        IdempotencyConfig { window: 24 }
+    }
+}
+
+/// Bearer tokens are accepted when `jwks` is set, to a file or an http(s) URL of the key set of
+/// the identity provider. Tokens must have been issued by `issuer` for `audience`.
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AuthConfig {
+    pub jwks: Option<String>,
+    pub issuer: String,
+    pub audience: String,
+}
+
+/// Users can log in to the web UI when `issuer` is set, to the URL of an OpenID Connect provider
+/// that `client_id` is registered with. The provider sends them back to `redirect_url`, the
+/// `/auth/callback` of the app, and their session lasts for `session_ttl` hours.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct OidcConfig {
+    pub issuer: Option<String>,
+    pub client_id: String,
+    /// Only for providers that do not take public clients.
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub session_ttl: u32,
+}
+
+impl Default for OidcConfig {
+    fn default() -> Self {
+        OidcConfig {
+            issuer: None,
+            client_id: String::new(),
+            client_secret: None,
+            redirect_url: String::new(),
+            session_ttl: 8,
+        }
+    }
+}
+
+/// Requests to `/api` are limited per client, which is the subject it is authenticated as. Reads,
+/// writes and imports of hours each have their own limit. Requests to `/auth` have one of their
+/// own, per IP address, and so do requests to `/api` that fail authentication, separately.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Take the IP address from the `Forwarded` or `X-Forwarded-For` header. Only for running
+    /// behind a proxy that sets them, clients could pick their own address otherwise.
+    pub trust_proxy: bool,
+    pub read: BucketConfig,
+    pub write: BucketConfig,
+    pub import: BucketConfig,
+    pub auth: BucketConfig,
+    pub failed_auth: BucketConfig,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            trust_proxy: false,
+            read: BucketConfig {
+                burst: 120,
+                per_minute: 600,
+            },
+            write: BucketConfig {
+                burst: 60,
+                per_minute: 120,
+            },
+            import: BucketConfig {
+                burst: 5,
+                per_minute: 10,
+            },
+            auth: BucketConfig {
+                burst: 10,
+                per_minute: 20,
+            },
+            failed_auth: BucketConfig {
+                burst: 10,
+                per_minute: 10,
+            },
+        }
+    }
+}
+
+/// A client can make `burst` requests at once, after which it gets `per_minute` more. A
+/// `per_minute` of 0 turns the limit off.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BucketConfig {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+/// The part of the configuration that the request handlers need.
+#[derive(Clone, Default)]
+pub struct Settings {
+    pub hours: HoursConfig,
+    pub rate_limit: RateLimitConfig,
+    /// Loaded from the [`AuthConfig`] with [`JwtVerifier::load`].
+    pub jwt: Option<JwtVerifier>,
+    /// Discovered from the [`OidcConfig`] with [`OidcClient::discover`].
+    pub oidc: Option<OidcClient>,
+}
+
+impl Config {
+    /// Bearer tokens are not accepted until the `jwt` verifier is loaded, and logging in is not
+    /// possible until the `oidc` provider is discovered.
+    pub fn settings(&self) -> Settings {
+        Settings {
+            hours: self.hours.clone(),
+            rate_limit: self.rate_limit.clone(),
+            jwt: None,
+            oidc: None,
+        }
+    }
+}
+
 impl DatabaseConfig {
     pub fn connection_string(&self) -> String {
         format!(
@@ -1,94 +1,712 @@
 use std::convert::TryFrom;
+use std::ops::DerefMut;
 
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, SubsecRound, Utc};
+use futures::channel::mpsc;
+use futures::stream::BoxStream;
+use futures::{SinkExt, StreamExt};
 use sqlx::pool::PoolConnection;
-use sqlx::{PgConnection, Postgres};
+use sqlx::types::Json;
+use sqlx::{Connection, PgConnection, PgPool, Postgres};
 use uuid::Uuid;
 
-use crate::hours::{Hours, NewHours};
+use crate::error::Error;
+use crate::hours::access::Scope;
+use crate::hours::audit::AuditRecord;
+use crate::hours::idempotency::IdempotencyKey;
+use crate::hours::{Hours, HoursQuery, NewHours, Page};
+use crate::reports::{SummaryQuery, SummaryRow};
 
-pub struct HoursRepository<'a>(&'a mut PgConnection);
+mod api_keys;
+mod employees;
+pub mod memory;
+mod periods;
+mod projects;
+mod sessions;
+mod timers;
+mod timesheets;
 
+pub use api_keys::ApiKeyRepo;
+pub use employees::EmployeeRepo;
+pub use memory::MemoryDatabase;
+pub use periods::PeriodRepo;
+pub use projects::ProjectRepo;
+pub use sessions::SessionRepo;
+pub use timers::TimerRepo;
+pub use timesheets::TimesheetRepo;
+
+pub(crate) const HOURS_CHANGED: &str = "The hours entry has been changed since it was read";
+pub(crate) const IDEMPOTENCY_KEY_USED: &str = "The Idempotency-Key has already been used";
+
+/// Number of exported entries that are fetched ahead of the client.
+const EXPORT_BUFFER: usize = 100;
+
+/// Shared `WHERE` clause of the hours listing. Every filter is optional, a `NULL` parameter
+/// matches all rows. `$6` are the employees in the scope of the caller. Entries in the trash are
+/// never listed.
+const HOURS_FILTER: &str = "WHERE deleted_at IS NULL
+    AND ($1::VARCHAR IS NULL OR employee = $1)
+    AND ($2::VARCHAR IS NULL OR project = $2)
+    AND ($3::VARCHAR IS NULL OR story_id = $3)
+    AND ($4::DATE IS NULL OR date >= $4)
+    AND ($5::DATE IS NULL OR date <= $5)
+    AND ($6::VARCHAR[] IS NULL OR employee = ANY($6))";
+
+/// Every repository, the connections of a [`Database`] implement all of them.
+pub trait Repositories:
+    HoursRepo
+    + EmployeeRepo
+    + ProjectRepo
+    + TimerRepo
+    + TimesheetRepo
+    + PeriodRepo
+    + ReportRepo
+    + ApiKeyRepo
+    + SessionRepo
+{
+}
+
+impl<C> Repositories for C where
+    C: HoursRepo
+        + EmployeeRepo
+        + ProjectRepo
+        + TimerRepo
+        + TimesheetRepo
+        + PeriodRepo
+        + ReportRepo
+        + ApiKeyRepo
+        + SessionRepo
+{
+}
+
+/// A source of repository connections that the server can be run against.
 #[async_trait]
-pub trait HoursRepo {
-    async fn by_id(&mut self, id: Uuid) -> Option<Hours>;
-    async fn list(&mut self) -> Vec<Hours>;
-    async fn insert(&mut self, h: NewHours) -> Hours;
-    async fn delete(&mut self, id: Uuid) -> bool;
+pub trait Database: Clone + Send + Sync + 'static {
+    type Connection: Repositories + 'static;
+    type Transaction: Repositories + Transaction + 'static;
+
+    async fn connection(&self) -> Result<Self::Connection, Error>;
+    /// A connection of its own whose changes are only kept once it is committed.
+    async fn begin(&self) -> Result<Self::Transaction, Error>;
 }
 
+/// Changes made through the repositories that are kept all at once, when the transaction is
+/// committed, or not at all.
 #[async_trait]
-impl HoursRepo for PoolConnection<Postgres> {
-    async fn by_id(&mut self, id: Uuid) -> Option<Hours> {
-        sqlx::query("SELECT * FROM hours WHERE id = $1")
-            .bind(id)
-            .map(|row| Hours::try_from(row).unwrap())
-            .fetch_optional(self)
-            .await
-            .unwrap()
+pub trait Transaction: Send {
+    /// Waits until no other transaction holds the lock of any of the `employees` and holds them
+    /// until this one ends. Changes to hours are checked against the hours the employee already
+    /// has, taking the lock first makes concurrent changes take turns.
+    async fn lock_employees(&mut self, employees: &[&str]) -> Result<(), Error>;
+    async fn commit(self) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl Database for PgPool {
+    type Connection = PoolConnection<Postgres>;
+    type Transaction = sqlx::Transaction<'static, Postgres>;
+
+    async fn connection(&self) -> Result<Self::Connection, Error> {
+        Ok(self.acquire().await?)
     }
 
-    async fn list(&mut self) -> Vec<Hours> {
-        sqlx::query("SELECT * FROM hours")
-            .map(|row| Hours::try_from(row).unwrap())
-            .fetch_all(self)
-            .await
-            .unwrap()
+    async fn begin(&self) -> Result<Self::Transaction, Error> {
+        Ok(PgPool::begin(self).await?)
     }
+}
 
-    async fn insert(&mut self, h: NewHours) -> Hours {
-        let hours = Hours::new(h);
-        let sql = "INSERT INTO hours (id, employee, date, project, story_id, description, hours)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)";
+#[async_trait]
+impl Transaction for sqlx::Transaction<'static, Postgres> {
+    async fn lock_employees(&mut self, employees: &[&str]) -> Result<(), Error> {
+        // Always taken in the same order, so that two transactions can not wait for each other.
+        let sql = "SELECT pg_advisory_xact_lock(key)
+            FROM (SELECT DISTINCT hashtext(employee) AS key FROM UNNEST($1::VARCHAR[]) employee
+                ORDER BY key) keys";
         sqlx::query(sql)
-            .bind(hours.id)
-            .bind(hours.employee.clone())
-            .bind(hours.date)
-            .bind(hours.project.clone())
-            .bind(hours.story_id.clone())
-            .bind(hours.description.clone())
-            .bind(hours.hours)
-            .execute(self)
-            .await
-            .unwrap();
-        hours
+            .bind(employees)
+            .execute(&mut **self)
+            .await?;
+        Ok(())
+    }
+
+    async fn commit(self) -> Result<(), Error> {
+        Ok(sqlx::Transaction::commit(self).await?)
+    }
+}
+
+/// A Postgres connection that the repositories can work with, either on its own or in a
+/// transaction.
+pub trait PgConnectionLike: DerefMut<Target = PgConnection> + Send + 'static {}
+
+impl<C> PgConnectionLike for C where C: DerefMut<Target = PgConnection> + Send + 'static {}
+
+/// Entries of employees outside of the `scope` of the caller are left out of listings and are
+/// forbidden otherwise.
+#[async_trait]
+pub trait HoursRepo: Send {
+    /// Entries in the trash are left out of everything but the methods dealing with the trash.
+    async fn by_id(&mut self, id: Uuid, scope: &Scope) -> Result<Option<Hours>, Error>;
+    async fn list(&mut self, query: &HoursQuery, scope: &Scope) -> Result<Page<Hours>, Error>;
+    /// Streams the entries `list` would return, without counting them. They are read with a
+    /// single query, so the stream is a consistent snapshot however long it takes to consume.
+    fn export(self, query: HoursQuery, scope: Scope) -> BoxStream<'static, Result<Hours, Error>>
+    where
+        Self: Sized;
+    /// Every change is recorded in the history of the entry along with `actor`, who made it.
+    async fn insert(&mut self, h: NewHours, actor: &str) -> Result<Hours, Error>;
+    /// Inserts the entry and stores it under the idempotency `key` of `subject`, unless they have
+    /// used the key since `since`.
+    async fn insert_idempotent(
+        &mut self,
+        h: NewHours,
+        subject: &str,
+        key: &str,
+        since: DateTime<Utc>,
+        actor: &str,
+    ) -> Result<Hours, Error>;
+    /// The entry logged by `subject` with the idempotency `key` since `since`.
+    async fn idempotency_key(
+        &mut self,
+        subject: &str,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<IdempotencyKey>, Error>;
+    /// Removes the idempotency keys used before `used_before`, returning how many there were.
+    async fn purge_idempotency_keys(&mut self, used_before: DateTime<Utc>) -> Result<u64, Error>;
+    /// Inserts all of the entries or, if any of them fails, none of them.
+    async fn insert_all(&mut self, hs: Vec<NewHours>, actor: &str) -> Result<Vec<Hours>, Error>;
+    /// Changes are only made to the entry while it is at `version`, every change increments it.
+    /// The employee of the entry has to be in `scope` both before and after the change.
+    async fn update(
+        &mut self,
+        id: Uuid,
+        h: NewHours,
+        version: i32,
+        scope: &Scope,
+        actor: &str,
+    ) -> Result<Option<Hours>, Error>;
+    /// Moves the entry to the trash.
+    async fn delete(
+        &mut self,
+        id: Uuid,
+        version: i32,
+        scope: &Scope,
+        actor: &str,
+    ) -> Result<bool, Error>;
+    /// The entries in the trash, the most recently deleted first.
+    async fn trash(&mut self, scope: &Scope) -> Result<Vec<Hours>, Error>;
+    async fn trash_by_id(&mut self, id: Uuid, scope: &Scope) -> Result<Option<Hours>, Error>;
+    /// Takes the entry out of the trash, returning it if it was there.
+    async fn restore(
+        &mut self,
+        id: Uuid,
+        version: i32,
+        scope: &Scope,
+        actor: &str,
+    ) -> Result<Option<Hours>, Error>;
+    /// Removes the entries deleted before `deleted_before` for good, returning how many there were.
+    async fn purge_trash(
+        &mut self,
+        deleted_before: DateTime<Utc>,
+        actor: &str,
+    ) -> Result<u64, Error>;
+    /// The changes made to the entry, oldest first. Entries that never existed have none.
+    async fn history(&mut self, id: Uuid, scope: &Scope) -> Result<Vec<AuditRecord>, Error>;
+    /// Finds the entries of the same employee on the same date whose start and end time overlap
+    /// those of `h`, leaving out the entry `exclude`. Entries without times never overlap.
+    async fn overlapping(
+        &mut self,
+        h: &NewHours,
+        exclude: Option<Uuid>,
+    ) -> Result<Vec<Hours>, Error>;
+    /// Sums up the minutes an employee has logged from `from` to `to`, inclusive, leaving out the
+    /// entry `exclude`.
+    async fn logged_minutes(
+        &mut self,
+        employee: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        exclude: Option<Uuid>,
+    ) -> Result<i64, Error>;
+}
+
+#[async_trait]
+impl<C: PgConnectionLike> HoursRepo for C {
+    async fn by_id(&mut self, id: Uuid, scope: &Scope) -> Result<Option<Hours>, Error> {
+        scope.check_entry(
+            sqlx::query("SELECT * FROM hours WHERE id = $1 AND deleted_at IS NULL")
+                .bind(id)
+                .try_map(Hours::try_from)
+                .fetch_optional(&mut **self)
+                .await?,
+        )
     }
 
-    async fn delete(&mut self, id: Uuid) -> bool {
-        sqlx::query("DELETE FROM hours WHERE id = $1 RETURNING 1")
+    async fn list(&mut self, query: &HoursQuery, scope: &Scope) -> Result<Page<Hours>, Error> {
+        let total = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM hours {}", HOURS_FILTER))
+            .bind(query.employee.clone())
+            .bind(query.project.clone())
+            .bind(query.story_id.clone())
+            .bind(query.from)
+            .bind(query.to)
+            .bind(scope.employees())
+            .fetch_one(&mut **self)
+            .await?;
+        let items = sqlx::query(&list_sql(query))
+            .bind(query.employee.clone())
+            .bind(query.project.clone())
+            .bind(query.story_id.clone())
+            .bind(query.from)
+            .bind(query.to)
+            .bind(scope.employees())
+            .bind(query.limit.map(i64::from))
+            .bind(i64::from(query.offset))
+            .try_map(Hours::try_from)
+            .fetch_all(&mut **self)
+            .await?;
+        Ok(Page { items, total })
+    }
+
+    /// The rows are fetched by a task of their own, which stops when the stream is dropped.
+    fn export(
+        mut self,
+        query: HoursQuery,
+        scope: Scope,
+    ) -> BoxStream<'static, Result<Hours, Error>> {
+        let (mut sender, receiver) = mpsc::channel(EXPORT_BUFFER);
+        actix_web::rt::spawn(async move {
+            let sql = list_sql(&query);
+            let mut rows = sqlx::query(&sql)
+                .bind(query.employee.clone())
+                .bind(query.project.clone())
+                .bind(query.story_id.clone())
+                .bind(query.from)
+                .bind(query.to)
+                .bind(scope.employees())
+                .bind(query.limit.map(i64::from))
+                .bind(i64::from(query.offset))
+                .try_map(Hours::try_from)
+                .fetch(&mut *self);
+            while let Some(row) = rows.next().await {
+                let failed = row.is_err();
+                if sender.send(row.map_err(Error::from)).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        receiver.boxed()
+    }
+
+    async fn insert(&mut self, h: NewHours, actor: &str) -> Result<Hours, Error> {
+        let mut tx = self.begin().await?;
+        let hours = insert_hours(&mut tx, h, actor).await?;
+        insert_audit(&mut tx, &AuditRecord::inserted(actor, &hours)).await?;
+        tx.commit().await?;
+        Ok(hours)
+    }
+
+    async fn insert_idempotent(
+        &mut self,
+        h: NewHours,
+        subject: &str,
+        key: &str,
+        since: DateTime<Utc>,
+        actor: &str,
+    ) -> Result<Hours, Error> {
+        let mut tx = self.begin().await?;
+        let hours = insert_hours(&mut tx, h, actor).await?;
+        insert_audit(&mut tx, &AuditRecord::inserted(actor, &hours)).await?;
+        let stored = IdempotencyKey::new(subject, key, &hours);
+        // An expired key is taken over, a concurrent request with the same key waits for this
+        // one to commit and then finds the key used.
+        let sql = "INSERT INTO idempotency_keys (subject, key, hours_id, response, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (subject, key) DO UPDATE
+            SET hours_id = EXCLUDED.hours_id, response = EXCLUDED.response,
+                created_at = EXCLUDED.created_at
+            WHERE idempotency_keys.created_at < $6";
+        let result = sqlx::query(sql)
+            .bind(stored.subject)
+            .bind(stored.key)
+            .bind(stored.hours_id)
+            .bind(Json(&stored.response))
+            .bind(stored.created_at)
+            .bind(since)
+            .execute(&mut tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::Conflict(IDEMPOTENCY_KEY_USED.to_owned()));
+        }
+        tx.commit().await?;
+        Ok(hours)
+    }
+
+    async fn idempotency_key(
+        &mut self,
+        subject: &str,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<IdempotencyKey>, Error> {
+        Ok(sqlx::query(
+            "SELECT * FROM idempotency_keys WHERE subject = $1 AND key = $2 AND created_at >= $3",
+        )
+        .bind(subject)
+        .bind(key)
+        .bind(since)
+        .try_map(IdempotencyKey::try_from)
+        .fetch_optional(&mut **self)
+        .await?)
+    }
+
+    async fn purge_idempotency_keys(&mut self, used_before: DateTime<Utc>) -> Result<u64, Error> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < $1")
+            .bind(used_before)
+            .execute(&mut **self)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn insert_all(&mut self, hs: Vec<NewHours>, actor: &str) -> Result<Vec<Hours>, Error> {
+        let mut tx = self.begin().await?;
+        let mut inserted = Vec::with_capacity(hs.len());
+        for h in hs {
+            let hours = insert_hours(&mut tx, h, actor).await?;
+            insert_audit(&mut tx, &AuditRecord::inserted(actor, &hours)).await?;
+            inserted.push(hours);
+        }
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    async fn update(
+        &mut self,
+        id: Uuid,
+        h: NewHours,
+        version: i32,
+        scope: &Scope,
+        actor: &str,
+    ) -> Result<Option<Hours>, Error> {
+        let mut tx = self.begin().await?;
+        let before = match scope.check_entry(live_for_update(&mut tx, id).await?)? {
+            Some(hours) => check_version(hours, version)?,
+            None => return Ok(None),
+        };
+        scope.check(&h.employee)?;
+        let sql = "UPDATE hours
+            SET employee = $2, date = $3, project = $4, story_id = $5, description = $6,
+                minutes = $7, start_time = $8, end_time = $9, version = version + 1
+            WHERE id = $1
+            RETURNING *";
+        let after = sqlx::query(sql)
             .bind(id)
-            .fetch_optional(self)
-            .await
-            .unwrap()
-            .is_some()
+            .bind(h.employee)
+            .bind(h.date)
+            .bind(h.project)
+            .bind(h.story_id)
+            .bind(h.description)
+            .bind(h.minutes)
+            .bind(h.start_time)
+            .bind(h.end_time)
+            .try_map(Hours::try_from)
+            .fetch_one(&mut tx)
+            .await?;
+        insert_audit(&mut tx, &AuditRecord::updated(actor, &before, &after)).await?;
+        tx.commit().await?;
+        Ok(Some(after))
+    }
+
+    async fn delete(
+        &mut self,
+        id: Uuid,
+        version: i32,
+        scope: &Scope,
+        actor: &str,
+    ) -> Result<bool, Error> {
+        let mut tx = self.begin().await?;
+        let before = match scope.check_entry(live_for_update(&mut tx, id).await?)? {
+            Some(hours) => check_version(hours, version)?,
+            None => return Ok(false),
+        };
+        sqlx::query("UPDATE hours SET deleted_at = $2, version = version + 1 WHERE id = $1")
+            .bind(id)
+            .bind(Utc::now().trunc_subsecs(6))
+            .execute(&mut tx)
+            .await?;
+        insert_audit(&mut tx, &AuditRecord::deleted(actor, &before)).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn trash(&mut self, scope: &Scope) -> Result<Vec<Hours>, Error> {
+        Ok(sqlx::query(
+            "SELECT * FROM hours
+            WHERE deleted_at IS NOT NULL AND ($1::VARCHAR[] IS NULL OR employee = ANY($1))
+            ORDER BY deleted_at DESC, id",
+        )
+        .bind(scope.employees())
+        .try_map(Hours::try_from)
+        .fetch_all(&mut **self)
+        .await?)
+    }
+
+    async fn trash_by_id(&mut self, id: Uuid, scope: &Scope) -> Result<Option<Hours>, Error> {
+        scope.check_entry(
+            sqlx::query("SELECT * FROM hours WHERE id = $1 AND deleted_at IS NOT NULL")
+                .bind(id)
+                .try_map(Hours::try_from)
+                .fetch_optional(&mut **self)
+                .await?,
+        )
+    }
+
+    async fn restore(
+        &mut self,
+        id: Uuid,
+        version: i32,
+        scope: &Scope,
+        actor: &str,
+    ) -> Result<Option<Hours>, Error> {
+        let mut tx = self.begin().await?;
+        let trashed =
+            sqlx::query("SELECT * FROM hours WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE")
+                .bind(id)
+                .try_map(Hours::try_from)
+                .fetch_optional(&mut tx)
+                .await?;
+        match scope.check_entry(trashed)? {
+            Some(hours) => check_version(hours, version)?,
+            None => return Ok(None),
+        };
+        let restored = sqlx::query(
+            "UPDATE hours SET deleted_at = NULL, version = version + 1
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING *",
+        )
+        .bind(id)
+        .try_map(Hours::try_from)
+        .fetch_optional(&mut tx)
+        .await?;
+        if let Some(hours) = &restored {
+            insert_audit(&mut tx, &AuditRecord::restored(actor, hours)).await?;
+        }
+        tx.commit().await?;
+        Ok(restored)
+    }
+
+    async fn purge_trash(
+        &mut self,
+        deleted_before: DateTime<Utc>,
+        actor: &str,
+    ) -> Result<u64, Error> {
+        let mut tx = self.begin().await?;
+        let purged = sqlx::query("DELETE FROM hours WHERE deleted_at < $1 RETURNING *")
+            .bind(deleted_before)
+            .try_map(Hours::try_from)
+            .fetch_all(&mut tx)
+            .await?;
+        for hours in &purged {
+            insert_audit(&mut tx, &AuditRecord::purged(actor, hours)).await?;
+        }
+        tx.commit().await?;
+        Ok(purged.len() as u64)
+    }
+
+    async fn history(&mut self, id: Uuid, scope: &Scope) -> Result<Vec<AuditRecord>, Error> {
+        scope.check_history(
+            sqlx::query("SELECT * FROM hours_audit WHERE hours_id = $1 ORDER BY id")
+                .bind(id)
+                .try_map(AuditRecord::try_from)
+                .fetch_all(&mut **self)
+                .await?,
+        )
+    }
+
+    async fn overlapping(
+        &mut self,
+        h: &NewHours,
+        exclude: Option<Uuid>,
+    ) -> Result<Vec<Hours>, Error> {
+        let (start, end) = match h.span() {
+            Some(span) => span,
+            None => return Ok(vec![]),
+        };
+        let sql = "SELECT * FROM hours
+            WHERE employee = $1 AND date = $2 AND start_time < $4 AND end_time > $3
+                AND ($5::UUID IS NULL OR id <> $5) AND deleted_at IS NULL
+            ORDER BY start_time, id";
+        Ok(sqlx::query(sql)
+            .bind(&h.employee)
+            .bind(h.date)
+            .bind(start)
+            .bind(end)
+            .bind(exclude)
+            .try_map(Hours::try_from)
+            .fetch_all(&mut **self)
+            .await?)
+    }
+
+    async fn logged_minutes(
+        &mut self,
+        employee: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        exclude: Option<Uuid>,
+    ) -> Result<i64, Error> {
+        let sql = "SELECT COALESCE(SUM(minutes), 0)::INT8 FROM hours
+            WHERE employee = $1 AND date BETWEEN $2 AND $3
+                AND ($4::UUID IS NULL OR id <> $4) AND deleted_at IS NULL";
+        Ok(sqlx::query_scalar(sql)
+            .bind(employee)
+            .bind(from)
+            .bind(to)
+            .bind(exclude)
+            .fetch_one(&mut **self)
+            .await?)
+    }
+}
+
+/// The page of the hours listing that `query` asks for, `$7` and `$8` are its limit and offset.
+fn list_sql(query: &HoursQuery) -> String {
+    format!(
+        "SELECT * FROM hours {} ORDER BY {} {}, id LIMIT $7 OFFSET $8",
+        HOURS_FILTER,
+        query.sort.column(),
+        query.order.keyword()
+    )
+}
+
+async fn insert_hours(
+    connection: &mut PgConnection,
+    h: NewHours,
+    actor: &str,
+) -> Result<Hours, Error> {
+    let hours = Hours::new(h, actor);
+    let sql = "INSERT INTO hours
+            (id, employee, date, project, story_id, description, minutes, start_time, end_time,
+                logged_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)";
+    sqlx::query(sql)
+        .bind(hours.id)
+        .bind(hours.employee.clone())
+        .bind(hours.date)
+        .bind(hours.project.clone())
+        .bind(hours.story_id.clone())
+        .bind(hours.description.clone())
+        .bind(hours.minutes)
+        .bind(hours.start_time)
+        .bind(hours.end_time)
+        .bind(hours.logged_by.clone())
+        .execute(connection)
+        .await?;
+    Ok(hours)
+}
+
+/// Locks the entry for the rest of the transaction, unless it is in the trash.
+async fn live_for_update(connection: &mut PgConnection, id: Uuid) -> Result<Option<Hours>, Error> {
+    Ok(
+        sqlx::query("SELECT * FROM hours WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+            .bind(id)
+            .try_map(Hours::try_from)
+            .fetch_optional(connection)
+            .await?,
+    )
+}
+
+/// Fails if the entry has been changed since `version`.
+fn check_version(hours: Hours, version: i32) -> Result<Hours, Error> {
+    if hours.version == version {
+        Ok(hours)
+    } else {
+        Err(Error::PreconditionFailed(HOURS_CHANGED.to_owned()))
+    }
+}
+
+async fn insert_audit(connection: &mut PgConnection, record: &AuditRecord) -> Result<(), Error> {
+    let sql = "INSERT INTO hours_audit (hours_id, operation, actor, changed_at, before, after)
+        VALUES ($1, $2, $3, $4, $5, $6)";
+    sqlx::query(sql)
+        .bind(record.hours_id)
+        .bind(record.operation.as_str())
+        .bind(record.actor.clone())
+        .bind(record.changed_at)
+        .bind(record.before.clone().map(Json))
+        .bind(record.after.clone().map(Json))
+        .execute(connection)
+        .await?;
+    Ok(())
+}
+
+#[async_trait]
+pub trait ReportRepo: Send {
+    /// Sums up hours per period, employee, project and story, ordered in that sequence. Only the
+    /// hours of employees in `scope` are counted.
+    async fn summary(
+        &mut self,
+        query: &SummaryQuery,
+        scope: &Scope,
+    ) -> Result<Vec<SummaryRow>, Error>;
+}
+
+#[async_trait]
+impl<C: PgConnectionLike> ReportRepo for C {
+    async fn summary(
+        &mut self,
+        query: &SummaryQuery,
+        scope: &Scope,
+    ) -> Result<Vec<SummaryRow>, Error> {
+        let sql = "SELECT date_trunc($1, date)::DATE AS period, employee, project, story_id,
+                SUM(minutes)::INT8 AS minutes
+            FROM hours
+            WHERE date BETWEEN $2 AND $3 AND deleted_at IS NULL
+                AND ($4::VARCHAR IS NULL OR employee = $4)
+                AND ($5::VARCHAR IS NULL OR project = $5)
+                AND ($6::VARCHAR[] IS NULL OR employee = ANY($6))
+            GROUP BY period, employee, project, story_id
+            ORDER BY period, employee, project, story_id";
+        Ok(sqlx::query(sql)
+            .bind(query.granularity.date_trunc_field())
+            .bind(query.from)
+            .bind(query.to)
+            .bind(query.employee.clone())
+            .bind(query.project.clone())
+            .bind(scope.employees())
+            .try_map(SummaryRow::try_from)
+            .fetch_all(&mut **self)
+            .await?)
     }
 }
 
 #[cfg(test)]
 pub mod tests {
-    use chrono::NaiveDate;
+    use chrono::NaiveTime;
+    use futures::TryStreamExt;
     use uuid::Uuid;
 
     use super::*;
+    use crate::hours::audit::Operation;
+    use crate::hours::model::LoggedTime;
+    use crate::hours::query::{SortField, SortOrder};
     use crate::hours::NewHours;
+    use crate::reports::model::Granularity;
     use crate::test_utils;
 
     #[actix_rt::test]
     async fn by_id_when_db_is_empty() {
-        let mut db = test_utils::internal::get_db_connection().await;
+        let mut db = get_db().await;
 
-        let result = db.by_id(Uuid::new_v4()).await;
+        let result = db.by_id(Uuid::new_v4(), &Scope::All).await.unwrap();
 
         assert!(result.is_none());
     }
 
     #[actix_rt::test]
     async fn by_id_exists() {
-        let mut db = test_utils::internal::get_db_connection().await;
+        let mut db = get_db().await;
 
-        let hours = db.insert(get_hours()).await;
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
 
-        match db.by_id(hours.id).await {
+        match db.by_id(hours.id, &Scope::All).await.unwrap() {
             Some(result) => assert_eq!(result, hours),
             None => panic!("Expected hours to be returned."),
         }
@@ -96,71 +714,704 @@ pub mod tests {
 
     #[actix_rt::test]
     async fn by_id_db_not_empty_invalid_key() {
-        let mut db = test_utils::internal::get_db_connection().await;
+        let mut db = get_db().await;
 
-        db.insert(get_hours()).await;
+        db.insert(get_hours(), "tester").await.unwrap();
 
-        let result = db.by_id(Uuid::new_v4()).await;
+        let result = db.by_id(Uuid::new_v4(), &Scope::All).await.unwrap();
 
         assert!(result.is_none());
     }
 
     #[actix_rt::test]
     async fn delete_db_empty() {
-        let mut db = test_utils::internal::get_db_connection().await;
+        let mut db = get_db().await;
 
-        let result = db.delete(Uuid::new_v4()).await;
+        let result = db
+            .delete(Uuid::new_v4(), 1, &Scope::All, "tester")
+            .await
+            .unwrap();
 
         assert!(!result);
     }
 
     #[actix_rt::test]
     async fn delete_db_not_empty() {
-        let mut db = test_utils::internal::get_db_connection().await;
+        let mut db = get_db().await;
 
-        let hours = db.insert(get_hours()).await;
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
 
-        let result = db.delete(hours.id).await;
+        let result = db.delete(hours.id, 1, &Scope::All, "tester").await.unwrap();
 
         assert!(result);
 
-        assert!(db.by_id(hours.id).await.is_none());
+        assert!(db.by_id(hours.id, &Scope::All).await.unwrap().is_none());
     }
 
     #[actix_rt::test]
     async fn delete_db_not_empty_invalid_key() {
-        let mut db = test_utils::internal::get_db_connection().await;
+        let mut db = get_db().await;
 
-        let hours = db.insert(get_hours()).await;
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
 
-        let result = db.delete(Uuid::new_v4()).await;
+        let result = db
+            .delete(Uuid::new_v4(), 1, &Scope::All, "tester")
+            .await
+            .unwrap();
 
         assert!(!result);
 
-        match db.by_id(hours.id).await {
+        match db.by_id(hours.id, &Scope::All).await.unwrap() {
             Some(stored) => assert_eq!(stored, hours),
             None => panic!("Expected hours to still be stored."),
         }
     }
 
+    #[actix_rt::test]
+    async fn export_streams_the_page_of_the_listing() {
+        let mut db = get_db().await;
+
+        db.insert_all(vec![get_hours(), get_hours(), get_hours()], "tester")
+            .await
+            .unwrap();
+        let query = HoursQuery {
+            limit: Some(2),
+            offset: 1,
+            ..HoursQuery::default()
+        };
+        let listed = db.list(&query, &Scope::All).await.unwrap().items;
+
+        let exported: Vec<Hours> = db.export(query, Scope::All).try_collect().await.unwrap();
+
+        assert_eq!(exported.len(), 2);
+        assert_eq!(exported, listed);
+    }
+
+    #[actix_rt::test]
+    async fn insert_all_inserts_every_entry() {
+        let mut db = get_db().await;
+
+        let mut result = db
+            .insert_all(vec![get_hours(), get_hours()], "tester")
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 2);
+        let mut stored = db
+            .list(&HoursQuery::default(), &Scope::All)
+            .await
+            .unwrap()
+            .items;
+        stored.sort_by_key(|h| h.id);
+        result.sort_by_key(|h| h.id);
+        assert_eq!(stored, result);
+    }
+
+    #[actix_rt::test]
+    async fn overlapping_finds_entries_of_same_employee_and_date() {
+        let mut db = get_db().await;
+
+        let existing = db.insert(get_span(9, 11), "tester").await.unwrap();
+        let mut other_employee = get_span(9, 11);
+        other_employee.employee = "other".to_owned();
+        db.insert(other_employee, "tester").await.unwrap();
+        let mut other_date = get_span(9, 11);
+        other_date.date = NaiveDate::from_ymd(2021, 10, 10);
+        db.insert(other_date, "tester").await.unwrap();
+
+        assert_eq!(
+            db.overlapping(&get_span(10, 12), None).await.unwrap(),
+            vec![existing.clone()]
+        );
+        assert!(db
+            .overlapping(&get_span(11, 12), None)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(db
+            .overlapping(&get_span(10, 12), Some(existing.id))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(db.overlapping(&get_hours(), None).await.unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn logged_minutes_sums_employee_entries_in_range() {
+        let mut db = get_db().await;
+
+        let first = db.insert(get_hours(), "tester").await.unwrap();
+        db.insert(get_hours(), "tester").await.unwrap();
+        let mut other_employee = get_hours();
+        other_employee.employee = "other".to_owned();
+        db.insert(other_employee, "tester").await.unwrap();
+        let mut other_date = get_hours();
+        other_date.date = NaiveDate::from_ymd(2021, 10, 11);
+        db.insert(other_date, "tester").await.unwrap();
+
+        let day = NaiveDate::from_ymd(2021, 10, 9);
+        let week_end = NaiveDate::from_ymd(2021, 10, 15);
+        assert_eq!(
+            db.logged_minutes("employee", day, day, None).await.unwrap(),
+            120
+        );
+        assert_eq!(
+            db.logged_minutes("employee", day, week_end, None)
+                .await
+                .unwrap(),
+            180
+        );
+        assert_eq!(
+            db.logged_minutes("employee", day, day, Some(first.id))
+                .await
+                .unwrap(),
+            60
+        );
+        assert_eq!(
+            db.logged_minutes("nobody", day, day, None).await.unwrap(),
+            0
+        );
+    }
+
+    #[actix_rt::test]
+    async fn idempotency_keys_are_used_once_per_window() {
+        let mut db = get_db().await;
+        let since = Utc::now() - chrono::Duration::hours(1);
+
+        let hours = db
+            .insert_idempotent(get_hours(), "employee", "key", since, "tester")
+            .await
+            .unwrap();
+        let stored = db
+            .idempotency_key("employee", "key", since)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.hours_id, hours.id);
+        assert_eq!(stored.response, hours);
+
+        match db
+            .insert_idempotent(get_hours(), "employee", "key", since, "tester")
+            .await
+        {
+            Err(Error::Conflict(message)) => assert_eq!(message, IDEMPOTENCY_KEY_USED),
+            _ => panic!("Expected a conflict."),
+        }
+        assert_eq!(
+            db.list(&HoursQuery::default(), &Scope::All)
+                .await
+                .unwrap()
+                .total,
+            1
+        );
+
+        let later = Utc::now() + chrono::Duration::seconds(1);
+        assert_eq!(
+            db.idempotency_key("employee", "key", later).await.unwrap(),
+            None
+        );
+        let again = db
+            .insert_idempotent(get_hours(), "employee", "key", later, "tester")
+            .await
+            .unwrap();
+        assert_eq!(
+            db.idempotency_key("employee", "key", since)
+                .await
+                .unwrap()
+                .unwrap()
+                .hours_id,
+            again.id
+        );
+    }
+
+    #[actix_rt::test]
+    async fn idempotency_keys_are_kept_per_subject() {
+        let mut db = get_db().await;
+        let since = Utc::now() - chrono::Duration::hours(1);
+
+        let own = db
+            .insert_idempotent(get_hours(), "employee", "key", since, "tester")
+            .await
+            .unwrap();
+        let other = db
+            .insert_idempotent(get_hours(), "other", "key", since, "tester")
+            .await
+            .unwrap();
+
+        let stored = db.idempotency_key("other", "key", since).await.unwrap();
+        assert_eq!(stored.unwrap().hours_id, other.id);
+        let stored = db.idempotency_key("employee", "key", since).await.unwrap();
+        assert_eq!(stored.unwrap().hours_id, own.id);
+
+        assert_eq!(db.purge_idempotency_keys(since).await.unwrap(), 0);
+        assert_eq!(db.purge_idempotency_keys(Utc::now()).await.unwrap(), 2);
+        assert_eq!(
+            db.idempotency_key("employee", "key", since).await.unwrap(),
+            None
+        );
+    }
+
+    #[actix_rt::test]
+    async fn insert_all_rolls_back_on_failure() {
+        let mut db = get_db().await;
+
+        let mut broken = get_hours();
+        broken.description = "\0".to_owned();
+
+        let result = db.insert_all(vec![get_hours(), broken], "tester").await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            db.list(&HoursQuery::default(), &Scope::All)
+                .await
+                .unwrap()
+                .total,
+            0
+        );
+    }
+
+    #[actix_rt::test]
+    async fn update_db_empty() {
+        let mut db = get_db().await;
+
+        let result = db
+            .update(Uuid::new_v4(), get_hours(), 1, &Scope::All, "tester")
+            .await
+            .unwrap();
+
+        assert!(result.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn update_db_not_empty() {
+        let mut db = get_db().await;
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+
+        let mut changed = get_hours();
+        changed.description = "changed".to_owned();
+        changed.minutes = 120;
+
+        let result = db
+            .update(hours.id, changed, 1, &Scope::All, "tester")
+            .await
+            .unwrap();
+
+        let expected = Hours {
+            description: "changed".to_owned(),
+            minutes: 120,
+            version: 2,
+            ..hours
+        };
+        assert_eq!(result, Some(expected.clone()));
+        assert_eq!(
+            db.by_id(expected.id, &Scope::All).await.unwrap(),
+            Some(expected)
+        );
+    }
+
+    #[actix_rt::test]
+    async fn entries_logged_on_behalf_of_the_employee_record_who_did_it() {
+        let mut db = get_db().await;
+
+        let own = db.insert(get_hours(), "employee").await.unwrap();
+        let logged = db.insert(get_hours(), "admin").await.unwrap();
+        db.update(logged.id, get_hours(), 1, &Scope::All, "employee")
+            .await
+            .unwrap();
+
+        assert_eq!(own.logged_by, None);
+        assert_eq!(logged.logged_by.as_deref(), Some("admin"));
+        assert_eq!(
+            db.by_id(logged.id, &Scope::All)
+                .await
+                .unwrap()
+                .unwrap()
+                .logged_by,
+            logged.logged_by
+        );
+    }
+
+    #[actix_rt::test]
+    async fn changes_are_recorded_in_history() {
+        let mut db = get_db().await;
+
+        let hours = db.insert(get_hours(), "creator").await.unwrap();
+        let mut changed = get_hours();
+        changed.minutes = 120;
+        db.update(hours.id, changed, 1, &Scope::All, "editor")
+            .await
+            .unwrap();
+        db.delete(hours.id, 2, &Scope::All, "remover")
+            .await
+            .unwrap();
+
+        let history = db.history(hours.id, &Scope::All).await.unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .map(|r| (r.operation, r.actor.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Operation::Insert, "creator"),
+                (Operation::Update, "editor"),
+                (Operation::Delete, "remover")
+            ]
+        );
+        assert_eq!(history[0].before, None);
+        assert_eq!(history[1].before, history[0].after);
+        assert_eq!(history[1].after.as_ref().unwrap()["hours"], 2);
+        assert_eq!(history[2].before, history[1].after);
+        assert_eq!(history[2].after, None);
+        assert!(db
+            .history(Uuid::new_v4(), &Scope::All)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn deleted_entries_move_to_trash() {
+        let mut db = get_db().await;
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+        assert!(db.delete(hours.id, 1, &Scope::All, "tester").await.unwrap());
+
+        assert_eq!(db.by_id(hours.id, &Scope::All).await.unwrap(), None);
+        assert_eq!(
+            db.list(&HoursQuery::default(), &Scope::All)
+                .await
+                .unwrap()
+                .total,
+            0
+        );
+        assert_eq!(
+            db.logged_minutes("employee", hours.date, hours.date, None)
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(!db.delete(hours.id, 1, &Scope::All, "tester").await.unwrap());
+        let trash = db.trash(&Scope::All).await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert!(trash[0].deleted_at.is_some());
+        assert_eq!(
+            db.trash_by_id(hours.id, &Scope::All).await.unwrap(),
+            Some(trash[0].clone())
+        );
+
+        let restored = Hours {
+            version: 3,
+            ..hours
+        };
+        assert!(matches!(
+            db.restore(hours.id, 1, &Scope::All, "tester").await,
+            Err(Error::PreconditionFailed(_))
+        ));
+        assert_eq!(
+            db.restore(hours.id, 2, &Scope::All, "tester")
+                .await
+                .unwrap(),
+            Some(restored.clone())
+        );
+        assert_eq!(
+            db.restore(hours.id, 3, &Scope::All, "tester")
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            db.by_id(hours.id, &Scope::All).await.unwrap(),
+            Some(restored)
+        );
+        assert!(db.trash(&Scope::All).await.unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn entries_outside_of_the_scope_are_forbidden() {
+        let mut db = get_db().await;
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+        let trashed = db.insert(get_hours(), "tester").await.unwrap();
+        db.delete(trashed.id, 1, &Scope::All, "tester")
+            .await
+            .unwrap();
+        let own = Scope::Employees(vec!["employee".to_owned()]);
+        let other = Scope::Employees(vec!["other".to_owned()]);
+
+        assert_eq!(
+            db.list(&HoursQuery::default(), &own).await.unwrap().total,
+            1
+        );
+        assert_eq!(
+            db.list(&HoursQuery::default(), &other).await.unwrap().total,
+            0
+        );
+        assert_eq!(db.trash(&own).await.unwrap().len(), 1);
+        assert!(db.trash(&other).await.unwrap().is_empty());
+        assert!(matches!(
+            db.by_id(hours.id, &other).await,
+            Err(Error::Forbidden(_))
+        ));
+        assert!(matches!(
+            db.history(hours.id, &other).await,
+            Err(Error::Forbidden(_))
+        ));
+        assert!(matches!(
+            db.delete(hours.id, 1, &other, "tester").await,
+            Err(Error::Forbidden(_))
+        ));
+        assert!(matches!(
+            db.restore(trashed.id, 2, &other, "tester").await,
+            Err(Error::Forbidden(_))
+        ));
+        let moved = NewHours {
+            employee: "other".to_owned(),
+            ..get_hours()
+        };
+        assert!(matches!(
+            db.update(hours.id, moved, 1, &own, "tester").await,
+            Err(Error::Forbidden(_))
+        ));
+        assert_eq!(db.by_id(hours.id, &own).await.unwrap(), Some(hours));
+    }
+
+    #[actix_rt::test]
+    async fn purge_trash_removes_old_entries() {
+        let mut db = get_db().await;
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+        let kept = db.insert(get_hours(), "tester").await.unwrap();
+        db.delete(hours.id, 1, &Scope::All, "tester").await.unwrap();
+        let deleted_before = Utc::now() + chrono::Duration::seconds(1);
+
+        assert_eq!(db.purge_trash(deleted_before, "tester").await.unwrap(), 1);
+        assert_eq!(db.purge_trash(deleted_before, "tester").await.unwrap(), 0);
+
+        assert!(db.trash(&Scope::All).await.unwrap().is_empty());
+        assert_eq!(db.by_id(kept.id, &Scope::All).await.unwrap(), Some(kept));
+        assert_eq!(
+            db.history(hours.id, &Scope::All)
+                .await
+                .unwrap()
+                .last()
+                .unwrap()
+                .operation,
+            Operation::Purge
+        );
+    }
+
+    #[actix_rt::test]
+    async fn history_is_append_only() {
+        let mut db = get_db().await;
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+
+        let result = sqlx::query("DELETE FROM hours_audit WHERE hours_id = $1")
+            .bind(hours.id)
+            .execute(&mut db)
+            .await;
+        assert!(result.is_err());
+        assert_eq!(db.history(hours.id, &Scope::All).await.unwrap().len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn stale_changes_are_rejected() {
+        let mut db = get_db().await;
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+        db.update(hours.id, get_hours(), 1, &Scope::All, "tester")
+            .await
+            .unwrap();
+
+        match db
+            .update(hours.id, get_hours(), 1, &Scope::All, "tester")
+            .await
+        {
+            Err(Error::PreconditionFailed(message)) => assert_eq!(message, HOURS_CHANGED),
+            _ => panic!("Expected a failed precondition."),
+        }
+        assert!(matches!(
+            db.delete(hours.id, 1, &Scope::All, "tester").await,
+            Err(Error::PreconditionFailed(_))
+        ));
+        assert!(db.delete(hours.id, 2, &Scope::All, "tester").await.unwrap());
+        assert_eq!(db.history(hours.id, &Scope::All).await.unwrap().len(), 3);
+    }
+
+    #[actix_rt::test]
+    async fn update_db_not_empty_invalid_key() {
+        let mut db = get_db().await;
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+
+        let result = db
+            .update(Uuid::new_v4(), get_hours(), 1, &Scope::All, "tester")
+            .await
+            .unwrap();
+
+        assert!(result.is_none());
+        assert_eq!(db.by_id(hours.id, &Scope::All).await.unwrap(), Some(hours));
+    }
+
     #[actix_rt::test]
     async fn list_db_empty() {
-        let mut db = test_utils::internal::get_db_connection().await;
+        let mut db = get_db().await;
 
-        let result = db.list().await;
+        let result = db.list(&HoursQuery::default(), &Scope::All).await.unwrap();
 
-        assert_eq!(result, vec![]);
+        assert_eq!(
+            result,
+            Page {
+                items: vec![],
+                total: 0
+            }
+        );
     }
 
     #[actix_rt::test]
     async fn list_db_not_empty() {
-        let mut db = test_utils::internal::get_db_connection().await;
+        let mut db = get_db().await;
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+
+        let result = db.list(&HoursQuery::default(), &Scope::All).await.unwrap();
+
+        assert_eq!(
+            result,
+            Page {
+                items: vec![hours],
+                total: 1
+            }
+        );
+    }
 
-        let hours = db.insert(get_hours()).await;
+    #[actix_rt::test]
+    async fn list_filters() {
+        let mut db = get_db().await;
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+        let mut other = get_hours();
+        other.employee = "other".to_owned();
+        other.date = NaiveDate::from_ymd(2021, 10, 11);
+        other.story_id = Some("story".to_owned());
+        let other = db.insert(other, "tester").await.unwrap();
+
+        let by_employee = HoursQuery {
+            employee: Some("other".to_owned()),
+            ..HoursQuery::default()
+        };
+        assert_eq!(
+            db.list(&by_employee, &Scope::All).await.unwrap().items,
+            vec![other.clone()]
+        );
+
+        let by_story = HoursQuery {
+            story_id: Some("story".to_owned()),
+            ..HoursQuery::default()
+        };
+        assert_eq!(
+            db.list(&by_story, &Scope::All).await.unwrap().items,
+            vec![other.clone()]
+        );
+
+        let by_date = HoursQuery {
+            from: Some(NaiveDate::from_ymd(2021, 10, 1)),
+            to: Some(NaiveDate::from_ymd(2021, 10, 10)),
+            ..HoursQuery::default()
+        };
+        assert_eq!(
+            db.list(&by_date, &Scope::All).await.unwrap().items,
+            vec![hours]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn list_sorts_and_paginates() {
+        let mut db = get_db().await;
 
-        let result = db.list().await;
+        let mut inserted = vec![];
+        for day in 1..=5 {
+            let mut hours = get_hours();
+            hours.date = NaiveDate::from_ymd(2021, 10, day);
+            inserted.push(db.insert(hours, "tester").await.unwrap());
+        }
+
+        let query = HoursQuery {
+            sort: SortField::Date,
+            order: SortOrder::Desc,
+            limit: Some(2),
+            offset: 1,
+            ..HoursQuery::default()
+        };
+        let result = db.list(&query, &Scope::All).await.unwrap();
+
+        assert_eq!(
+            result,
+            Page {
+                items: vec![inserted[3].clone(), inserted[2].clone()],
+                total: 5
+            }
+        );
+    }
+
+    #[actix_rt::test]
+    async fn summary_groups_by_period() {
+        let mut db = get_db().await;
+
+        for (day, story_id, hours) in &[
+            (4, None, 2),
+            (5, None, 3),
+            (6, Some("story"), 4),
+            (11, None, 1),
+        ] {
+            let mut new_hours = get_hours();
+            new_hours.date = NaiveDate::from_ymd(2021, 10, *day);
+            new_hours.story_id = story_id.map(str::to_owned);
+            new_hours.minutes = *hours * 60;
+            db.insert(new_hours, "tester").await.unwrap();
+        }
+        let mut outside = get_hours();
+        outside.date = NaiveDate::from_ymd(2021, 9, 30);
+        db.insert(outside, "tester").await.unwrap();
+
+        let query = SummaryQuery {
+            from: NaiveDate::from_ymd(2021, 10, 1),
+            to: NaiveDate::from_ymd(2021, 10, 31),
+            granularity: Granularity::Week,
+            employee: None,
+            project: None,
+        };
+        let result = db.summary(&query, &Scope::All).await.unwrap();
+
+        let row = |day, story_id: Option<&str>, hours: i64| SummaryRow {
+            period: NaiveDate::from_ymd(2021, 10, day),
+            employee: "employee".to_owned(),
+            project: "project".to_owned(),
+            story_id: story_id.map(str::to_owned),
+            minutes: hours * 60,
+        };
+        assert_eq!(
+            result,
+            vec![row(4, Some("story"), 4), row(4, None, 5), row(11, None, 1)]
+        );
+        let other = Scope::Employees(vec!["other".to_owned()]);
+        assert!(db.summary(&query, &other).await.unwrap().is_empty());
+    }
+
+    async fn get_db() -> PoolConnection<Postgres> {
+        let mut db = test_utils::internal::get_db_connection().await;
+        test_utils::internal::seed(&mut db).await;
+        db
+    }
 
-        assert_eq!(result, vec![hours]);
+    fn get_span(start: u32, end: u32) -> NewHours {
+        let mut new_hours = get_hours();
+        new_hours.set_time(LoggedTime::Span(
+            NaiveTime::from_hms(start, 0, 0),
+            NaiveTime::from_hms(end, 0, 0),
+        ));
+        new_hours
     }
 
     fn get_hours() -> NewHours {
@@ -170,7 +1421,9 @@ pub mod tests {
             project: "project".to_owned(),
             story_id: None,
             description: "description".to_owned(),
-            hours: 1,
+            minutes: 60,
+            start_time: None,
+            end_time: None,
         }
     }
 }
@@ -0,0 +1,115 @@
+use std::convert::TryFrom;
+
+use async_trait::async_trait;
+use chrono::{SubsecRound, Utc};
+use uuid::Uuid;
+
+use super::PgConnectionLike;
+use crate::api_keys::ApiKey;
+use crate::error::Error;
+
+#[async_trait]
+pub trait ApiKeyRepo: Send {
+    /// The key with the given hash, unless it has been revoked.
+    async fn api_key_by_hash(&mut self, hash: &str) -> Result<Option<ApiKey>, Error>;
+    /// Every key, including the revoked ones, oldest first.
+    async fn list_api_keys(&mut self) -> Result<Vec<ApiKey>, Error>;
+    async fn insert_api_key(&mut self, key: &ApiKey) -> Result<(), Error>;
+    /// Revokes the key, returning it if it was still valid.
+    async fn revoke_api_key(&mut self, id: Uuid) -> Result<Option<ApiKey>, Error>;
+}
+
+#[async_trait]
+impl<C: PgConnectionLike> ApiKeyRepo for C {
+    async fn api_key_by_hash(&mut self, hash: &str) -> Result<Option<ApiKey>, Error> {
+        Ok(
+            sqlx::query("SELECT * FROM api_keys WHERE hash = $1 AND revoked_at IS NULL")
+                .bind(hash)
+                .try_map(ApiKey::try_from)
+                .fetch_optional(&mut **self)
+                .await?,
+        )
+    }
+
+    async fn list_api_keys(&mut self) -> Result<Vec<ApiKey>, Error> {
+        Ok(
+            sqlx::query("SELECT * FROM api_keys ORDER BY created_at, id")
+                .try_map(ApiKey::try_from)
+                .fetch_all(&mut **self)
+                .await?,
+        )
+    }
+
+    async fn insert_api_key(&mut self, key: &ApiKey) -> Result<(), Error> {
+        let sql = "INSERT INTO api_keys (id, name, prefix, hash, role, created_at, revoked_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)";
+        sqlx::query(sql)
+            .bind(key.id)
+            .bind(key.name.clone())
+            .bind(key.prefix.clone())
+            .bind(key.hash.clone())
+            .bind(key.role.as_str())
+            .bind(key.created_at)
+            .bind(key.revoked_at)
+            .execute(&mut **self)
+            .await?;
+        Ok(())
+    }
+
+    async fn revoke_api_key(&mut self, id: Uuid) -> Result<Option<ApiKey>, Error> {
+        Ok(sqlx::query(
+            "UPDATE api_keys SET revoked_at = $2
+            WHERE id = $1 AND revoked_at IS NULL
+            RETURNING *",
+        )
+        .bind(id)
+        .bind(Utc::now().trunc_subsecs(6))
+        .try_map(ApiKey::try_from)
+        .fetch_optional(&mut **self)
+        .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_keys::{hash, NewApiKey};
+    use crate::auth::Role;
+    use crate::test_utils;
+
+    #[actix_rt::test]
+    async fn insert_and_find_by_hash() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let created = ApiKey::generate(get_key("mobile"));
+        db.insert_api_key(&created.api_key).await.unwrap();
+
+        assert_eq!(
+            db.api_key_by_hash(&hash(&created.key)).await.unwrap(),
+            Some(created.api_key.clone())
+        );
+        assert_eq!(db.api_key_by_hash(&hash("wrong")).await.unwrap(), None);
+        assert_eq!(db.list_api_keys().await.unwrap(), vec![created.api_key]);
+    }
+
+    #[actix_rt::test]
+    async fn revoked_keys_are_not_found() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let created = ApiKey::generate(get_key("mobile"));
+        db.insert_api_key(&created.api_key).await.unwrap();
+
+        let revoked = db.revoke_api_key(created.api_key.id).await.unwrap();
+        assert!(revoked.unwrap().revoked_at.is_some());
+        assert_eq!(db.revoke_api_key(created.api_key.id).await.unwrap(), None);
+        assert_eq!(db.api_key_by_hash(&hash(&created.key)).await.unwrap(), None);
+        assert_eq!(db.list_api_keys().await.unwrap().len(), 1);
+    }
+
+    fn get_key(name: &str) -> NewApiKey {
+        NewApiKey {
+            name: name.to_owned(),
+            role: Role::Manager,
+        }
+    }
+}
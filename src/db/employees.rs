@@ -0,0 +1,248 @@
+use std::convert::TryFrom;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::PgConnectionLike;
+use crate::employees::{Employee, NewEmployee};
+use crate::error::{conflict_on_constraint, Error};
+
+const DUPLICATE_USERNAME: &str = "An employee with this username already exists";
+
+#[async_trait]
+pub trait EmployeeRepo: Send {
+    async fn employee_by_id(&mut self, id: Uuid) -> Result<Option<Employee>, Error>;
+    async fn employee_by_username(&mut self, username: &str) -> Result<Option<Employee>, Error>;
+    async fn list_employees(&mut self) -> Result<Vec<Employee>, Error>;
+    /// The usernames of the employees that `manager` manages.
+    async fn team(&mut self, manager: &str) -> Result<Vec<String>, Error>;
+    async fn insert_employee(&mut self, e: NewEmployee) -> Result<Employee, Error>;
+    /// Renaming an employee also renames them on their hours entries.
+    async fn update_employee(
+        &mut self,
+        id: Uuid,
+        e: NewEmployee,
+    ) -> Result<Option<Employee>, Error>;
+    /// Employees that have logged hours can not be deleted.
+    async fn delete_employee(&mut self, id: Uuid) -> Result<bool, Error>;
+}
+
+#[async_trait]
+impl<C: PgConnectionLike> EmployeeRepo for C {
+    async fn employee_by_id(&mut self, id: Uuid) -> Result<Option<Employee>, Error> {
+        Ok(sqlx::query("SELECT * FROM employees WHERE id = $1")
+            .bind(id)
+            .try_map(Employee::try_from)
+            .fetch_optional(&mut **self)
+            .await?)
+    }
+
+    async fn employee_by_username(&mut self, username: &str) -> Result<Option<Employee>, Error> {
+        Ok(sqlx::query("SELECT * FROM employees WHERE username = $1")
+            .bind(username)
+            .try_map(Employee::try_from)
+            .fetch_optional(&mut **self)
+            .await?)
+    }
+
+    async fn list_employees(&mut self) -> Result<Vec<Employee>, Error> {
+        Ok(sqlx::query("SELECT * FROM employees ORDER BY username")
+            .try_map(Employee::try_from)
+            .fetch_all(&mut **self)
+            .await?)
+    }
+
+    async fn team(&mut self, manager: &str) -> Result<Vec<String>, Error> {
+        Ok(sqlx::query_scalar(
+            "SELECT username FROM employees WHERE manager = $1 ORDER BY username",
+        )
+        .bind(manager)
+        .fetch_all(&mut **self)
+        .await?)
+    }
+
+    async fn insert_employee(&mut self, e: NewEmployee) -> Result<Employee, Error> {
+        let employee = Employee::new(e);
+        sqlx::query("INSERT INTO employees (id, username, name, manager) VALUES ($1, $2, $3, $4)")
+            .bind(employee.id)
+            .bind(employee.username.clone())
+            .bind(employee.name.clone())
+            .bind(employee.manager.clone())
+            .execute(&mut **self)
+            .await
+            .map_err(|e| conflict_on_constraint(e, DUPLICATE_USERNAME))?;
+        Ok(employee)
+    }
+
+    async fn update_employee(
+        &mut self,
+        id: Uuid,
+        e: NewEmployee,
+    ) -> Result<Option<Employee>, Error> {
+        let e = e.normalized();
+        sqlx::query(
+            "UPDATE employees SET username = $2, name = $3, manager = $4 WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .bind(e.username)
+        .bind(e.name)
+        .bind(e.manager)
+        .try_map(Employee::try_from)
+        .fetch_optional(&mut **self)
+        .await
+        .map_err(|e| conflict_on_constraint(e, DUPLICATE_USERNAME))
+    }
+
+    async fn delete_employee(&mut self, id: Uuid) -> Result<bool, Error> {
+        Ok(
+            sqlx::query("DELETE FROM employees WHERE id = $1 RETURNING 1")
+                .bind(id)
+                .fetch_optional(&mut **self)
+                .await
+                .map_err(|e| conflict_on_constraint(e, "The employee has logged hours"))?
+                .is_some(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::db::HoursRepo;
+    use crate::hours::access::Scope;
+    use crate::hours::NewHours;
+    use crate::test_utils;
+
+    #[actix_rt::test]
+    async fn insert_and_find() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let employee = db.insert_employee(get_employee("Kaarel")).await.unwrap();
+
+        assert_eq!(employee.username, "kaarel");
+        assert_eq!(
+            db.employee_by_id(employee.id).await.unwrap(),
+            Some(employee.clone())
+        );
+        assert_eq!(
+            db.employee_by_username("kaarel").await.unwrap(),
+            Some(employee.clone())
+        );
+        assert_eq!(db.list_employees().await.unwrap(), vec![employee]);
+    }
+
+    #[actix_rt::test]
+    async fn insert_duplicate_username() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        db.insert_employee(get_employee("kaarel")).await.unwrap();
+        let result = db.insert_employee(get_employee("KAAREL")).await;
+
+        assert!(matches!(result, Err(Error::Conflict(_))));
+    }
+
+    #[actix_rt::test]
+    async fn update_renames_hours() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        test_utils::internal::seed(&mut db).await;
+        let employee = db.insert_employee(get_employee("kaarel")).await.unwrap();
+        let hours = db.insert(get_hours("kaarel"), "tester").await.unwrap();
+
+        let result = db
+            .update_employee(employee.id, get_employee("kratas"))
+            .await
+            .unwrap();
+
+        assert_eq!(result.unwrap().username, "kratas");
+        assert_eq!(
+            db.by_id(hours.id, &Scope::All)
+                .await
+                .unwrap()
+                .unwrap()
+                .employee,
+            "kratas"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn team_follows_renames_of_the_manager() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let manager = db.insert_employee(get_employee("boss")).await.unwrap();
+        for username in &["kaarel", "mari"] {
+            db.insert_employee(NewEmployee {
+                manager: Some("boss".to_owned()),
+                ..get_employee(username)
+            })
+            .await
+            .unwrap();
+        }
+        db.insert_employee(get_employee("other")).await.unwrap();
+
+        assert_eq!(db.team("boss").await.unwrap(), vec!["kaarel", "mari"]);
+        db.update_employee(manager.id, get_employee("chief"))
+            .await
+            .unwrap();
+        assert!(db.team("boss").await.unwrap().is_empty());
+        assert_eq!(db.team("chief").await.unwrap(), vec!["kaarel", "mari"]);
+    }
+
+    #[actix_rt::test]
+    async fn update_nonexistent() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let result = db
+            .update_employee(Uuid::new_v4(), get_employee("kaarel"))
+            .await
+            .unwrap();
+
+        assert!(result.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn delete_with_hours() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        test_utils::internal::seed(&mut db).await;
+        let employee = db.insert_employee(get_employee("kaarel")).await.unwrap();
+        db.insert(get_hours("kaarel"), "tester").await.unwrap();
+
+        let result = db.delete_employee(employee.id).await;
+
+        assert!(matches!(result, Err(Error::Conflict(_))));
+    }
+
+    #[actix_rt::test]
+    async fn delete_without_hours() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let employee = db.insert_employee(get_employee("kaarel")).await.unwrap();
+
+        assert!(db.delete_employee(employee.id).await.unwrap());
+        assert!(!db.delete_employee(employee.id).await.unwrap());
+    }
+
+    fn get_employee(username: &str) -> NewEmployee {
+        NewEmployee {
+            username: username.to_owned(),
+            name: "Kaarel Ratas".to_owned(),
+            manager: None,
+        }
+    }
+
+    fn get_hours(employee: &str) -> NewHours {
+        NewHours {
+            employee: employee.to_owned(),
+            date: NaiveDate::from_ymd(2021, 10, 9),
+            project: "project".to_owned(),
+            story_id: None,
+            description: "description".to_owned(),
+            minutes: 60,
+            start_time: None,
+            end_time: None,
+        }
+    }
+}
@@ -0,0 +1,1144 @@
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, SubsecRound, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
+use uuid::Uuid;
+
+use super::timers::TIMER_RUNNING;
+use super::timesheets::changed_concurrently;
+use super::{
+    ApiKeyRepo, Database, EmployeeRepo, HoursRepo, PeriodRepo, ProjectRepo, ReportRepo,
+    SessionRepo, TimerRepo, TimesheetRepo, Transaction, HOURS_CHANGED, IDEMPOTENCY_KEY_USED,
+};
+use crate::api_keys::ApiKey;
+use crate::employees::{Employee, NewEmployee};
+use crate::error::Error;
+use crate::hours::access::Scope;
+use crate::hours::audit::AuditRecord;
+use crate::hours::idempotency::IdempotencyKey;
+use crate::hours::query::{SortField, SortOrder};
+use crate::hours::{Hours, HoursQuery, NewHours, Page};
+use crate::periods::PeriodLock;
+use crate::projects::{NewProject, Project, ProjectQuery};
+use crate::reports::{SummaryQuery, SummaryRow};
+use crate::sessions::{LoginAttempt, Session};
+use crate::timers::{NewTimer, Timer, TimerQuery};
+use crate::timesheets::model::TimesheetState;
+use crate::timesheets::{Timesheet, TimesheetQuery};
+
+/// A database that lives in the process memory. Useful for tests and for running a demo
+/// without Postgres. Cloning it gives another handle to the same data.
+#[derive(Clone, Default)]
+pub struct MemoryDatabase {
+    hours: Arc<Mutex<Vec<Hours>>>,
+    audit: Arc<Mutex<Vec<AuditRecord>>>,
+    idempotency_keys: Arc<Mutex<Vec<IdempotencyKey>>>,
+    employees: Arc<Mutex<Vec<Employee>>>,
+    projects: Arc<Mutex<Vec<Project>>>,
+    timers: Arc<Mutex<Vec<Timer>>>,
+    timesheets: Arc<Mutex<Vec<Timesheet>>>,
+    period_locks: Arc<Mutex<Vec<PeriodLock>>>,
+    api_keys: Arc<Mutex<Vec<ApiKey>>>,
+    logins: Arc<Mutex<Vec<LoginAttempt>>>,
+    sessions: Arc<Mutex<Vec<Session>>>,
+}
+
+impl MemoryDatabase {
+    fn hours(&self) -> MutexGuard<'_, Vec<Hours>> {
+        self.hours.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Always taken after `hours` when both are needed.
+    fn audit(&self) -> MutexGuard<'_, Vec<AuditRecord>> {
+        self.audit.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Always taken before `hours` when both are needed.
+    fn idempotency_keys(&self) -> MutexGuard<'_, Vec<IdempotencyKey>> {
+        self.idempotency_keys
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn employees(&self) -> MutexGuard<'_, Vec<Employee>> {
+        self.employees
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn projects(&self) -> MutexGuard<'_, Vec<Project>> {
+        self.projects.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn timers(&self) -> MutexGuard<'_, Vec<Timer>> {
+        self.timers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn timesheets(&self) -> MutexGuard<'_, Vec<Timesheet>> {
+        self.timesheets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn period_locks(&self) -> MutexGuard<'_, Vec<PeriodLock>> {
+        self.period_locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn api_keys(&self) -> MutexGuard<'_, Vec<ApiKey>> {
+        self.api_keys.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn logins(&self) -> MutexGuard<'_, Vec<LoginAttempt>> {
+        self.logins.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn sessions(&self) -> MutexGuard<'_, Vec<Session>> {
+        self.sessions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn page(&self, query: &HoursQuery, scope: &Scope) -> Page<Hours> {
+        let mut matching: Vec<Hours> = self
+            .hours()
+            .iter()
+            .filter(|h| matches(query, h) && scope.includes(&h.employee))
+            .cloned()
+            .collect();
+        matching.sort_by(|a, b| compare(query.sort, query.order, a, b).then(a.id.cmp(&b.id)));
+        let total = matching.len() as i64;
+        let items = matching
+            .into_iter()
+            .skip(query.offset as usize)
+            .take(query.limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect();
+        Page { items, total }
+    }
+}
+
+#[async_trait]
+impl Database for MemoryDatabase {
+    type Connection = MemoryDatabase;
+    type Transaction = MemoryDatabase;
+
+    async fn connection(&self) -> Result<Self::Connection, Error> {
+        Ok(self.clone())
+    }
+
+    async fn begin(&self) -> Result<Self::Transaction, Error> {
+        Ok(self.clone())
+    }
+}
+
+/// Changes are kept right away and there is nothing to roll back. Employees are not locked.
+#[async_trait]
+impl Transaction for MemoryDatabase {
+    async fn lock_employees(&mut self, _employees: &[&str]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn commit(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HoursRepo for MemoryDatabase {
+    async fn by_id(&mut self, id: Uuid, scope: &Scope) -> Result<Option<Hours>, Error> {
+        let hours = self
+            .hours()
+            .iter()
+            .find(|h| h.id == id && h.deleted_at.is_none())
+            .cloned();
+        scope.check_entry(hours)
+    }
+
+    async fn list(&mut self, query: &HoursQuery, scope: &Scope) -> Result<Page<Hours>, Error> {
+        Ok(self.page(query, scope))
+    }
+
+    fn export(self, query: HoursQuery, scope: Scope) -> BoxStream<'static, Result<Hours, Error>> {
+        let items = self.page(&query, &scope).items;
+        stream::iter(items.into_iter().map(Ok)).boxed()
+    }
+
+    async fn insert(&mut self, h: NewHours, actor: &str) -> Result<Hours, Error> {
+        let hours = Hours::new(h, actor);
+        self.hours().push(hours.clone());
+        self.audit().push(AuditRecord::inserted(actor, &hours));
+        Ok(hours)
+    }
+
+    async fn insert_idempotent(
+        &mut self,
+        h: NewHours,
+        subject: &str,
+        key: &str,
+        since: DateTime<Utc>,
+        actor: &str,
+    ) -> Result<Hours, Error> {
+        let mut keys = self.idempotency_keys();
+        if keys
+            .iter()
+            .any(|k| k.subject == subject && k.key == key && k.created_at >= since)
+        {
+            return Err(Error::Conflict(IDEMPOTENCY_KEY_USED.to_owned()));
+        }
+        let hours = Hours::new(h, actor);
+        self.hours().push(hours.clone());
+        self.audit().push(AuditRecord::inserted(actor, &hours));
+        keys.retain(|k| k.subject != subject || k.key != key);
+        keys.push(IdempotencyKey::new(subject, key, &hours));
+        Ok(hours)
+    }
+
+    async fn idempotency_key(
+        &mut self,
+        subject: &str,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<IdempotencyKey>, Error> {
+        Ok(self
+            .idempotency_keys()
+            .iter()
+            .find(|k| k.subject == subject && k.key == key && k.created_at >= since)
+            .cloned())
+    }
+
+    async fn purge_idempotency_keys(&mut self, used_before: DateTime<Utc>) -> Result<u64, Error> {
+        let mut keys = self.idempotency_keys();
+        let count = keys.len();
+        keys.retain(|k| k.created_at >= used_before);
+        Ok((count - keys.len()) as u64)
+    }
+
+    async fn insert_all(&mut self, hs: Vec<NewHours>, actor: &str) -> Result<Vec<Hours>, Error> {
+        let inserted: Vec<Hours> = hs.into_iter().map(|h| Hours::new(h, actor)).collect();
+        self.hours().extend(inserted.iter().cloned());
+        self.audit().extend(
+            inserted
+                .iter()
+                .map(|hours| AuditRecord::inserted(actor, hours)),
+        );
+        Ok(inserted)
+    }
+
+    async fn update(
+        &mut self,
+        id: Uuid,
+        h: NewHours,
+        version: i32,
+        scope: &Scope,
+        actor: &str,
+    ) -> Result<Option<Hours>, Error> {
+        let mut all_hours = self.hours();
+        let stored = match all_hours
+            .iter_mut()
+            .find(|h| h.id == id && h.deleted_at.is_none())
+        {
+            Some(stored) => stored,
+            None => return Ok(None),
+        };
+        scope.check(&stored.employee)?;
+        if stored.version != version {
+            return Err(Error::PreconditionFailed(HOURS_CHANGED.to_owned()));
+        }
+        scope.check(&h.employee)?;
+        let updated = Hours {
+            id,
+            version: version + 1,
+            logged_by: stored.logged_by.clone(),
+            ..Hours::new(h, actor)
+        };
+        self.audit()
+            .push(AuditRecord::updated(actor, stored, &updated));
+        *stored = updated.clone();
+        Ok(Some(updated))
+    }
+
+    async fn delete(
+        &mut self,
+        id: Uuid,
+        version: i32,
+        scope: &Scope,
+        actor: &str,
+    ) -> Result<bool, Error> {
+        let mut all_hours = self.hours();
+        let stored = match all_hours
+            .iter_mut()
+            .find(|h| h.id == id && h.deleted_at.is_none())
+        {
+            Some(stored) => stored,
+            None => return Ok(false),
+        };
+        scope.check(&stored.employee)?;
+        if stored.version != version {
+            return Err(Error::PreconditionFailed(HOURS_CHANGED.to_owned()));
+        }
+        self.audit().push(AuditRecord::deleted(actor, stored));
+        stored.deleted_at = Some(Utc::now().trunc_subsecs(6));
+        stored.version += 1;
+        Ok(true)
+    }
+
+    async fn trash(&mut self, scope: &Scope) -> Result<Vec<Hours>, Error> {
+        let mut trash: Vec<Hours> = self
+            .hours()
+            .iter()
+            .filter(|h| h.deleted_at.is_some() && scope.includes(&h.employee))
+            .cloned()
+            .collect();
+        trash.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then(a.id.cmp(&b.id)));
+        Ok(trash)
+    }
+
+    async fn trash_by_id(&mut self, id: Uuid, scope: &Scope) -> Result<Option<Hours>, Error> {
+        let hours = self
+            .hours()
+            .iter()
+            .find(|h| h.id == id && h.deleted_at.is_some())
+            .cloned();
+        scope.check_entry(hours)
+    }
+
+    async fn restore(
+        &mut self,
+        id: Uuid,
+        version: i32,
+        scope: &Scope,
+        actor: &str,
+    ) -> Result<Option<Hours>, Error> {
+        let mut all_hours = self.hours();
+        let stored = match all_hours
+            .iter_mut()
+            .find(|h| h.id == id && h.deleted_at.is_some())
+        {
+            Some(stored) => stored,
+            None => return Ok(None),
+        };
+        scope.check(&stored.employee)?;
+        if stored.version != version {
+            return Err(Error::PreconditionFailed(HOURS_CHANGED.to_owned()));
+        }
+        stored.deleted_at = None;
+        stored.version += 1;
+        self.audit().push(AuditRecord::restored(actor, stored));
+        Ok(Some(stored.clone()))
+    }
+
+    async fn purge_trash(
+        &mut self,
+        deleted_before: DateTime<Utc>,
+        actor: &str,
+    ) -> Result<u64, Error> {
+        let mut all_hours = self.hours();
+        let (purged, kept): (Vec<Hours>, Vec<Hours>) = all_hours
+            .drain(..)
+            .partition(|h| h.deleted_at.is_some_and(|at| at < deleted_before));
+        *all_hours = kept;
+        self.audit()
+            .extend(purged.iter().map(|hours| AuditRecord::purged(actor, hours)));
+        Ok(purged.len() as u64)
+    }
+
+    async fn history(&mut self, id: Uuid, scope: &Scope) -> Result<Vec<AuditRecord>, Error> {
+        let history = self
+            .audit()
+            .iter()
+            .filter(|record| record.hours_id == id)
+            .cloned()
+            .collect();
+        scope.check_history(history)
+    }
+
+    async fn overlapping(
+        &mut self,
+        h: &NewHours,
+        exclude: Option<Uuid>,
+    ) -> Result<Vec<Hours>, Error> {
+        let (start, end) = match h.span() {
+            Some(span) => span,
+            None => return Ok(vec![]),
+        };
+        let mut overlapping: Vec<Hours> = self
+            .hours()
+            .iter()
+            .filter(|other| {
+                other.deleted_at.is_none()
+                    && other.employee == h.employee
+                    && other.date == h.date
+                    && Some(other.id) != exclude
+                    && matches!((other.start_time, other.end_time),
+                        (Some(other_start), Some(other_end)) if other_start < end && other_end > start)
+            })
+            .cloned()
+            .collect();
+        overlapping.sort_by(|a, b| a.start_time.cmp(&b.start_time).then(a.id.cmp(&b.id)));
+        Ok(overlapping)
+    }
+
+    async fn logged_minutes(
+        &mut self,
+        employee: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        exclude: Option<Uuid>,
+    ) -> Result<i64, Error> {
+        Ok(self
+            .hours()
+            .iter()
+            .filter(|h| {
+                h.deleted_at.is_none()
+                    && h.employee == employee
+                    && h.date >= from
+                    && h.date <= to
+                    && Some(h.id) != exclude
+            })
+            .map(|h| i64::from(h.minutes))
+            .sum())
+    }
+}
+
+#[async_trait]
+impl EmployeeRepo for MemoryDatabase {
+    async fn employee_by_id(&mut self, id: Uuid) -> Result<Option<Employee>, Error> {
+        Ok(self.employees().iter().find(|e| e.id == id).cloned())
+    }
+
+    async fn employee_by_username(&mut self, username: &str) -> Result<Option<Employee>, Error> {
+        Ok(self
+            .employees()
+            .iter()
+            .find(|e| e.username == username)
+            .cloned())
+    }
+
+    async fn list_employees(&mut self) -> Result<Vec<Employee>, Error> {
+        let mut employees = self.employees().clone();
+        employees.sort_by(|a, b| a.username.cmp(&b.username));
+        Ok(employees)
+    }
+
+    async fn team(&mut self, manager: &str) -> Result<Vec<String>, Error> {
+        let mut team: Vec<String> = self
+            .employees()
+            .iter()
+            .filter(|e| e.manager.as_deref() == Some(manager))
+            .map(|e| e.username.clone())
+            .collect();
+        team.sort();
+        Ok(team)
+    }
+
+    async fn insert_employee(&mut self, e: NewEmployee) -> Result<Employee, Error> {
+        let employee = Employee::new(e);
+        let mut employees = self.employees();
+        if employees.iter().any(|e| e.username == employee.username) {
+            return Err(duplicate_username());
+        }
+        employees.push(employee.clone());
+        Ok(employee)
+    }
+
+    async fn update_employee(
+        &mut self,
+        id: Uuid,
+        e: NewEmployee,
+    ) -> Result<Option<Employee>, Error> {
+        let updated = Employee {
+            id,
+            ..Employee::new(e)
+        };
+        let mut employees = self.employees();
+        if employees
+            .iter()
+            .any(|e| e.id != id && e.username == updated.username)
+        {
+            return Err(duplicate_username());
+        }
+        let stored = match employees.iter_mut().find(|e| e.id == id) {
+            Some(stored) => stored,
+            None => return Ok(None),
+        };
+        for hours in self.hours().iter_mut() {
+            if hours.employee == stored.username {
+                hours.employee = updated.username.clone();
+            }
+        }
+        for timer in self.timers().iter_mut() {
+            if timer.employee == stored.username {
+                timer.employee = updated.username.clone();
+            }
+        }
+        for timesheet in self.timesheets().iter_mut() {
+            if timesheet.employee == stored.username {
+                timesheet.employee = updated.username.clone();
+            }
+        }
+        let previous = std::mem::replace(stored, updated.clone());
+        for employee in employees.iter_mut() {
+            if employee.manager.as_ref() == Some(&previous.username) {
+                employee.manager = Some(updated.username.clone());
+            }
+        }
+        Ok(Some(updated))
+    }
+
+    async fn delete_employee(&mut self, id: Uuid) -> Result<bool, Error> {
+        let mut employees = self.employees();
+        let employee = match employees.iter().find(|e| e.id == id) {
+            Some(employee) => employee,
+            None => return Ok(false),
+        };
+        if self.hours().iter().any(|h| h.employee == employee.username) {
+            return Err(Error::Conflict("The employee has logged hours".to_owned()));
+        }
+        let username = employee.username.clone();
+        self.timers().retain(|t| t.employee != username);
+        self.timesheets().retain(|t| t.employee != username);
+        employees.retain(|e| e.id != id);
+        for employee in employees.iter_mut() {
+            if employee.manager.as_ref() == Some(&username) {
+                employee.manager = None;
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl TimesheetRepo for MemoryDatabase {
+    async fn timesheet(
+        &mut self,
+        employee: &str,
+        week: NaiveDate,
+    ) -> Result<Option<Timesheet>, Error> {
+        Ok(self
+            .timesheets()
+            .iter()
+            .find(|t| t.employee == employee && t.week == week)
+            .cloned())
+    }
+
+    async fn list_timesheets(
+        &mut self,
+        query: &TimesheetQuery,
+        scope: &Scope,
+    ) -> Result<Vec<Timesheet>, Error> {
+        let mut timesheets: Vec<Timesheet> = self
+            .timesheets()
+            .iter()
+            .filter(|t| {
+                query.employee.as_ref().is_none_or(|e| e == &t.employee)
+                    && query.state.is_none_or(|state| state == t.state)
+                    && scope.includes(&t.employee)
+            })
+            .cloned()
+            .collect();
+        timesheets.sort_by(|a, b| a.week.cmp(&b.week).then(a.employee.cmp(&b.employee)));
+        Ok(timesheets)
+    }
+
+    async fn save_timesheet(&mut self, t: &Timesheet, from: TimesheetState) -> Result<(), Error> {
+        if !self.employees().iter().any(|e| e.username == t.employee) {
+            return Err(Error::Conflict("The employee does not exist".to_owned()));
+        }
+        let mut timesheets = self.timesheets();
+        match timesheets
+            .iter_mut()
+            .find(|stored| stored.employee == t.employee && stored.week == t.week)
+        {
+            Some(stored) if stored.state == from => *stored = t.clone(),
+            Some(_) => return Err(changed_concurrently()),
+            None => timesheets.push(t.clone()),
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PeriodRepo for MemoryDatabase {
+    async fn current_lock(&mut self) -> Result<Option<PeriodLock>, Error> {
+        Ok(self.period_locks().last().cloned())
+    }
+
+    async fn lock_history(&mut self) -> Result<Vec<PeriodLock>, Error> {
+        Ok(self.period_locks().iter().rev().cloned().collect())
+    }
+
+    async fn insert_lock(&mut self, lock: &PeriodLock) -> Result<(), Error> {
+        self.period_locks().push(lock.clone());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepo for MemoryDatabase {
+    async fn api_key_by_hash(&mut self, hash: &str) -> Result<Option<ApiKey>, Error> {
+        Ok(self
+            .api_keys()
+            .iter()
+            .find(|k| k.hash == hash && k.revoked_at.is_none())
+            .cloned())
+    }
+
+    async fn list_api_keys(&mut self) -> Result<Vec<ApiKey>, Error> {
+        Ok(self.api_keys().clone())
+    }
+
+    async fn insert_api_key(&mut self, key: &ApiKey) -> Result<(), Error> {
+        self.api_keys().push(key.clone());
+        Ok(())
+    }
+
+    async fn revoke_api_key(&mut self, id: Uuid) -> Result<Option<ApiKey>, Error> {
+        let mut api_keys = self.api_keys();
+        Ok(api_keys
+            .iter_mut()
+            .find(|k| k.id == id && k.revoked_at.is_none())
+            .map(|stored| {
+                stored.revoked_at = Some(Utc::now().trunc_subsecs(6));
+                stored.clone()
+            }))
+    }
+}
+
+#[async_trait]
+impl SessionRepo for MemoryDatabase {
+    async fn insert_login(
+        &mut self,
+        login: &LoginAttempt,
+        since: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        let mut logins = self.logins();
+        logins.retain(|l| l.created_at >= since);
+        logins.push(login.clone());
+        Ok(())
+    }
+
+    async fn take_login(
+        &mut self,
+        state: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<LoginAttempt>, Error> {
+        let mut logins = self.logins();
+        logins.retain(|l| l.created_at >= since);
+        Ok(logins
+            .iter()
+            .position(|l| l.state == state)
+            .map(|index| logins.remove(index)))
+    }
+
+    async fn insert_session(&mut self, session: &Session) -> Result<(), Error> {
+        let mut sessions = self.sessions();
+        sessions.retain(|s| s.expires_at > session.created_at);
+        sessions.push(session.clone());
+        Ok(())
+    }
+
+    async fn session_by_hash(
+        &mut self,
+        hash: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Session>, Error> {
+        Ok(self
+            .sessions()
+            .iter()
+            .find(|s| s.hash == hash && s.expires_at > now)
+            .cloned())
+    }
+
+    async fn delete_session(&mut self, hash: &str) -> Result<bool, Error> {
+        let mut sessions = self.sessions();
+        let before = sessions.len();
+        sessions.retain(|s| s.hash != hash);
+        Ok(sessions.len() < before)
+    }
+}
+
+fn duplicate_username() -> Error {
+    Error::Conflict("An employee with this username already exists".to_owned())
+}
+
+#[async_trait]
+impl ProjectRepo for MemoryDatabase {
+    async fn project_by_id(&mut self, id: Uuid) -> Result<Option<Project>, Error> {
+        Ok(self.projects().iter().find(|p| p.id == id).cloned())
+    }
+
+    async fn project_by_code(&mut self, code: &str) -> Result<Option<Project>, Error> {
+        Ok(self.projects().iter().find(|p| p.code == code).cloned())
+    }
+
+    async fn list_projects(&mut self, query: &ProjectQuery) -> Result<Vec<Project>, Error> {
+        let mut projects: Vec<Project> = self
+            .projects()
+            .iter()
+            .filter(|p| query.archived.is_none_or(|archived| p.archived == archived))
+            .cloned()
+            .collect();
+        projects.sort_by(|a, b| a.code.cmp(&b.code));
+        Ok(projects)
+    }
+
+    async fn insert_project(&mut self, p: NewProject) -> Result<Project, Error> {
+        let project = Project::new(p);
+        let mut projects = self.projects();
+        if projects.iter().any(|p| p.code == project.code) {
+            return Err(duplicate_code());
+        }
+        projects.push(project.clone());
+        Ok(project)
+    }
+
+    async fn update_project(&mut self, id: Uuid, p: NewProject) -> Result<Option<Project>, Error> {
+        let updated = Project {
+            id,
+            ..Project::new(p)
+        };
+        let mut projects = self.projects();
+        if projects
+            .iter()
+            .any(|p| p.id != id && p.code == updated.code)
+        {
+            return Err(duplicate_code());
+        }
+        let stored = match projects.iter_mut().find(|p| p.id == id) {
+            Some(stored) => stored,
+            None => return Ok(None),
+        };
+        for hours in self.hours().iter_mut() {
+            if hours.project == stored.code {
+                hours.project = updated.code.clone();
+            }
+        }
+        for timer in self.timers().iter_mut() {
+            if timer.project == stored.code {
+                timer.project = updated.code.clone();
+            }
+        }
+        *stored = updated.clone();
+        Ok(Some(updated))
+    }
+
+    async fn delete_project(&mut self, id: Uuid) -> Result<bool, Error> {
+        let mut projects = self.projects();
+        let project = match projects.iter().find(|p| p.id == id) {
+            Some(project) => project,
+            None => return Ok(false),
+        };
+        if self.hours().iter().any(|h| h.project == project.code) {
+            return Err(Error::Conflict("The project has logged hours".to_owned()));
+        }
+        let code = project.code.clone();
+        self.timers().retain(|t| t.project != code);
+        projects.retain(|p| p.id != id);
+        Ok(true)
+    }
+}
+
+fn duplicate_code() -> Error {
+    Error::Conflict("A project with this code already exists".to_owned())
+}
+
+#[async_trait]
+impl TimerRepo for MemoryDatabase {
+    async fn timer_by_id(&mut self, id: Uuid) -> Result<Option<Timer>, Error> {
+        Ok(self.timers().iter().find(|t| t.id == id).cloned())
+    }
+
+    async fn list_timers(
+        &mut self,
+        query: &TimerQuery,
+        scope: &Scope,
+    ) -> Result<Vec<Timer>, Error> {
+        let mut timers: Vec<Timer> = self
+            .timers()
+            .iter()
+            .filter(|t| {
+                query.employee.as_ref().is_none_or(|e| &t.employee == e)
+                    && scope.includes(&t.employee)
+            })
+            .cloned()
+            .collect();
+        timers.sort_by(|a, b| {
+            a.started_at
+                .cmp(&b.started_at)
+                .then_with(|| a.employee.cmp(&b.employee))
+        });
+        Ok(timers)
+    }
+
+    async fn insert_timer(&mut self, t: NewTimer) -> Result<Timer, Error> {
+        let timer = Timer::new(t);
+        let mut timers = self.timers();
+        if timers.iter().any(|t| t.employee == timer.employee) {
+            return Err(Error::Conflict(TIMER_RUNNING.to_owned()));
+        }
+        timers.push(timer.clone());
+        Ok(timer)
+    }
+
+    async fn delete_timer(&mut self, id: Uuid) -> Result<Option<Timer>, Error> {
+        let mut timers = self.timers();
+        let index = timers.iter().position(|t| t.id == id);
+        Ok(index.map(|index| timers.remove(index)))
+    }
+
+    async fn stop_timer(
+        &mut self,
+        id: Uuid,
+        h: NewHours,
+        actor: &str,
+    ) -> Result<Option<Hours>, Error> {
+        if self.delete_timer(id).await?.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(self.insert(h, actor).await?))
+    }
+}
+
+#[async_trait]
+impl ReportRepo for MemoryDatabase {
+    async fn summary(
+        &mut self,
+        query: &SummaryQuery,
+        scope: &Scope,
+    ) -> Result<Vec<SummaryRow>, Error> {
+        let mut rows: Vec<SummaryRow> = vec![];
+        for h in self.hours().iter().filter(|h| {
+            h.deleted_at.is_none()
+                && h.date >= query.from
+                && h.date <= query.to
+                && query.employee.as_ref().is_none_or(|e| *e == h.employee)
+                && query.project.as_ref().is_none_or(|p| *p == h.project)
+                && scope.includes(&h.employee)
+        }) {
+            let period = query.granularity.period_start(h.date);
+            match rows.iter_mut().find(|row| {
+                row.period == period
+                    && row.employee == h.employee
+                    && row.project == h.project
+                    && row.story_id == h.story_id
+            }) {
+                Some(row) => row.minutes += i64::from(h.minutes),
+                None => rows.push(SummaryRow {
+                    period,
+                    employee: h.employee.clone(),
+                    project: h.project.clone(),
+                    story_id: h.story_id.clone(),
+                    minutes: i64::from(h.minutes),
+                }),
+            }
+        }
+        rows.sort_by(|a, b| {
+            (a.period, &a.employee, &a.project)
+                .cmp(&(b.period, &b.employee, &b.project))
+                .then_with(|| match (&a.story_id, &b.story_id) {
+                    (Some(a), Some(b)) => a.cmp(b),
+                    (a, b) => b.is_some().cmp(&a.is_some()),
+                })
+        });
+        Ok(rows)
+    }
+}
+
+fn matches(query: &HoursQuery, hours: &Hours) -> bool {
+    hours.deleted_at.is_none()
+        && query.employee.as_ref().is_none_or(|e| *e == hours.employee)
+        && query.project.as_ref().is_none_or(|p| *p == hours.project)
+        && query
+            .story_id
+            .as_ref()
+            .is_none_or(|s| Some(s) == hours.story_id.as_ref())
+        && query.from.is_none_or(|from| hours.date >= from)
+        && query.to.is_none_or(|to| hours.date <= to)
+}
+
+/// Orders the same way Postgres does, `NULL`s are larger than any value.
+fn compare(field: SortField, order: SortOrder, a: &Hours, b: &Hours) -> Ordering {
+    let ordering = match field {
+        SortField::Date => a.date.cmp(&b.date),
+        SortField::Employee => a.employee.cmp(&b.employee),
+        SortField::Project => a.project.cmp(&b.project),
+        SortField::StoryId => match (&a.story_id, &b.story_id) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        },
+        SortField::Hours => a.minutes.cmp(&b.minutes),
+    };
+    match order {
+        SortOrder::Asc => ordering,
+        SortOrder::Desc => ordering.reverse(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::hours::audit::Operation;
+    use crate::reports::model::Granularity;
+
+    #[actix_rt::test]
+    async fn by_id_exists() {
+        let mut db = MemoryDatabase::default();
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+
+        assert_eq!(db.by_id(hours.id, &Scope::All).await.unwrap(), Some(hours));
+        assert_eq!(db.by_id(Uuid::new_v4(), &Scope::All).await.unwrap(), None);
+    }
+
+    #[actix_rt::test]
+    async fn clones_share_data() {
+        let mut db = MemoryDatabase::default();
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+
+        let mut other = db.connection().await.unwrap();
+        assert_eq!(
+            other.by_id(hours.id, &Scope::All).await.unwrap(),
+            Some(hours)
+        );
+    }
+
+    #[actix_rt::test]
+    async fn update_and_delete() {
+        let mut db = MemoryDatabase::default();
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+        let mut changed = get_hours();
+        changed.minutes = 180;
+
+        let result = db
+            .update(hours.id, changed, 1, &Scope::All, "tester")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result,
+            Some(Hours {
+                minutes: 180,
+                version: 2,
+                ..hours.clone()
+            })
+        );
+        assert!(matches!(
+            db.delete(hours.id, 1, &Scope::All, "tester").await,
+            Err(Error::PreconditionFailed(_))
+        ));
+        assert!(db.delete(hours.id, 2, &Scope::All, "tester").await.unwrap());
+        assert!(!db.delete(hours.id, 2, &Scope::All, "tester").await.unwrap());
+        assert_eq!(db.by_id(hours.id, &Scope::All).await.unwrap(), None);
+        assert_eq!(
+            db.history(hours.id, &Scope::All)
+                .await
+                .unwrap()
+                .iter()
+                .map(|r| r.operation)
+                .collect::<Vec<_>>(),
+            vec![Operation::Insert, Operation::Update, Operation::Delete]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn list_filters_sorts_and_paginates() {
+        let mut db = MemoryDatabase::default();
+
+        let mut inserted = vec![];
+        for day in 1..=5 {
+            let mut hours = get_hours();
+            hours.date = NaiveDate::from_ymd(2021, 10, day);
+            inserted.push(db.insert(hours, "tester").await.unwrap());
+        }
+        let mut other = get_hours();
+        other.employee = "other".to_owned();
+        db.insert(other, "tester").await.unwrap();
+
+        let query = HoursQuery {
+            employee: Some("employee".to_owned()),
+            order: SortOrder::Desc,
+            limit: Some(2),
+            offset: 1,
+            ..HoursQuery::default()
+        };
+        let result = db.list(&query, &Scope::All).await.unwrap();
+
+        assert_eq!(
+            result,
+            Page {
+                items: vec![inserted[3].clone(), inserted[2].clone()],
+                total: 5
+            }
+        );
+    }
+
+    #[actix_rt::test]
+    async fn list_sorts_missing_story_last() {
+        let mut db = MemoryDatabase::default();
+
+        let without_story = db.insert(get_hours(), "tester").await.unwrap();
+        let mut hours = get_hours();
+        hours.story_id = Some("story".to_owned());
+        let with_story = db.insert(hours, "tester").await.unwrap();
+
+        let query = HoursQuery {
+            sort: SortField::StoryId,
+            ..HoursQuery::default()
+        };
+        let result = db.list(&query, &Scope::All).await.unwrap();
+
+        assert_eq!(result.items, vec![with_story, without_story]);
+    }
+
+    #[actix_rt::test]
+    async fn summary_groups_by_period() {
+        let mut db = MemoryDatabase::default();
+
+        for (day, hours) in &[(4, 2), (10, 3), (11, 1)] {
+            let mut new_hours = get_hours();
+            new_hours.date = NaiveDate::from_ymd(2021, 10, *day);
+            new_hours.minutes = *hours * 60;
+            db.insert(new_hours, "tester").await.unwrap();
+        }
+
+        let query = SummaryQuery {
+            from: NaiveDate::from_ymd(2021, 10, 1),
+            to: NaiveDate::from_ymd(2021, 10, 31),
+            granularity: Granularity::Week,
+            employee: None,
+            project: None,
+        };
+        let result = db.summary(&query, &Scope::All).await.unwrap();
+
+        assert_eq!(
+            result
+                .iter()
+                .map(|row| (row.period, row.minutes))
+                .collect::<Vec<_>>(),
+            vec![
+                (NaiveDate::from_ymd(2021, 10, 4), 300),
+                (NaiveDate::from_ymd(2021, 10, 11), 60)
+            ]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn idempotency_keys_are_used_once_per_window() {
+        let mut db = MemoryDatabase::default();
+        let since = Utc::now() - chrono::Duration::hours(1);
+
+        let hours = db
+            .insert_idempotent(get_hours(), "employee", "key", since, "tester")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            db.insert_idempotent(get_hours(), "employee", "key", since, "tester")
+                .await,
+            Err(Error::Conflict(_))
+        ));
+        assert_eq!(
+            db.idempotency_key("employee", "key", since)
+                .await
+                .unwrap()
+                .unwrap()
+                .response,
+            hours
+        );
+        assert_eq!(
+            db.list(&HoursQuery::default(), &Scope::All)
+                .await
+                .unwrap()
+                .total,
+            1
+        );
+    }
+
+    #[actix_rt::test]
+    async fn trash_and_restore() {
+        let mut db = MemoryDatabase::default();
+
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+        db.delete(hours.id, 1, &Scope::All, "tester").await.unwrap();
+
+        assert_eq!(
+            db.list(&HoursQuery::default(), &Scope::All)
+                .await
+                .unwrap()
+                .total,
+            0
+        );
+        assert_eq!(db.trash(&Scope::All).await.unwrap().len(), 1);
+        let restored = Hours {
+            version: 3,
+            ..hours
+        };
+        assert_eq!(
+            db.restore(hours.id, 2, &Scope::All, "tester")
+                .await
+                .unwrap(),
+            Some(restored.clone())
+        );
+        assert_eq!(
+            db.by_id(hours.id, &Scope::All).await.unwrap(),
+            Some(restored)
+        );
+    }
+
+    #[actix_rt::test]
+    async fn update_employee_renames_hours() {
+        let mut db = MemoryDatabase::default();
+
+        let employee = db
+            .insert_employee(NewEmployee {
+                username: "employee".to_owned(),
+                name: "Employee".to_owned(),
+                manager: None,
+            })
+            .await
+            .unwrap();
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+
+        db.update_employee(
+            employee.id,
+            NewEmployee {
+                username: "renamed".to_owned(),
+                name: "Employee".to_owned(),
+                manager: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            db.by_id(hours.id, &Scope::All)
+                .await
+                .unwrap()
+                .unwrap()
+                .employee,
+            "renamed"
+        );
+        assert!(matches!(
+            db.delete_employee(employee.id).await,
+            Err(Error::Conflict(_))
+        ));
+    }
+
+    fn get_hours() -> NewHours {
+        NewHours {
+            employee: "employee".to_owned(),
+            date: NaiveDate::from_ymd(2021, 10, 9),
+            project: "project".to_owned(),
+            story_id: None,
+            description: "description".to_owned(),
+            minutes: 60,
+            start_time: None,
+            end_time: None,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::convert::TryFrom;
+
+use async_trait::async_trait;
+
+use super::PgConnectionLike;
+use crate::error::Error;
+use crate::periods::PeriodLock;
+
+#[async_trait]
+pub trait PeriodRepo: Send {
+    /// The latest change of the cutoff, which is the one in effect.
+    async fn current_lock(&mut self) -> Result<Option<PeriodLock>, Error>;
+    /// Every change of the cutoff, latest first.
+    async fn lock_history(&mut self) -> Result<Vec<PeriodLock>, Error>;
+    async fn insert_lock(&mut self, lock: &PeriodLock) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl<C: PgConnectionLike> PeriodRepo for C {
+    async fn current_lock(&mut self) -> Result<Option<PeriodLock>, Error> {
+        Ok(
+            sqlx::query("SELECT * FROM period_locks ORDER BY changed_at DESC LIMIT 1")
+                .try_map(PeriodLock::try_from)
+                .fetch_optional(&mut **self)
+                .await?,
+        )
+    }
+
+    async fn lock_history(&mut self) -> Result<Vec<PeriodLock>, Error> {
+        Ok(
+            sqlx::query("SELECT * FROM period_locks ORDER BY changed_at DESC")
+                .try_map(PeriodLock::try_from)
+                .fetch_all(&mut **self)
+                .await?,
+        )
+    }
+
+    async fn insert_lock(&mut self, lock: &PeriodLock) -> Result<(), Error> {
+        let sql = "INSERT INTO period_locks (id, locked_until, reason, changed_by, changed_at)
+            VALUES ($1, $2, $3, $4, $5)";
+        sqlx::query(sql)
+            .bind(lock.id)
+            .bind(lock.locked_until)
+            .bind(lock.reason.clone())
+            .bind(lock.changed_by.clone())
+            .bind(lock.changed_at)
+            .execute(&mut **self)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate};
+
+    use super::*;
+    use crate::periods::NewPeriodLock;
+    use crate::test_utils;
+
+    #[actix_rt::test]
+    async fn latest_lock_is_current() {
+        let mut db = test_utils::internal::get_db_connection().await;
+        assert_eq!(db.current_lock().await.unwrap(), None);
+
+        let first = get_lock(Some(NaiveDate::from_ymd(2021, 10, 31)));
+        let mut reopened = get_lock(None);
+        reopened.changed_at = first.changed_at + Duration::seconds(1);
+        db.insert_lock(&first).await.unwrap();
+        db.insert_lock(&reopened).await.unwrap();
+
+        assert_eq!(db.current_lock().await.unwrap(), Some(reopened.clone()));
+        assert_eq!(db.lock_history().await.unwrap(), vec![reopened, first]);
+    }
+
+    fn get_lock(locked_until: Option<NaiveDate>) -> PeriodLock {
+        PeriodLock::new(
+            NewPeriodLock {
+                locked_until,
+                reason: Some("month end".to_owned()),
+            },
+            "admin".to_owned(),
+        )
+    }
+}
@@ -0,0 +1,228 @@
+use std::convert::TryFrom;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::PgConnectionLike;
+use crate::error::{conflict_on_constraint, Error};
+use crate::projects::{NewProject, Project, ProjectQuery};
+
+const DUPLICATE_CODE: &str = "A project with this code already exists";
+
+#[async_trait]
+pub trait ProjectRepo: Send {
+    async fn project_by_id(&mut self, id: Uuid) -> Result<Option<Project>, Error>;
+    async fn project_by_code(&mut self, code: &str) -> Result<Option<Project>, Error>;
+    async fn list_projects(&mut self, query: &ProjectQuery) -> Result<Vec<Project>, Error>;
+    async fn insert_project(&mut self, p: NewProject) -> Result<Project, Error>;
+    /// Changing the code also changes it on the hours entries of the project.
+    async fn update_project(&mut self, id: Uuid, p: NewProject) -> Result<Option<Project>, Error>;
+    /// Projects that have hours logged to them can not be deleted, only archived.
+    async fn delete_project(&mut self, id: Uuid) -> Result<bool, Error>;
+}
+
+#[async_trait]
+impl<C: PgConnectionLike> ProjectRepo for C {
+    async fn project_by_id(&mut self, id: Uuid) -> Result<Option<Project>, Error> {
+        Ok(sqlx::query("SELECT * FROM projects WHERE id = $1")
+            .bind(id)
+            .try_map(Project::try_from)
+            .fetch_optional(&mut **self)
+            .await?)
+    }
+
+    async fn project_by_code(&mut self, code: &str) -> Result<Option<Project>, Error> {
+        Ok(sqlx::query("SELECT * FROM projects WHERE code = $1")
+            .bind(code)
+            .try_map(Project::try_from)
+            .fetch_optional(&mut **self)
+            .await?)
+    }
+
+    async fn list_projects(&mut self, query: &ProjectQuery) -> Result<Vec<Project>, Error> {
+        Ok(sqlx::query(
+            "SELECT * FROM projects WHERE ($1::BOOLEAN IS NULL OR archived = $1) ORDER BY code",
+        )
+        .bind(query.archived)
+        .try_map(Project::try_from)
+        .fetch_all(&mut **self)
+        .await?)
+    }
+
+    async fn insert_project(&mut self, p: NewProject) -> Result<Project, Error> {
+        let project = Project::new(p);
+        let sql = "INSERT INTO projects (id, code, name, start_date, end_date, archived)
+            VALUES ($1, $2, $3, $4, $5, $6)";
+        sqlx::query(sql)
+            .bind(project.id)
+            .bind(project.code.clone())
+            .bind(project.name.clone())
+            .bind(project.start_date)
+            .bind(project.end_date)
+            .bind(project.archived)
+            .execute(&mut **self)
+            .await
+            .map_err(|e| conflict_on_constraint(e, DUPLICATE_CODE))?;
+        Ok(project)
+    }
+
+    async fn update_project(&mut self, id: Uuid, p: NewProject) -> Result<Option<Project>, Error> {
+        let project = Project {
+            id,
+            ..Project::new(p)
+        };
+        let sql = "UPDATE projects
+            SET code = $2, name = $3, start_date = $4, end_date = $5, archived = $6
+            WHERE id = $1
+            RETURNING *";
+        sqlx::query(sql)
+            .bind(project.id)
+            .bind(project.code)
+            .bind(project.name)
+            .bind(project.start_date)
+            .bind(project.end_date)
+            .bind(project.archived)
+            .try_map(Project::try_from)
+            .fetch_optional(&mut **self)
+            .await
+            .map_err(|e| conflict_on_constraint(e, DUPLICATE_CODE))
+    }
+
+    async fn delete_project(&mut self, id: Uuid) -> Result<bool, Error> {
+        Ok(
+            sqlx::query("DELETE FROM projects WHERE id = $1 RETURNING 1")
+                .bind(id)
+                .fetch_optional(&mut **self)
+                .await
+                .map_err(|e| conflict_on_constraint(e, "The project has logged hours"))?
+                .is_some(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::db::HoursRepo;
+    use crate::hours::access::Scope;
+    use crate::hours::NewHours;
+    use crate::test_utils;
+
+    #[actix_rt::test]
+    async fn insert_and_find() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let project = db.insert_project(get_project("CB-1", false)).await.unwrap();
+
+        assert_eq!(
+            db.project_by_id(project.id).await.unwrap(),
+            Some(project.clone())
+        );
+        assert_eq!(
+            db.project_by_code("CB-1").await.unwrap(),
+            Some(project.clone())
+        );
+        assert!(db.project_by_code("cb-1").await.unwrap().is_none());
+    }
+
+    #[actix_rt::test]
+    async fn insert_duplicate_code() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        db.insert_project(get_project("CB-1", false)).await.unwrap();
+        let result = db.insert_project(get_project("CB-1", false)).await;
+
+        assert!(matches!(result, Err(Error::Conflict(_))));
+    }
+
+    #[actix_rt::test]
+    async fn list_by_archived() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let active = db.insert_project(get_project("CB-1", false)).await.unwrap();
+        let archived = db.insert_project(get_project("CB-2", true)).await.unwrap();
+
+        let all = db.list_projects(&ProjectQuery::default()).await.unwrap();
+        let only_archived = db
+            .list_projects(&ProjectQuery {
+                archived: Some(true),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(all, vec![active, archived.clone()]);
+        assert_eq!(only_archived, vec![archived]);
+    }
+
+    #[actix_rt::test]
+    async fn update_renames_hours() {
+        let mut db = test_utils::internal::get_db_connection().await;
+        test_utils::internal::seed(&mut db).await;
+
+        let project = db.project_by_code("project").await.unwrap().unwrap();
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+
+        let result = db
+            .update_project(project.id, get_project("CB-1", true))
+            .await
+            .unwrap();
+
+        assert_eq!(result.unwrap().code, "CB-1");
+        assert_eq!(
+            db.by_id(hours.id, &Scope::All)
+                .await
+                .unwrap()
+                .unwrap()
+                .project,
+            "CB-1"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn delete_with_hours() {
+        let mut db = test_utils::internal::get_db_connection().await;
+        test_utils::internal::seed(&mut db).await;
+
+        let project = db.project_by_code("project").await.unwrap().unwrap();
+        db.insert(get_hours(), "tester").await.unwrap();
+
+        let result = db.delete_project(project.id).await;
+
+        assert!(matches!(result, Err(Error::Conflict(_))));
+    }
+
+    #[actix_rt::test]
+    async fn delete_without_hours() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let project = db.insert_project(get_project("CB-1", false)).await.unwrap();
+
+        assert!(db.delete_project(project.id).await.unwrap());
+        assert!(!db.delete_project(project.id).await.unwrap());
+    }
+
+    fn get_project(code: &str, archived: bool) -> NewProject {
+        NewProject {
+            code: code.to_owned(),
+            name: "Project".to_owned(),
+            start_date: None,
+            end_date: None,
+            archived,
+        }
+    }
+
+    fn get_hours() -> NewHours {
+        NewHours {
+            employee: "employee".to_owned(),
+            date: NaiveDate::from_ymd(2021, 10, 9),
+            project: "project".to_owned(),
+            story_id: None,
+            description: "description".to_owned(),
+            minutes: 60,
+            start_time: None,
+            end_time: None,
+        }
+    }
+}
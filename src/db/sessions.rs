@@ -0,0 +1,201 @@
+use std::convert::TryFrom;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::PgConnectionLike;
+use crate::error::Error;
+use crate::sessions::{LoginAttempt, Session};
+
+#[async_trait]
+pub trait SessionRepo: Send {
+    /// Logins started before `since` are removed along the way.
+    async fn insert_login(
+        &mut self,
+        login: &LoginAttempt,
+        since: DateTime<Utc>,
+    ) -> Result<(), Error>;
+    /// Removes the login with the given state and returns it, if it was started after `since`.
+    /// Logins started before `since` are forgotten.
+    async fn take_login(
+        &mut self,
+        state: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<LoginAttempt>, Error>;
+    /// Sessions that have expired are removed along the way.
+    async fn insert_session(&mut self, session: &Session) -> Result<(), Error>;
+    /// The session with the given hash, unless it has expired by `now`.
+    async fn session_by_hash(
+        &mut self,
+        hash: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Session>, Error>;
+    async fn delete_session(&mut self, hash: &str) -> Result<bool, Error>;
+}
+
+#[async_trait]
+impl<C: PgConnectionLike> SessionRepo for C {
+    async fn insert_login(
+        &mut self,
+        login: &LoginAttempt,
+        since: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        sqlx::query("DELETE FROM oidc_logins WHERE created_at < $1")
+            .bind(since)
+            .execute(&mut **self)
+            .await?;
+        let sql = "INSERT INTO oidc_logins (state, code_verifier, nonce, created_at)
+            VALUES ($1, $2, $3, $4)";
+        sqlx::query(sql)
+            .bind(login.state.clone())
+            .bind(login.code_verifier.clone())
+            .bind(login.nonce.clone())
+            .bind(login.created_at)
+            .execute(&mut **self)
+            .await?;
+        Ok(())
+    }
+
+    async fn take_login(
+        &mut self,
+        state: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<LoginAttempt>, Error> {
+        sqlx::query("DELETE FROM oidc_logins WHERE created_at < $1")
+            .bind(since)
+            .execute(&mut **self)
+            .await?;
+        Ok(
+            sqlx::query("DELETE FROM oidc_logins WHERE state = $1 RETURNING *")
+                .bind(state)
+                .try_map(LoginAttempt::try_from)
+                .fetch_optional(&mut **self)
+                .await?,
+        )
+    }
+
+    async fn insert_session(&mut self, session: &Session) -> Result<(), Error> {
+        sqlx::query("DELETE FROM sessions WHERE expires_at <= $1")
+            .bind(session.created_at)
+            .execute(&mut **self)
+            .await?;
+        let sql = "INSERT INTO sessions (hash, subject, role, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5)";
+        sqlx::query(sql)
+            .bind(session.hash.clone())
+            .bind(session.subject.clone())
+            .bind(session.role.as_str())
+            .bind(session.created_at)
+            .bind(session.expires_at)
+            .execute(&mut **self)
+            .await?;
+        Ok(())
+    }
+
+    async fn session_by_hash(
+        &mut self,
+        hash: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Session>, Error> {
+        Ok(
+            sqlx::query("SELECT * FROM sessions WHERE hash = $1 AND expires_at > $2")
+                .bind(hash)
+                .bind(now)
+                .try_map(Session::try_from)
+                .fetch_optional(&mut **self)
+                .await?,
+        )
+    }
+
+    async fn delete_session(&mut self, hash: &str) -> Result<bool, Error> {
+        let result = sqlx::query("DELETE FROM sessions WHERE hash = $1")
+            .bind(hash)
+            .execute(&mut **self)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::auth::{Identity, Role};
+    use crate::test_utils;
+
+    #[actix_rt::test]
+    async fn logins_are_taken_once() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let login = LoginAttempt::generate();
+        let since = login.created_at - Duration::minutes(10);
+        db.insert_login(&login, since).await.unwrap();
+
+        assert_eq!(
+            db.take_login(&login.state, since).await.unwrap(),
+            Some(login.clone())
+        );
+        assert_eq!(db.take_login(&login.state, since).await.unwrap(), None);
+    }
+
+    #[actix_rt::test]
+    async fn expired_logins_are_not_taken() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let login = LoginAttempt::generate();
+        db.insert_login(&login, login.created_at - Duration::minutes(10))
+            .await
+            .unwrap();
+
+        let since = login.created_at + Duration::seconds(1);
+        assert_eq!(db.take_login(&login.state, since).await.unwrap(), None);
+    }
+
+    #[actix_rt::test]
+    async fn expired_logins_are_removed_by_new_ones() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let expired = LoginAttempt::generate();
+        db.insert_login(&expired, expired.created_at - Duration::minutes(10))
+            .await
+            .unwrap();
+        let login = LoginAttempt::generate();
+        let since = expired.created_at + Duration::microseconds(1);
+        db.insert_login(&login, since).await.unwrap();
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM oidc_logins")
+            .fetch_one(&mut db)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[actix_rt::test]
+    async fn sessions_last_until_they_expire_or_are_deleted() {
+        let mut db = test_utils::internal::get_db_connection().await;
+
+        let identity = Identity {
+            subject: "employee".to_owned(),
+            role: Role::Manager,
+        };
+        let (id, session) = Session::generate(identity, Duration::hours(1));
+        db.insert_session(&session).await.unwrap();
+
+        let hash = crate::api_keys::hash(&id);
+        assert_eq!(
+            db.session_by_hash(&hash, session.created_at).await.unwrap(),
+            Some(session.clone())
+        );
+        assert_eq!(
+            db.session_by_hash(&hash, session.expires_at).await.unwrap(),
+            None
+        );
+        assert!(db.delete_session(&hash).await.unwrap());
+        assert!(!db.delete_session(&hash).await.unwrap());
+        assert_eq!(
+            db.session_by_hash(&hash, session.created_at).await.unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use std::convert::TryFrom;
+
+use async_trait::async_trait;
+use sqlx::Connection;
+use uuid::Uuid;
+
+use super::{insert_audit, insert_hours, PgConnectionLike};
+use crate::error::{conflict_on_constraint, Error};
+use crate::hours::access::Scope;
+use crate::hours::audit::AuditRecord;
+use crate::hours::{Hours, NewHours};
+use crate::timers::{NewTimer, Timer, TimerQuery};
+
+pub(crate) const TIMER_RUNNING: &str = "The employee already has a running timer";
+
+#[async_trait]
+pub trait TimerRepo: Send {
+    async fn timer_by_id(&mut self, id: Uuid) -> Result<Option<Timer>, Error>;
+    /// The running timers of the employees in `scope`.
+    async fn list_timers(&mut self, query: &TimerQuery, scope: &Scope)
+        -> Result<Vec<Timer>, Error>;
+    /// Starts a timer, an employee can only have one running at a time.
+    async fn insert_timer(&mut self, t: NewTimer) -> Result<Timer, Error>;
+    /// Removes the timer, returning it if it was still running.
+    async fn delete_timer(&mut self, id: Uuid) -> Result<Option<Timer>, Error>;
+    /// Removes the timer and logs `h` for it, both or neither. Returns the entry if the timer was
+    /// still running.
+    async fn stop_timer(
+        &mut self,
+        id: Uuid,
+        h: NewHours,
+        actor: &str,
+    ) -> Result<Option<Hours>, Error>;
+}
+
+#[async_trait]
+impl<C: PgConnectionLike> TimerRepo for C {
+    async fn timer_by_id(&mut self, id: Uuid) -> Result<Option<Timer>, Error> {
+        Ok(sqlx::query("SELECT * FROM timers WHERE id = $1")
+            .bind(id)
+            .try_map(Timer::try_from)
+            .fetch_optional(&mut **self)
+            .await?)
+    }
+
+    async fn list_timers(
+        &mut self,
+        query: &TimerQuery,
+        scope: &Scope,
+    ) -> Result<Vec<Timer>, Error> {
+        Ok(sqlx::query(
+            "SELECT * FROM timers
+            WHERE ($1::VARCHAR IS NULL OR employee = $1)
+                AND ($2::VARCHAR[] IS NULL OR employee = ANY($2))
+            ORDER BY started_at, employee",
+        )
+        .bind(query.employee.clone())
+        .bind(scope.employees())
+        .try_map(Timer::try_from)
+        .fetch_all(&mut **self)
+        .await?)
+    }
+
+    async fn insert_timer(&mut self, t: NewTimer) -> Result<Timer, Error> {
+        let timer = Timer::new(t);
+        let sql = "INSERT INTO timers (id, employee, project, story_id, description, started_at)
+            VALUES ($1, $2, $3, $4, $5, $6)";
+        sqlx::query(sql)
+            .bind(timer.id)
+            .bind(timer.employee.clone())
+            .bind(timer.project.clone())
+            .bind(timer.story_id.clone())
+            .bind(timer.description.clone())
+            .bind(timer.started_at)
+            .execute(&mut **self)
+            .await
+            .map_err(|e| conflict_on_constraint(e, TIMER_RUNNING))?;
+        Ok(timer)
+    }
+
+    async fn delete_timer(&mut self, id: Uuid) -> Result<Option<Timer>, Error> {
+        Ok(sqlx::query("DELETE FROM timers WHERE id = $1 RETURNING *")
+            .bind(id)
+            .try_map(Timer::try_from)
+            .fetch_optional(&mut **self)
+            .await?)
+    }
+
+    async fn stop_timer(
+        &mut self,
+        id: Uuid,
+        h: NewHours,
+        actor: &str,
+    ) -> Result<Option<Hours>, Error> {
+        let mut tx = self.begin().await?;
+        let stopped = sqlx::query("DELETE FROM timers WHERE id = $1 RETURNING id")
+            .bind(id)
+            .fetch_optional(&mut tx)
+            .await?;
+        if stopped.is_none() {
+            return Ok(None);
+        }
+        let hours = insert_hours(&mut tx, h, actor).await?;
+        insert_audit(&mut tx, &AuditRecord::inserted(actor, &hours)).await?;
+        tx.commit().await?;
+        Ok(Some(hours))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::pool::PoolConnection;
+    use sqlx::Postgres;
+
+    use super::*;
+    use crate::test_utils;
+
+    #[actix_rt::test]
+    async fn insert_and_find() {
+        let mut db = get_db().await;
+
+        let timer = db.insert_timer(get_timer("employee")).await.unwrap();
+
+        assert_eq!(db.timer_by_id(timer.id).await.unwrap(), Some(timer.clone()));
+        assert_eq!(
+            db.list_timers(&TimerQuery::default(), &Scope::All)
+                .await
+                .unwrap(),
+            vec![timer]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn one_running_timer_per_employee() {
+        let mut db = get_db().await;
+
+        db.insert_timer(get_timer("employee")).await.unwrap();
+        let result = db.insert_timer(get_timer("employee")).await;
+
+        match result {
+            Err(Error::Conflict(message)) => assert_eq!(message, TIMER_RUNNING),
+            _ => panic!("Expected a conflict."),
+        }
+        db.insert_timer(get_timer("other")).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn list_filtered_by_employee() {
+        let mut db = get_db().await;
+
+        db.insert_timer(get_timer("employee")).await.unwrap();
+        let other = db.insert_timer(get_timer("other")).await.unwrap();
+
+        let result = db
+            .list_timers(
+                &TimerQuery {
+                    employee: Some("other".to_owned()),
+                },
+                &Scope::All,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result, vec![other]);
+    }
+
+    #[actix_rt::test]
+    async fn list_within_scope() {
+        let mut db = get_db().await;
+
+        let own = db.insert_timer(get_timer("employee")).await.unwrap();
+        db.insert_timer(get_timer("other")).await.unwrap();
+
+        let result = db
+            .list_timers(
+                &TimerQuery::default(),
+                &Scope::Employees(vec!["employee".to_owned()]),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result, vec![own]);
+    }
+
+    #[actix_rt::test]
+    async fn delete_returns_timer_once() {
+        let mut db = get_db().await;
+
+        let timer = db.insert_timer(get_timer("employee")).await.unwrap();
+
+        assert_eq!(
+            db.delete_timer(timer.id).await.unwrap(),
+            Some(timer.clone())
+        );
+        assert_eq!(db.delete_timer(timer.id).await.unwrap(), None);
+    }
+
+    #[actix_rt::test]
+    async fn stop_logs_the_hours_once() {
+        let mut db = get_db().await;
+
+        let timer = db.insert_timer(get_timer("employee")).await.unwrap();
+
+        let hours = db
+            .stop_timer(timer.id, timer.to_hours(timer.started_at), "employee")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(hours.employee, "employee");
+        assert_eq!(db.timer_by_id(timer.id).await.unwrap(), None);
+        assert_eq!(
+            db.stop_timer(timer.id, timer.to_hours(timer.started_at), "employee")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[actix_rt::test]
+    async fn stop_keeps_the_timer_when_the_hours_can_not_be_logged() {
+        let mut db = get_db().await;
+
+        let timer = db.insert_timer(get_timer("employee")).await.unwrap();
+        let mut new_hours = timer.to_hours(timer.started_at);
+        new_hours.project = "unknown".to_owned();
+
+        assert!(db
+            .stop_timer(timer.id, new_hours, "employee")
+            .await
+            .is_err());
+        assert_eq!(db.timer_by_id(timer.id).await.unwrap(), Some(timer));
+    }
+
+    async fn get_db() -> PoolConnection<Postgres> {
+        let mut db = test_utils::internal::get_db_connection().await;
+        test_utils::internal::seed(&mut db).await;
+        db
+    }
+
+    fn get_timer(employee: &str) -> NewTimer {
+        NewTimer {
+            employee: employee.to_owned(),
+            project: "project".to_owned(),
+            story_id: None,
+            description: "description".to_owned(),
+        }
+    }
+}
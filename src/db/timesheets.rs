@@ -0,0 +1,204 @@
+use std::convert::TryFrom;
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+
+use super::PgConnectionLike;
+use crate::error::{conflict_on_constraint, Error};
+use crate::hours::access::Scope;
+use crate::timesheets::model::TimesheetState;
+use crate::timesheets::{Timesheet, TimesheetQuery};
+
+#[async_trait]
+pub trait TimesheetRepo: Send {
+    /// The timesheet of the week starting on Monday `week`, if it has ever left the draft state.
+    async fn timesheet(
+        &mut self,
+        employee: &str,
+        week: NaiveDate,
+    ) -> Result<Option<Timesheet>, Error>;
+    /// The stored timesheets of the employees in `scope`.
+    async fn list_timesheets(
+        &mut self,
+        query: &TimesheetQuery,
+        scope: &Scope,
+    ) -> Result<Vec<Timesheet>, Error>;
+    /// Inserts the timesheet or replaces the stored one of the same employee and week, as long
+    /// as that one is still in the state `from`. A draft is never stored, so saving from a draft
+    /// only inserts.
+    async fn save_timesheet(&mut self, t: &Timesheet, from: TimesheetState) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl<C: PgConnectionLike> TimesheetRepo for C {
+    async fn timesheet(
+        &mut self,
+        employee: &str,
+        week: NaiveDate,
+    ) -> Result<Option<Timesheet>, Error> {
+        Ok(
+            sqlx::query("SELECT * FROM timesheets WHERE employee = $1 AND week = $2")
+                .bind(employee)
+                .bind(week)
+                .try_map(Timesheet::try_from)
+                .fetch_optional(&mut **self)
+                .await?,
+        )
+    }
+
+    async fn list_timesheets(
+        &mut self,
+        query: &TimesheetQuery,
+        scope: &Scope,
+    ) -> Result<Vec<Timesheet>, Error> {
+        Ok(sqlx::query(
+            "SELECT * FROM timesheets
+            WHERE ($1::VARCHAR IS NULL OR employee = $1)
+                AND ($2::VARCHAR IS NULL OR state = $2)
+                AND ($3::VARCHAR[] IS NULL OR employee = ANY($3))
+            ORDER BY week, employee",
+        )
+        .bind(query.employee.clone())
+        .bind(query.state.map(|state| state.as_str()))
+        .bind(scope.employees())
+        .try_map(Timesheet::try_from)
+        .fetch_all(&mut **self)
+        .await?)
+    }
+
+    async fn save_timesheet(&mut self, t: &Timesheet, from: TimesheetState) -> Result<(), Error> {
+        let sql = "INSERT INTO timesheets (employee, week, state, reason)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (employee, week) DO UPDATE SET state = $3, reason = $4
+            WHERE timesheets.state = $5";
+        let result = sqlx::query(sql)
+            .bind(t.employee.clone())
+            .bind(t.week)
+            .bind(t.state.as_str())
+            .bind(t.reason.clone())
+            .bind(from.as_str())
+            .execute(&mut **self)
+            .await
+            .map_err(|e| conflict_on_constraint(e, "The employee does not exist"))?;
+        if result.rows_affected() == 0 {
+            return Err(changed_concurrently());
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn changed_concurrently() -> Error {
+    Error::Conflict("The timesheet was changed by another request".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::pool::PoolConnection;
+    use sqlx::Postgres;
+
+    use super::*;
+    use crate::test_utils;
+
+    #[actix_rt::test]
+    async fn save_and_find() {
+        let mut db = get_db().await;
+
+        let mut timesheet = get_timesheet("employee", TimesheetState::Submitted);
+        db.save_timesheet(&timesheet, TimesheetState::Draft)
+            .await
+            .unwrap();
+        timesheet.state = TimesheetState::Rejected;
+        timesheet.reason = Some("reason".to_owned());
+        db.save_timesheet(&timesheet, TimesheetState::Submitted)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.timesheet("employee", timesheet.week).await.unwrap(),
+            Some(timesheet.clone())
+        );
+        assert_eq!(db.timesheet("other", timesheet.week).await.unwrap(), None);
+    }
+
+    #[actix_rt::test]
+    async fn list_filtered_by_state() {
+        let mut db = get_db().await;
+
+        let submitted = get_timesheet("employee", TimesheetState::Submitted);
+        db.save_timesheet(&submitted, TimesheetState::Draft)
+            .await
+            .unwrap();
+        db.save_timesheet(
+            &get_timesheet("other", TimesheetState::Approved),
+            TimesheetState::Draft,
+        )
+        .await
+        .unwrap();
+
+        let query = TimesheetQuery {
+            employee: None,
+            state: Some(TimesheetState::Submitted),
+        };
+        let result = db.list_timesheets(&query, &Scope::All).await.unwrap();
+
+        assert_eq!(result, vec![submitted]);
+        let other = Scope::Employees(vec!["other".to_owned()]);
+        assert!(db.list_timesheets(&query, &other).await.unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn save_for_unknown_employee() {
+        let mut db = get_db().await;
+
+        let result = db
+            .save_timesheet(
+                &get_timesheet("nobody", TimesheetState::Submitted),
+                TimesheetState::Draft,
+            )
+            .await;
+
+        assert!(matches!(result, Err(Error::Conflict(_))));
+    }
+
+    #[actix_rt::test]
+    async fn save_from_a_state_the_timesheet_has_left() {
+        let mut db = get_db().await;
+        let submitted = get_timesheet("employee", TimesheetState::Submitted);
+        db.save_timesheet(&submitted, TimesheetState::Draft)
+            .await
+            .unwrap();
+        let approved = get_timesheet("employee", TimesheetState::Approved);
+        db.save_timesheet(&approved, TimesheetState::Submitted)
+            .await
+            .unwrap();
+
+        let mut rejected = get_timesheet("employee", TimesheetState::Rejected);
+        rejected.reason = Some("reason".to_owned());
+        let result = db
+            .save_timesheet(&rejected, TimesheetState::Submitted)
+            .await;
+
+        assert!(matches!(result, Err(Error::Conflict(_))));
+        assert_eq!(
+            db.timesheet("employee", approved.week).await.unwrap(),
+            Some(approved)
+        );
+        let result = db.save_timesheet(&submitted, TimesheetState::Draft).await;
+        assert!(matches!(result, Err(Error::Conflict(_))));
+    }
+
+    async fn get_db() -> PoolConnection<Postgres> {
+        let mut db = test_utils::internal::get_db_connection().await;
+        test_utils::internal::seed(&mut db).await;
+        db
+    }
+
+    fn get_timesheet(employee: &str, state: TimesheetState) -> Timesheet {
+        Timesheet {
+            employee: employee.to_owned(),
+            week: NaiveDate::from_ymd(2021, 10, 4),
+            state,
+            reason: None,
+        }
+    }
+}
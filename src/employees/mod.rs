@@ -0,0 +1,88 @@
+use actix_web::{
+    web::{self, Data, Path},
+    HttpResponse,
+};
+use uuid::Uuid;
+
+use crate::auth::Identity;
+use crate::db::{Database, EmployeeRepo};
+use crate::error::{Error, FieldValidationError, Validated};
+
+mod model;
+
+pub use model::{Employee, NewEmployee};
+
+pub async fn list_employees<D: Database>(db: Data<D>) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    Ok(HttpResponse::Ok().json(connection.list_employees().await?))
+}
+
+pub async fn get_employee<D: Database>(id: Path<Uuid>, db: Data<D>) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    let id = id.into_inner();
+    Ok(match connection.employee_by_id(id).await? {
+        Some(employee) => HttpResponse::Ok().json(employee),
+        None => HttpResponse::NotFound().json(id),
+    })
+}
+
+pub async fn create_employee<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    json: web::Json<NewEmployee>,
+) -> Result<HttpResponse, Error> {
+    identity.require_admin()?;
+    let new_employee = json.into_inner().normalized();
+    new_employee.validate()?;
+    let mut connection = db.connection().await?;
+    check_manager(&mut connection, &new_employee).await?;
+    let employee = connection.insert_employee(new_employee).await?;
+    Ok(HttpResponse::Created().json(employee))
+}
+
+pub async fn replace_employee<D: Database>(
+    id: Path<Uuid>,
+    db: Data<D>,
+    identity: Identity,
+    json: web::Json<NewEmployee>,
+) -> Result<HttpResponse, Error> {
+    identity.require_admin()?;
+    let new_employee = json.into_inner().normalized();
+    new_employee.validate()?;
+    let mut connection = db.connection().await?;
+    check_manager(&mut connection, &new_employee).await?;
+    let id = id.into_inner();
+    Ok(match connection.update_employee(id, new_employee).await? {
+        Some(employee) => HttpResponse::Ok().json(employee),
+        None => HttpResponse::NotFound().json(id),
+    })
+}
+
+pub async fn delete_employee<D: Database>(
+    id: Path<Uuid>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    identity.require_admin()?;
+    let mut connection = db.connection().await?;
+    let id = id.into_inner();
+    Ok(match connection.delete_employee(id).await? {
+        true => HttpResponse::NoContent().finish(),
+        false => HttpResponse::NotFound().json(id),
+    })
+}
+
+async fn check_manager<C: EmployeeRepo>(
+    connection: &mut C,
+    new_employee: &NewEmployee,
+) -> Result<(), Error> {
+    match &new_employee.manager {
+        Some(manager) if connection.employee_by_username(manager).await?.is_none() => {
+            Err(Error::Validation(vec![FieldValidationError::new(
+                "manager".to_owned(),
+                "does not exist".to_owned(),
+            )]))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,127 @@
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::error::{FieldValidationError, Validated};
+
+#[derive(Debug, Deserialize)]
+pub struct NewEmployee {
+    /// The name hours are logged under. Stored trimmed and in lower case.
+    pub username: String,
+    pub name: String,
+    /// The username of their manager, who can see their hours.
+    #[serde(default)]
+    pub manager: Option<String>,
+}
+
+impl NewEmployee {
+    pub fn normalized(self) -> NewEmployee {
+        NewEmployee {
+            username: self.username.trim().to_lowercase(),
+            name: self.name.trim().to_owned(),
+            manager: self.manager.map(|manager| manager.trim().to_lowercase()),
+        }
+    }
+}
+
+impl Validated for NewEmployee {
+    fn validate(&self) -> Result<(), Vec<FieldValidationError>> {
+        let mut errors = vec![];
+        if self.username.trim().is_empty() {
+            errors.push(FieldValidationError::new(
+                "username".to_owned(),
+                "can not be empty".to_owned(),
+            ));
+        }
+        if self.name.trim().is_empty() {
+            errors.push(FieldValidationError::new(
+                "name".to_owned(),
+                "can not be empty".to_owned(),
+            ));
+        }
+        if let Some(manager) = &self.manager {
+            if manager.trim().eq_ignore_ascii_case(self.username.trim()) {
+                errors.push(FieldValidationError::new(
+                    "manager".to_owned(),
+                    "can not be the employee".to_owned(),
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Employee {
+    pub id: Uuid,
+    pub username: String,
+    pub name: String,
+    pub manager: Option<String>,
+}
+
+impl Employee {
+    pub fn new(new_employee: NewEmployee) -> Employee {
+        let new_employee = new_employee.normalized();
+        Employee {
+            id: Uuid::new_v4(),
+            username: new_employee.username,
+            name: new_employee.name,
+            manager: new_employee.manager,
+        }
+    }
+}
+
+impl TryFrom<PgRow> for Employee {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(Employee {
+            id: value.try_get("id")?,
+            username: value.try_get("username")?,
+            name: value.try_get("name")?,
+            manager: value.try_get("manager")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_normalizes_username() {
+        let employee = Employee::new(NewEmployee {
+            username: " Kaarel ".to_owned(),
+            name: " Kaarel Ratas ".to_owned(),
+            manager: Some(" Boss ".to_owned()),
+        });
+
+        assert_eq!(employee.username, "kaarel");
+        assert_eq!(employee.name, "Kaarel Ratas");
+        assert_eq!(employee.manager, Some("boss".to_owned()));
+    }
+
+    #[test]
+    fn validate_rejects_blank_fields() {
+        let employee = NewEmployee {
+            username: " ".to_owned(),
+            name: "".to_owned(),
+            manager: None,
+        };
+
+        assert_eq!(
+            employee.validate(),
+            Err(vec![
+                FieldValidationError::new("username".to_owned(), "can not be empty".to_owned()),
+                FieldValidationError::new("name".to_owned(), "can not be empty".to_owned()),
+            ])
+        );
+    }
+}
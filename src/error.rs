@@ -1,5 +1,14 @@
+use std::fmt;
+
+use actix_web::{
+    http::{header, StatusCode},
+    HttpResponse, ResponseError,
+};
 use serde::{Deserialize, Serialize};
 
+use crate::auth::{API_KEY_SCHEME, BEARER_SCHEME};
+use crate::rate_limit::RateLimitStatus;
+
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub struct ErrorResponse {
     message: String,
@@ -7,6 +16,13 @@ pub struct ErrorResponse {
 }
 
 impl ErrorResponse {
+    pub fn new(message: String) -> Self {
+        ErrorResponse {
+            message,
+            fields: None,
+        }
+    }
+
     pub fn with_validation_errors(message: String, errors: Vec<FieldValidationError>) -> Self {
         ErrorResponse {
             message,
@@ -15,7 +31,7 @@ impl ErrorResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, PartialEq, Debug)]
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
 pub struct FieldValidationError {
     name: String,
     error: String,
@@ -30,3 +46,183 @@ impl FieldValidationError {
 pub trait Validated {
     fn validate(&self) -> Result<(), Vec<FieldValidationError>>;
 }
+
+/// Errors that end request handling early.
+#[derive(Debug)]
+pub enum Error {
+    Database(sqlx::Error),
+    /// The request is well-formed but some of its fields are not acceptable.
+    Validation(Vec<FieldValidationError>),
+    /// The request does not carry valid credentials.
+    Unauthorized(String),
+    /// The credentials of the request do not allow it.
+    Forbidden(String),
+    /// The request conflicts with the current state of a resource.
+    Conflict(String),
+    /// The resource has changed since the version the request was based on.
+    PreconditionFailed(String),
+    /// The request has to name the version of the resource it is based on.
+    PreconditionRequired(String),
+    /// The client has made more requests than its rate limit allows.
+    TooManyRequests(RateLimitStatus),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Database(e) => write!(f, "database error: {}", e),
+            Error::Validation(errors) => write!(f, "{} validation errors", errors.len()),
+            Error::Unauthorized(message) => write!(f, "unauthorized: {}", message),
+            Error::Forbidden(message) => write!(f, "forbidden: {}", message),
+            Error::Conflict(message) => write!(f, "conflict: {}", message),
+            Error::PreconditionFailed(message) => write!(f, "precondition failed: {}", message),
+            Error::PreconditionRequired(message) => {
+                write!(f, "precondition required: {}", message)
+            }
+            Error::TooManyRequests(status) => write!(
+                f,
+                "too many requests, retry after {} seconds",
+                status.retry_after.unwrap_or_default()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Database(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(e: sqlx::Error) -> Self {
+        Error::Database(e)
+    }
+}
+
+impl From<Vec<FieldValidationError>> for Error {
+    fn from(errors: Vec<FieldValidationError>) -> Self {
+        Error::Validation(errors)
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Error::Database(sqlx::Error::PoolTimedOut)
+            | Error::Database(sqlx::Error::PoolClosed)
+            | Error::Database(sqlx::Error::Io(_)) => StatusCode::SERVICE_UNAVAILABLE,
+            Error::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Validation(_) => StatusCode::BAD_REQUEST,
+            Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Error::Forbidden(_) => StatusCode::FORBIDDEN,
+            Error::Conflict(_) => StatusCode::CONFLICT,
+            Error::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            Error::PreconditionRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+            Error::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let body = match self {
+            Error::Database(_) => {
+                log::error!("{}", self);
+                let message = match self.status_code() {
+                    StatusCode::SERVICE_UNAVAILABLE => "Service temporarily unavailable",
+                    _ => "Internal server error",
+                };
+                ErrorResponse::new(message.into())
+            }
+            Error::Validation(errors) => {
+                ErrorResponse::with_validation_errors("Validation errors".into(), errors.clone())
+            }
+            Error::Unauthorized(message)
+            | Error::Forbidden(message)
+            | Error::Conflict(message)
+            | Error::PreconditionFailed(message)
+            | Error::PreconditionRequired(message) => ErrorResponse::new(message.clone()),
+            Error::TooManyRequests(status) => ErrorResponse::new(format!(
+                "Too many requests, try again in {} seconds",
+                status.retry_after.unwrap_or_default()
+            )),
+        };
+        let mut response = HttpResponse::build(self.status_code());
+        match self {
+            Error::Unauthorized(_) => {
+                response.insert_header((
+                    header::WWW_AUTHENTICATE,
+                    format!("{}, {}", API_KEY_SCHEME, BEARER_SCHEME),
+                ));
+            }
+            Error::TooManyRequests(status) => {
+                response
+                    .insert_header((header::RETRY_AFTER, status.retry_after.unwrap_or_default()));
+                for header in status.headers() {
+                    response.insert_header(header);
+                }
+            }
+            _ => {}
+        }
+        response.json(body)
+    }
+}
+
+/// Turns a unique or foreign key violation into a conflict with the given message.
+pub(crate) fn conflict_on_constraint(e: sqlx::Error, message: &str) -> Error {
+    match &e {
+        sqlx::Error::Database(db_error)
+            if matches!(db_error.code().as_deref(), Some("23505") | Some("23503")) =>
+        {
+            Error::Conflict(message.to_owned())
+        }
+        _ => Error::Database(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::AnyBody;
+
+    use super::*;
+
+    #[test]
+    fn pool_timeout_is_service_unavailable() {
+        let error = Error::from(sqlx::Error::PoolTimedOut);
+
+        assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn query_error_is_internal_server_error() {
+        let error = Error::from(sqlx::Error::RowNotFound);
+
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn validation_error_is_bad_request() {
+        let error = Error::from(vec![FieldValidationError::new(
+            "hours".into(),
+            "can not be zero".into(),
+        )]);
+
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn error_response_has_error_body() {
+        let response = Error::from(sqlx::Error::PoolClosed).error_response();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        match response.body() {
+            AnyBody::Bytes(bytes) => assert_eq!(
+                serde_json::from_slice::<ErrorResponse>(bytes).unwrap(),
+                ErrorResponse::new("Service temporarily unavailable".into())
+            ),
+            _ => panic!("Expected a JSON body."),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use super::audit::AuditRecord;
+use super::Hours;
+use crate::auth::{Identity, Role};
+use crate::db::EmployeeRepo;
+use crate::error::Error;
+
+pub(crate) const NOT_ALLOWED: &str = "You are not allowed to access the hours of this employee";
+
+/// The employees whose hours are within reach.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scope {
+    All,
+    Employees(Vec<String>),
+}
+
+impl Scope {
+    /// `None` when every employee is included, the way the repositories take it.
+    pub fn employees(&self) -> Option<&[String]> {
+        match self {
+            Scope::All => None,
+            Scope::Employees(employees) => Some(employees),
+        }
+    }
+
+    pub fn includes(&self, employee: &str) -> bool {
+        self.employees()
+            .is_none_or(|employees| employees.iter().any(|e| e == employee))
+    }
+
+    /// Forbids the hours of employees outside of the scope.
+    pub fn check(&self, employee: &str) -> Result<(), Error> {
+        if self.includes(employee) {
+            Ok(())
+        } else {
+            Err(Error::Forbidden(NOT_ALLOWED.to_owned()))
+        }
+    }
+
+    /// Forbids an entry of an employee outside of the scope, instead of returning it.
+    pub fn check_entry(&self, hours: Option<Hours>) -> Result<Option<Hours>, Error> {
+        if let Some(hours) = &hours {
+            self.check(&hours.employee)?;
+        }
+        Ok(hours)
+    }
+
+    /// Forbids the history of an entry that last belonged to an employee outside of the scope.
+    pub fn check_history(&self, history: Vec<AuditRecord>) -> Result<Vec<AuditRecord>, Error> {
+        if let Some(employee) = history.last().and_then(AuditRecord::employee) {
+            self.check(employee)?;
+        }
+        Ok(history)
+    }
+}
+
+/// Whose hours an identity can see and whose it can change. Employees see and change their own,
+/// managers also see those of their team and admins see and change everyone's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Access {
+    pub read: Scope,
+    pub write: Scope,
+}
+
+impl Access {
+    pub async fn of<C: EmployeeRepo>(
+        connection: &mut C,
+        identity: &Identity,
+    ) -> Result<Access, Error> {
+        let own = Scope::Employees(vec![identity.subject.clone()]);
+        Ok(match identity.role {
+            Role::Admin => Access {
+                read: Scope::All,
+                write: Scope::All,
+            },
+            Role::Manager => {
+                let mut team = connection.team(&identity.subject).await?;
+                team.push(identity.subject.clone());
+                Access {
+                    read: Scope::Employees(team),
+                    write: own,
+                }
+            }
+            Role::Employee => Access {
+                read: own.clone(),
+                write: own,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDatabase;
+    use crate::employees::NewEmployee;
+
+    #[actix_rt::test]
+    async fn managers_see_their_team() {
+        let mut db = MemoryDatabase::default();
+        for (username, manager) in &[("boss", None), ("employee", Some("boss")), ("other", None)] {
+            db.insert_employee(NewEmployee {
+                username: username.to_string(),
+                name: username.to_string(),
+                manager: manager.map(str::to_owned),
+            })
+            .await
+            .unwrap();
+        }
+
+        let access = Access::of(&mut db, &identity("boss", Role::Manager))
+            .await
+            .unwrap();
+
+        assert!(access.read.includes("boss"));
+        assert!(access.read.includes("employee"));
+        assert!(!access.read.includes("other"));
+        assert!(access.write.includes("boss"));
+        assert!(matches!(
+            access.write.check("employee"),
+            Err(Error::Forbidden(_))
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn employees_and_admins() {
+        let mut db = MemoryDatabase::default();
+
+        let access = Access::of(&mut db, &identity("employee", Role::Employee))
+            .await
+            .unwrap();
+        assert_eq!(access.read, Scope::Employees(vec!["employee".to_owned()]));
+        assert_eq!(access.write, access.read);
+
+        let access = Access::of(&mut db, &identity("admin", Role::Admin))
+            .await
+            .unwrap();
+        assert_eq!(access.read, Scope::All);
+        assert!(access.write.includes("employee"));
+    }
+
+    fn identity(subject: &str, role: Role) -> Identity {
+        Identity {
+            subject: subject.to_owned(),
+            role,
+        }
+    }
+}
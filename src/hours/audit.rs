@@ -0,0 +1,147 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
+use sqlx::Row;
+use uuid::Uuid;
+
+use super::Hours;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Insert,
+    Update,
+    Delete,
+    Restore,
+    Purge,
+}
+
+impl Operation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operation::Insert => "insert",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+            Operation::Restore => "restore",
+            Operation::Purge => "purge",
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Operation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "insert" => Ok(Operation::Insert),
+            "update" => Ok(Operation::Update),
+            "delete" => Ok(Operation::Delete),
+            "restore" => Ok(Operation::Restore),
+            "purge" => Ok(Operation::Purge),
+            other => Err(format!("unknown operation {}", other)),
+        }
+    }
+}
+
+/// A single change of an hours entry, with the entry as the API showed it before and after.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AuditRecord {
+    pub hours_id: Uuid,
+    pub operation: Operation,
+    pub actor: String,
+    pub changed_at: DateTime<Utc>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+impl AuditRecord {
+    /// Whose the entry was after the change, or before it if it was deleted.
+    pub fn employee(&self) -> Option<&str> {
+        self.after
+            .as_ref()
+            .or(self.before.as_ref())
+            .and_then(|hours| hours.get("employee"))
+            .and_then(serde_json::Value::as_str)
+    }
+
+    pub fn inserted(actor: &str, hours: &Hours) -> AuditRecord {
+        AuditRecord::new(hours.id, Operation::Insert, actor, None, Some(hours))
+    }
+
+    pub fn updated(actor: &str, before: &Hours, after: &Hours) -> AuditRecord {
+        AuditRecord::new(
+            before.id,
+            Operation::Update,
+            actor,
+            Some(before),
+            Some(after),
+        )
+    }
+
+    /// Moving the entry to the trash.
+    pub fn deleted(actor: &str, hours: &Hours) -> AuditRecord {
+        AuditRecord::new(hours.id, Operation::Delete, actor, Some(hours), None)
+    }
+
+    pub fn restored(actor: &str, hours: &Hours) -> AuditRecord {
+        AuditRecord::new(hours.id, Operation::Restore, actor, None, Some(hours))
+    }
+
+    /// Removing the entry from the trash for good.
+    pub fn purged(actor: &str, hours: &Hours) -> AuditRecord {
+        AuditRecord::new(hours.id, Operation::Purge, actor, Some(hours), None)
+    }
+
+    /// The change time is truncated to microseconds, the precision Postgres stores it with.
+    fn new(
+        hours_id: Uuid,
+        operation: Operation,
+        actor: &str,
+        before: Option<&Hours>,
+        after: Option<&Hours>,
+    ) -> AuditRecord {
+        AuditRecord {
+            hours_id,
+            operation,
+            actor: actor.to_owned(),
+            changed_at: Utc::now().trunc_subsecs(6),
+            before: before.map(to_json),
+            after: after.map(to_json),
+        }
+    }
+}
+
+fn to_json(hours: &Hours) -> serde_json::Value {
+    serde_json::to_value(hours).expect("Hours serialize to JSON")
+}
+
+impl TryFrom<PgRow> for AuditRecord {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        let before: Option<Json<serde_json::Value>> = value.try_get("before")?;
+        let after: Option<Json<serde_json::Value>> = value.try_get("after")?;
+        Ok(AuditRecord {
+            hours_id: value.try_get("hours_id")?,
+            operation: value
+                .try_get::<String, _>("operation")?
+                .parse()
+                .map_err(|e: String| sqlx::Error::Decode(e.into()))?,
+            actor: value.try_get("actor")?,
+            changed_at: value.try_get("changed_at")?,
+            before: before.map(|json| json.0),
+            after: after.map(|json| json.0),
+        })
+    }
+}
@@ -0,0 +1,176 @@
+//! Logged time is kept in whole minutes. The API talks in hours, which may be fractional, or in
+//! ISO 8601 durations.
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub const MINUTES_PER_HOUR: i32 = 60;
+pub const MINUTES_PER_DAY: i32 = 24 * MINUTES_PER_HOUR;
+
+/// Converts a number of hours to minutes, rounded to the nearest minute.
+pub fn minutes_from_hours(hours: f64) -> Result<i32, String> {
+    let minutes = (hours * f64::from(MINUTES_PER_HOUR)).round();
+    if !minutes.is_finite() || minutes.abs() > f64::from(i32::MAX) {
+        return Err(format!("{} is not a valid number of hours", hours));
+    }
+    Ok(minutes as i32)
+}
+
+/// Parses the time part of an ISO 8601 duration, such as `PT1H30M`, to minutes rounded to the
+/// nearest minute. Years, months, weeks and days are not accepted as their length in working
+/// time is ambiguous.
+pub fn parse_duration(duration: &str) -> Result<i32, String> {
+    let invalid = || {
+        format!(
+            "{} is not a valid ISO 8601 duration such as PT1H30M",
+            duration
+        )
+    };
+    let time = duration
+        .strip_prefix("PT")
+        .filter(|time| !time.is_empty())
+        .ok_or_else(invalid)?;
+    let mut seconds = 0.0;
+    let mut number = String::new();
+    let mut units = ['H', 'M', 'S'].iter();
+    for c in time.chars() {
+        if c.is_ascii_digit() || c == '.' || c == ',' {
+            number.push(if c == ',' { '.' } else { c });
+            continue;
+        }
+        // Units have to come in order, each at most once.
+        let multiplier = match units.by_ref().find(|&&unit| unit == c) {
+            Some('H') => 3600.0,
+            Some('M') => 60.0,
+            Some('S') => 1.0,
+            _ => return Err(invalid()),
+        };
+        let value: f64 = number.parse().map_err(|_| invalid())?;
+        seconds += value * multiplier;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    minutes_from_hours(seconds / 3600.0)
+}
+
+/// Rounds `minutes` to the nearest multiple of `increment`, halves are rounded up. `None` when
+/// the result does not fit.
+pub fn round(minutes: i32, increment: u16) -> Option<i32> {
+    let increment = i32::from(increment.max(1));
+    let remainder = minutes.rem_euclid(increment);
+    let rounded_down = minutes.checked_sub(remainder)?;
+    if remainder * 2 >= increment {
+        rounded_down.checked_add(increment)
+    } else {
+        Some(rounded_down)
+    }
+}
+
+/// Formats minutes as hours. Whole hours are written without a fraction, like they were before
+/// minutes were tracked.
+pub fn format_hours(minutes: i64) -> String {
+    let per_hour = i64::from(MINUTES_PER_HOUR);
+    if minutes % per_hour == 0 {
+        (minutes / per_hour).to_string()
+    } else {
+        (minutes as f64 / per_hour as f64).to_string()
+    }
+}
+
+/// Serializes minutes as hours: an integer when the time is whole hours, so that clients that
+/// only know about whole hours keep working, a decimal otherwise.
+pub fn serialize_as_hours<T, S>(minutes: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Copy + Into<i64>,
+    S: Serializer,
+{
+    let minutes: i64 = (*minutes).into();
+    let per_hour = i64::from(MINUTES_PER_HOUR);
+    if minutes % per_hour == 0 {
+        serializer.serialize_i64(minutes / per_hour)
+    } else {
+        serializer.serialize_f64(minutes as f64 / per_hour as f64)
+    }
+}
+
+pub fn deserialize_hours<'de, D>(deserializer: D) -> Result<i32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    minutes_from_hours(f64::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+/// The two ways a client can state the logged time, at most one of which may be used.
+pub fn minutes_from(hours: Option<f64>, duration: Option<&str>) -> Result<Option<i32>, String> {
+    match (hours, duration) {
+        (Some(_), Some(_)) => Err("only one of hours and duration can be given".to_owned()),
+        (Some(hours), None) => minutes_from_hours(hours).map(Some),
+        (None, Some(duration)) => parse_duration(duration).map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_accepts_time_components() {
+        assert_eq!(parse_duration("PT1H30M"), Ok(90));
+        assert_eq!(parse_duration("PT90M"), Ok(90));
+        assert_eq!(parse_duration("PT1.5H"), Ok(90));
+        assert_eq!(parse_duration("PT0,25H"), Ok(15));
+        assert_eq!(parse_duration("PT1H15S"), Ok(60));
+        assert_eq!(parse_duration("PT1H30S"), Ok(61));
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid() {
+        for duration in &[
+            "", "PT", "P1D", "PT1M1H", "PT1H1H", "PT1", "1H", "PTH", "PT1X",
+        ] {
+            assert!(parse_duration(duration).is_err(), "{}", duration);
+        }
+    }
+
+    #[test]
+    fn minutes_from_hours_rounds_to_minutes() {
+        assert_eq!(minutes_from_hours(1.5), Ok(90));
+        assert_eq!(minutes_from_hours(1.0 / 3.0), Ok(20));
+        assert!(minutes_from_hours(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn round_to_increment() {
+        assert_eq!(round(52, 15), Some(45));
+        assert_eq!(round(53, 15), Some(60));
+        assert_eq!(round(7, 15), Some(0));
+        assert_eq!(round(8, 15), Some(15));
+        assert_eq!(round(52, 1), Some(52));
+        assert_eq!(round(52, 0), Some(52));
+    }
+
+    #[test]
+    fn round_does_not_overflow() {
+        assert_eq!(round(i32::MAX, 15), Some(i32::MAX - 7));
+        assert_eq!(round(i32::MAX, 16), None);
+        assert_eq!(round(-i32::MAX, 15), None);
+        assert_eq!(round(i32::MAX, 1), Some(i32::MAX));
+    }
+
+    #[test]
+    fn format_hours_keeps_whole_hours_integers() {
+        assert_eq!(format_hours(120), "2");
+        assert_eq!(format_hours(90), "1.5");
+        assert_eq!(format_hours(20), format!("{}", 1.0 / 3.0));
+    }
+
+    #[test]
+    fn minutes_from_either_field() {
+        assert_eq!(minutes_from(Some(2.0), None), Ok(Some(120)));
+        assert_eq!(minutes_from(None, Some("PT2H")), Ok(Some(120)));
+        assert_eq!(minutes_from(None, None), Ok(None));
+        assert!(minutes_from(Some(2.0), Some("PT2H")).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use actix_web::http::header::{self, EntityTag, Header, IfMatch, IfNoneMatch};
+use actix_web::HttpRequest;
+
+use super::Hours;
+use crate::db::HOURS_CHANGED;
+use crate::error::{Error, FieldValidationError};
+
+/// The entity tag of an entry is its version.
+pub(crate) fn etag(hours: &Hours) -> header::ETag {
+    header::ETag(tag(hours))
+}
+
+fn tag(hours: &Hours) -> EntityTag {
+    EntityTag::strong(hours.version.to_string())
+}
+
+/// Modifying requests have to send the entity tag they last read in `If-Match`, or `*` to
+/// change the entry whatever its version.
+pub(crate) fn check_if_match(req: &HttpRequest, current: &Hours) -> Result<(), Error> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return Err(Error::PreconditionRequired(
+            "The If-Match header is required to change an hours entry".to_owned(),
+        ));
+    }
+    match IfMatch::parse(req) {
+        Ok(IfMatch::Any) => Ok(()),
+        Ok(IfMatch::Items(tags)) if tags.iter().any(|t| t.strong_eq(&tag(current))) => Ok(()),
+        Ok(IfMatch::Items(_)) => Err(Error::PreconditionFailed(HOURS_CHANGED.to_owned())),
+        Err(_) => Err(invalid_header("If-Match")),
+    }
+}
+
+/// Whether the client already has the current version of the entry.
+pub(crate) fn is_not_modified(req: &HttpRequest, current: &Hours) -> Result<bool, Error> {
+    match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => Ok(true),
+        Ok(IfNoneMatch::Items(tags)) => Ok(tags.iter().any(|t| t.weak_eq(&tag(current)))),
+        Err(_) => Err(invalid_header("If-None-Match")),
+    }
+}
+
+fn invalid_header(name: &str) -> Error {
+    Error::Validation(vec![FieldValidationError::new(
+        name.to_owned(),
+        "must be * or a list of entity tags".to_owned(),
+    )])
+}
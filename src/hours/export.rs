@@ -0,0 +1,172 @@
+use std::future::ready;
+
+use actix_web::web::Bytes;
+use futures::stream::{self, Stream, StreamExt};
+
+use super::access::Scope;
+use super::duration::format_hours;
+use super::{Hours, HoursQuery};
+use crate::db::HoursRepo;
+use crate::error::Error;
+
+/// Number of rows encoded into a single chunk of the export at most.
+const BATCH_SIZE: u32 = 500;
+
+/// Column headers of the export. Scripts depend on these, so only ever append to the end.
+pub const CSV_HEADERS: [&str; 9] = [
+    "id",
+    "date",
+    "employee",
+    "project",
+    "story_id",
+    "description",
+    "hours",
+    "start_time",
+    "end_time",
+];
+
+/// Streams every entry matching `query` within `scope` as RFC 4180 CSV. The entries are read
+/// from the database with a single query and encoded in batches as they arrive.
+///
+/// `limit` and `offset` of the query are honoured, without a `limit` all matching entries are
+/// exported.
+pub fn csv_stream<C>(
+    connection: C,
+    query: HoursQuery,
+    scope: Scope,
+) -> impl Stream<Item = Result<Bytes, Error>>
+where
+    C: HoursRepo + 'static,
+{
+    let headers = stream::once(ready(Ok(encode(Some(&CSV_HEADERS), &[]))));
+    let rows = connection
+        .export(query, scope)
+        .ready_chunks(BATCH_SIZE as usize)
+        .map(encode_batch);
+    headers.chain(rows)
+}
+
+fn encode_batch(batch: Vec<Result<Hours, Error>>) -> Result<Bytes, Error> {
+    match batch.into_iter().collect::<Result<Vec<_>, _>>() {
+        Ok(rows) => Ok(encode(None, &rows)),
+        Err(e) => {
+            log::error!("CSV export failed: {}", e);
+            Err(e)
+        }
+    }
+}
+
+fn encode(headers: Option<&[&str]>, rows: &[Hours]) -> Bytes {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(vec![]);
+    if let Some(headers) = headers {
+        writer.write_record(headers).unwrap();
+    }
+    for hours in rows {
+        writer
+            .write_record(&[
+                hours.id.to_string(),
+                hours.date.to_string(),
+                hours.employee.clone(),
+                hours.project.clone(),
+                hours.story_id.clone().unwrap_or_default(),
+                hours.description.clone(),
+                format_hours(hours.minutes.into()),
+                hours.start_time.map(|t| t.to_string()).unwrap_or_default(),
+                hours.end_time.map(|t| t.to_string()).unwrap_or_default(),
+            ])
+            .unwrap();
+    }
+    // Writing into a `Vec` can not fail.
+    Bytes::from(writer.into_inner().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::db::MemoryDatabase;
+    use crate::hours::NewHours;
+
+    #[actix_rt::test]
+    async fn csv_stream_quotes_fields() {
+        let mut db = MemoryDatabase::default();
+        let hours = db
+            .insert(
+                NewHours {
+                    employee: "employee".to_owned(),
+                    date: NaiveDate::from_ymd(2021, 10, 9),
+                    project: "project".to_owned(),
+                    story_id: None,
+                    description: "fixed \"quotes\", commas\nand newlines".to_owned(),
+                    minutes: 60,
+                    start_time: None,
+                    end_time: None,
+                },
+                "tester",
+            )
+            .await
+            .unwrap();
+
+        let chunks: Vec<Bytes> = csv_stream(db, HoursQuery::default(), Scope::All)
+            .try_collect()
+            .await
+            .unwrap();
+
+        let csv = String::from_utf8(chunks.concat()).unwrap();
+        assert_eq!(
+            csv,
+            format!(
+                "id,date,employee,project,story_id,description,hours,start_time,end_time\r\n\
+                 {},2021-10-09,employee,project,,\"fixed \"\"quotes\"\", commas\nand newlines\",1,,\r\n",
+                hours.id
+            )
+        );
+    }
+
+    #[actix_rt::test]
+    async fn csv_stream_fetches_in_batches() {
+        let mut db = MemoryDatabase::default();
+        for _ in 0..(BATCH_SIZE + 10) {
+            db.insert(
+                NewHours {
+                    employee: "employee".to_owned(),
+                    date: NaiveDate::from_ymd(2021, 10, 9),
+                    project: "project".to_owned(),
+                    story_id: None,
+                    description: "description".to_owned(),
+                    minutes: 60,
+                    start_time: None,
+                    end_time: None,
+                },
+                "tester",
+            )
+            .await
+            .unwrap();
+        }
+
+        let all: Vec<Bytes> = csv_stream(db.clone(), HoursQuery::default(), Scope::All)
+            .try_collect()
+            .await
+            .unwrap();
+        let limited: Vec<Bytes> = csv_stream(
+            db,
+            HoursQuery {
+                limit: Some(3),
+                ..HoursQuery::default()
+            },
+            Scope::All,
+        )
+        .try_collect()
+        .await
+        .unwrap();
+
+        let lines =
+            |chunks: Vec<Bytes>| String::from_utf8(chunks.concat()).unwrap().lines().count();
+        assert_eq!(lines(all), BATCH_SIZE as usize + 11);
+        assert_eq!(lines(limited), 4);
+    }
+}
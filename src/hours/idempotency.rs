@@ -0,0 +1,82 @@
+use std::convert::TryFrom;
+
+use actix_web::{HttpRequest, HttpResponse};
+use chrono::{DateTime, SubsecRound, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
+use sqlx::Row;
+use uuid::Uuid;
+
+use super::etag::etag;
+use super::Hours;
+use crate::error::{Error, FieldValidationError};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// Set on responses that were replayed for a key that had already been used.
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+const MAX_KEY_LENGTH: usize = 255;
+
+/// An entry logged by a request that carried an idempotency key, along with the response that was
+/// sent for it. Keys are only unique per `subject`, who sent the request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdempotencyKey {
+    pub subject: String,
+    pub key: String,
+    pub hours_id: Uuid,
+    pub response: Hours,
+    pub created_at: DateTime<Utc>,
+}
+
+impl IdempotencyKey {
+    pub fn new(subject: &str, key: &str, hours: &Hours) -> IdempotencyKey {
+        IdempotencyKey {
+            subject: subject.to_owned(),
+            key: key.to_owned(),
+            hours_id: hours.id,
+            response: hours.clone(),
+            created_at: Utc::now().trunc_subsecs(6),
+        }
+    }
+
+    /// The response that was sent when the entry was logged.
+    pub fn replay(&self) -> HttpResponse {
+        HttpResponse::Created()
+            .insert_header(etag(&self.response))
+            .insert_header((REPLAYED_HEADER, "true"))
+            .json(&self.response)
+    }
+}
+
+impl TryFrom<PgRow> for IdempotencyKey {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        let response: Json<Hours> = value.try_get("response")?;
+        Ok(IdempotencyKey {
+            subject: value.try_get("subject")?,
+            key: value.try_get("key")?,
+            hours_id: value.try_get("hours_id")?,
+            response: response.0,
+            created_at: value.try_get("created_at")?,
+        })
+    }
+}
+
+/// The idempotency key of the request, if it has one.
+pub(crate) fn from_request(req: &HttpRequest) -> Result<Option<String>, Error> {
+    let value = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let error = match value.to_str().map(str::trim) {
+        Ok("") => "can not be empty",
+        Ok(key) if key.len() > MAX_KEY_LENGTH => "can not be longer than 255 characters",
+        Ok(key) => return Ok(Some(key.to_owned())),
+        Err(_) => "must be visible ASCII",
+    };
+    Err(Error::Validation(vec![FieldValidationError::new(
+        IDEMPOTENCY_KEY_HEADER.to_owned(),
+        error.to_owned(),
+    )]))
+}
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+
+use super::validation::{check_editable, check_limits, overlap_error, validate, Totals};
+use super::{Hours, NewHours};
+use crate::configuration::HoursConfig;
+use crate::db::{EmployeeRepo, HoursRepo, PeriodRepo, ProjectRepo, TimesheetRepo};
+use crate::error::{Error, FieldValidationError};
+
+/// Largest accepted import body.
+pub const MAX_IMPORT_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Nothing is imported unless every row is valid.
+    #[default]
+    Atomic,
+    /// Valid rows are imported, invalid ones are reported and left out.
+    SkipInvalid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct RowErrors {
+    /// 1-based number of the data row, the CSV header does not count.
+    pub row: usize,
+    pub errors: Vec<FieldValidationError>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct ImportReport {
+    pub inserted: Vec<Hours>,
+    pub errors: Vec<RowErrors>,
+}
+
+/// A row that could not be parsed is reported the same way as one that does not validate.
+pub type ParsedRow = Result<NewHours, Vec<FieldValidationError>>;
+
+/// Parses a JSON array of `NewHours`, every element on its own so that one broken element does
+/// not hide the problems of the others.
+pub fn parse_json(body: &[u8]) -> Result<Vec<ParsedRow>, String> {
+    let values: Vec<serde_json::Value> = serde_json::from_slice(body).map_err(|e| e.to_string())?;
+    Ok(values
+        .into_iter()
+        .map(|value| serde_json::from_value(value).map_err(|e| unparseable(e.to_string())))
+        .collect())
+}
+
+/// Parses CSV with a header row naming the `NewHours` fields. An empty `story_id` is `null`.
+pub fn parse_csv(body: &[u8]) -> Result<Vec<ParsedRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body);
+    reader.headers().map_err(|e| e.to_string())?;
+    Ok(reader
+        .deserialize()
+        .map(|row| row.map_err(|e: csv::Error| unparseable(e.to_string())))
+        .collect())
+}
+
+/// Validates every row and splits them into the valid entries and the errors. Rows are also
+/// checked for overlapping with the valid rows before them, which count towards the totals too.
+/// Rows in closed periods or in submitted or approved timesheets are reported as errors on their
+/// date.
+pub async fn validate_rows<
+    C: HoursRepo + EmployeeRepo + ProjectRepo + TimesheetRepo + PeriodRepo,
+>(
+    connection: &mut C,
+    rows: Vec<ParsedRow>,
+    config: &HoursConfig,
+) -> Result<(Vec<NewHours>, Vec<RowErrors>), Error> {
+    let mut valid: Vec<(usize, NewHours)> = vec![];
+    let mut invalid = vec![];
+    for (index, row) in rows.into_iter().enumerate() {
+        let errors = match row {
+            Ok(new_hours) => match validate(connection, &new_hours, None, config).await {
+                Ok(()) => {
+                    let mut errors: Vec<FieldValidationError> = valid
+                        .iter()
+                        .filter(|(_, other)| overlaps(&new_hours, other))
+                        .map(|(row, _)| overlap_error(&format!("in row {}", row)))
+                        .collect();
+                    let mut batch = Totals::default();
+                    for (_, other) in &valid {
+                        batch.add(&new_hours, other);
+                    }
+                    match check_editable(connection, &new_hours.employee, new_hours.date).await {
+                        Ok(()) => {}
+                        Err(Error::Conflict(message)) => {
+                            errors.push(FieldValidationError::new("date".to_owned(), message))
+                        }
+                        Err(e) => return Err(e),
+                    }
+                    if errors.is_empty() && batch != Totals::default() {
+                        let mut logged = Totals::logged(connection, &new_hours, None).await?;
+                        logged.day += batch.day;
+                        logged.week += batch.week;
+                        errors = check_limits(&new_hours, logged, &config.limit);
+                    }
+                    if errors.is_empty() {
+                        valid.push((index + 1, new_hours));
+                        continue;
+                    }
+                    errors
+                }
+                Err(Error::Validation(errors)) => errors,
+                Err(e) => return Err(e),
+            },
+            Err(errors) => errors,
+        };
+        invalid.push(RowErrors {
+            row: index + 1,
+            errors,
+        });
+    }
+    Ok((valid.into_iter().map(|(_, row)| row).collect(), invalid))
+}
+
+fn overlaps(a: &NewHours, b: &NewHours) -> bool {
+    match (a.span(), b.span()) {
+        (Some((a_start, a_end)), Some((b_start, b_end))) => {
+            a.employee == b.employee && a.date == b.date && a_start < b_end && b_start < a_end
+        }
+        _ => false,
+    }
+}
+
+fn unparseable(error: String) -> Vec<FieldValidationError> {
+    vec![FieldValidationError::new("row".to_owned(), error)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryDatabase;
+    use crate::test_utils;
+
+    #[test]
+    fn parse_csv_reads_rows() {
+        let csv = "employee,date,project,story_id,description,hours\r\n\
+                   employee,2021-10-09,project,,description,1\r\n\
+                   employee,2021-10-10,project,story,\"with, comma\",2\r\n";
+
+        let rows = parse_csv(csv.as_bytes()).unwrap();
+
+        let rows: Vec<NewHours> = rows.into_iter().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].story_id, None);
+        assert_eq!(rows[1].story_id, Some("story".to_owned()));
+        assert_eq!(rows[1].description, "with, comma");
+    }
+
+    #[actix_rt::test]
+    async fn validate_rows_reports_row_numbers() {
+        let mut db = MemoryDatabase::default();
+        test_utils::internal::seed(&mut db).await;
+        let json = r#"[
+            {"employee": "employee", "date": "2021-10-09", "project": "project", "description": "d", "hours": 1},
+            {"employee": "employee", "date": "not a date", "project": "project", "description": "d", "hours": 1},
+            {"employee": "employee", "date": "2021-10-09", "project": "project", "description": "d", "hours": 25}
+        ]"#;
+
+        let config = HoursConfig::default();
+        let (valid, invalid) =
+            validate_rows(&mut db, parse_json(json.as_bytes()).unwrap(), &config)
+                .await
+                .unwrap();
+
+        assert_eq!(valid.len(), 1);
+        assert_eq!(
+            invalid.iter().map(|e| e.row).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(
+            invalid[1].errors,
+            vec![FieldValidationError::new(
+                "hours".to_owned(),
+                "can not be larger than 24".to_owned()
+            )]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn validate_rows_reports_overlapping_rows() {
+        let mut db = MemoryDatabase::default();
+        test_utils::internal::seed(&mut db).await;
+        let json = r#"[
+            {"employee": "employee", "date": "2021-10-09", "project": "project", "description": "d",
+                "start_time": "09:00:00", "end_time": "10:00:00"},
+            {"employee": "other", "date": "2021-10-09", "project": "project", "description": "d",
+                "start_time": "09:00:00", "end_time": "10:00:00"},
+            {"employee": "employee", "date": "2021-10-09", "project": "project", "description": "d",
+                "start_time": "09:30:00", "end_time": "10:30:00"}
+        ]"#;
+
+        let config = HoursConfig::default();
+        let (valid, invalid) =
+            validate_rows(&mut db, parse_json(json.as_bytes()).unwrap(), &config)
+                .await
+                .unwrap();
+
+        assert_eq!(valid.len(), 2);
+        assert_eq!(
+            invalid,
+            vec![RowErrors {
+                row: 3,
+                errors: vec![overlap_error("in row 1")]
+            }]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn validate_rows_counts_earlier_rows_towards_limits() {
+        let mut db = MemoryDatabase::default();
+        test_utils::internal::seed(&mut db).await;
+        let json = r#"[
+            {"employee": "employee", "date": "2021-10-09", "project": "project", "description": "d", "hours": 5},
+            {"employee": "other", "date": "2021-10-09", "project": "project", "description": "d", "hours": 5},
+            {"employee": "employee", "date": "2021-10-09", "project": "project", "description": "d", "hours": 4}
+        ]"#;
+        let mut config = HoursConfig::default();
+        config.limit.day = 8.0;
+
+        let (valid, invalid) =
+            validate_rows(&mut db, parse_json(json.as_bytes()).unwrap(), &config)
+                .await
+                .unwrap();
+
+        assert_eq!(valid.len(), 2);
+        assert_eq!(
+            invalid,
+            vec![RowErrors {
+                row: 3,
+                errors: vec![FieldValidationError::new(
+                    "hours".to_owned(),
+                    "would make 9 hours on 2021-10-09, the daily maximum is 8, 5 hours are already logged".to_owned()
+                )]
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_json_requires_array() {
+        assert!(parse_json(b"{}").is_err());
+    }
+}
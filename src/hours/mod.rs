@@ -1,52 +1,364 @@
 use actix_web::{
-    web::{self, Data, Path},
-    HttpResponse,
+    http::header,
+    web::{self, Bytes, Data, Path, Query},
+    HttpMessage, HttpRequest, HttpResponse,
 };
-use sqlx::PgPool;
+use chrono::Utc;
 use uuid::Uuid;
 
-use crate::db::HoursRepo;
-use crate::error::{ErrorResponse, Validated};
+use crate::auth::Identity;
+use crate::configuration::HoursConfig;
+use crate::db::{Database, HoursRepo, Transaction};
+use crate::error::{Error, ErrorResponse, Validated};
 
-mod model;
+pub(crate) mod access;
+pub(crate) mod audit;
+pub(crate) mod duration;
+mod etag;
+mod export;
+pub(crate) mod idempotency;
+mod import;
+pub(crate) mod model;
+pub(crate) mod query;
+mod trash;
+pub(crate) mod validation;
 
-pub use model::{Hours, NewHours};
+use access::Access;
+use etag::{check_if_match, etag, is_not_modified};
+pub use import::{ImportMode, ImportQuery, ImportReport, MAX_IMPORT_SIZE};
+pub use model::{Hours, HoursPatch, NewHours};
+pub use query::{HoursQuery, Page};
+pub use trash::purge_trash;
+use validation::{check_editable, validate};
 
-pub async fn list_all_logged_hours(db: Data<PgPool>) -> HttpResponse {
-    let mut connection = db.acquire().await.unwrap();
-    let all_hours = connection.list().await;
-    HttpResponse::Ok().json(all_hours)
+pub const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+pub async fn list_all_logged_hours<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    query: Query<HoursQuery>,
+) -> Result<HttpResponse, Error> {
+    let mut query = query.into_inner();
+    query.validate()?;
+    query.limit.get_or_insert(query::DEFAULT_PAGE_SIZE);
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    let page = connection.list(&query, &access.read).await?;
+    Ok(HttpResponse::Ok()
+        .insert_header((TOTAL_COUNT_HEADER, page.total.to_string()))
+        .json(page.items))
+}
+
+pub async fn export_logged_hours<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    query: Query<HoursQuery>,
+) -> Result<HttpResponse, Error> {
+    let query = query.into_inner();
+    query.validate()?;
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"hours.csv\"",
+        ))
+        .streaming(Box::pin(export::csv_stream(connection, query, access.read))))
 }
 
-pub async fn get_single_hours_entry(id: Path<Uuid>, db: Data<PgPool>) -> HttpResponse {
-    let mut connection = db.acquire().await.unwrap();
+pub async fn get_single_hours_entry<D: Database>(
+    req: HttpRequest,
+    id: Path<Uuid>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
     let id = id.into_inner();
-    match connection.by_id(id).await {
-        Some(hours) => HttpResponse::Ok().json(hours),
+    Ok(match connection.by_id(id, &access.read).await? {
+        Some(hours) if is_not_modified(&req, &hours)? => HttpResponse::NotModified()
+            .insert_header(etag(&hours))
+            .finish(),
+        Some(hours) => HttpResponse::Ok().insert_header(etag(&hours)).json(hours),
         None => HttpResponse::NotFound().json(id),
+    })
+}
+
+/// The entry is logged for the caller unless it names an employee, which only admins can do
+/// for anyone else. Requests with an `Idempotency-Key` that the caller has used within the
+/// configured window are answered with the response to the first one, without logging the entry
+/// again.
+pub async fn log_hours<D: Database>(
+    req: HttpRequest,
+    db: Data<D>,
+    config: Data<HoursConfig>,
+    identity: Identity,
+    json: web::Json<NewHours>,
+) -> Result<HttpResponse, Error> {
+    let key = idempotency::from_request(&req)?;
+    let since = Utc::now() - chrono::Duration::hours(config.idempotency.window.into());
+    let mut connection = db.begin().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    if let Some(key) = &key {
+        if let Some(stored) = connection
+            .idempotency_key(&identity.subject, key, since)
+            .await?
+        {
+            access.read.check(&stored.response.employee)?;
+            return Ok(stored.replay());
+        }
     }
+    let mut new_hours = json.into_inner();
+    new_hours.default_employee(&identity.subject);
+    access.write.check(&new_hours.employee)?;
+    new_hours.round(config.rounding);
+    connection.lock_employees(&[&new_hours.employee]).await?;
+    check_editable(&mut connection, &new_hours.employee, new_hours.date).await?;
+    validate(&mut connection, &new_hours, None, &config).await?;
+    let hours_entry = match &key {
+        Some(key) => {
+            match connection
+                .insert_idempotent(new_hours, &identity.subject, key, since, &identity.subject)
+                .await
+            {
+                // Another request with the same key got there first.
+                Err(Error::Conflict(message)) => {
+                    return match connection
+                        .idempotency_key(&identity.subject, key, since)
+                        .await?
+                    {
+                        Some(stored) => {
+                            access.read.check(&stored.response.employee)?;
+                            Ok(stored.replay())
+                        }
+                        None => Err(Error::Conflict(message)),
+                    }
+                }
+                result => result?,
+            }
+        }
+        None => connection.insert(new_hours, &identity.subject).await?,
+    };
+    connection.commit().await?;
+    Ok(HttpResponse::Created()
+        .insert_header(etag(&hours_entry))
+        .json(hours_entry))
 }
 
-pub async fn log_hours(db: Data<PgPool>, json: web::Json<NewHours>) -> HttpResponse {
-    let new_hours = json.into_inner();
-    match new_hours.validate() {
-        Err(errors) => HttpResponse::BadRequest().json(ErrorResponse::with_validation_errors(
-            "Validation errors".into(),
-            errors,
-        )),
-        Ok(_) => {
-            let mut connection = db.acquire().await.unwrap();
-            let hours_entry = connection.insert(new_hours).await;
-            HttpResponse::Created().json(hours_entry)
+pub async fn import_hours<D: Database>(
+    req: HttpRequest,
+    db: Data<D>,
+    config: Data<HoursConfig>,
+    identity: Identity,
+    query: Query<ImportQuery>,
+    body: Bytes,
+) -> Result<HttpResponse, Error> {
+    let rows = match req.content_type() {
+        "text/csv" => import::parse_csv(&body),
+        "application/json" | "" => import::parse_json(&body),
+        other => {
+            return Ok(
+                HttpResponse::UnsupportedMediaType().json(ErrorResponse::new(format!(
+                    "Can not import {}, use text/csv or application/json",
+                    other
+                ))),
+            )
         }
+    };
+    let mut rows = match rows {
+        Ok(rows) => rows,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest()
+                .json(ErrorResponse::new(format!("Invalid import: {}", e))))
+        }
+    };
+    let mut connection = db.begin().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    for new_hours in rows.iter_mut().flatten() {
+        new_hours.default_employee(&identity.subject);
+        access.write.check(&new_hours.employee)?;
+        new_hours.round(config.rounding);
+    }
+    let employees: Vec<&str> = rows
+        .iter()
+        .flatten()
+        .map(|new_hours| new_hours.employee.as_str())
+        .collect();
+    connection.lock_employees(&employees).await?;
+    let (valid, errors) = import::validate_rows(&mut connection, rows, &config).await?;
+    if query.mode == ImportMode::Atomic && !errors.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ImportReport {
+            inserted: vec![],
+            errors,
+        }));
     }
+    let inserted = connection.insert_all(valid, &identity.subject).await?;
+    connection.commit().await?;
+    Ok(HttpResponse::Created().json(ImportReport { inserted, errors }))
+}
+
+pub async fn replace_logged_hours<D: Database>(
+    req: HttpRequest,
+    id: Path<Uuid>,
+    db: Data<D>,
+    config: Data<HoursConfig>,
+    identity: Identity,
+    json: web::Json<NewHours>,
+) -> Result<HttpResponse, Error> {
+    let mut new_hours = json.into_inner();
+    new_hours.round(config.rounding);
+    let mut connection = db.begin().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    let id = id.into_inner();
+    let existing = match connection.by_id(id, &access.read).await? {
+        Some(hours) => hours,
+        None => return Ok(HttpResponse::NotFound().json(id)),
+    };
+    access.write.check(&existing.employee)?;
+    new_hours.default_employee(&existing.employee);
+    access.write.check(&new_hours.employee)?;
+    check_if_match(&req, &existing)?;
+    connection
+        .lock_employees(&[&existing.employee, &new_hours.employee])
+        .await?;
+    check_editable(&mut connection, &existing.employee, existing.date).await?;
+    check_editable(&mut connection, &new_hours.employee, new_hours.date).await?;
+    validate(&mut connection, &new_hours, Some(id), &config).await?;
+    let updated = connection
+        .update(
+            id,
+            new_hours,
+            existing.version,
+            &access.write,
+            &identity.subject,
+        )
+        .await?;
+    connection.commit().await?;
+    Ok(match updated {
+        Some(hours) => HttpResponse::Ok().insert_header(etag(&hours)).json(hours),
+        None => HttpResponse::NotFound().json(id),
+    })
 }
 
-pub async fn delete_logged_hours(id: Path<Uuid>, db: Data<PgPool>) -> HttpResponse {
-    let mut connection = db.acquire().await.unwrap();
+pub async fn update_logged_hours<D: Database>(
+    req: HttpRequest,
+    id: Path<Uuid>,
+    db: Data<D>,
+    config: Data<HoursConfig>,
+    identity: Identity,
+    json: web::Json<HoursPatch>,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.begin().await?;
+    let access = Access::of(&mut connection, &identity).await?;
     let id = id.into_inner();
-    match connection.delete(id).await {
+    let existing = match connection.by_id(id, &access.read).await? {
+        Some(hours) => hours,
+        None => return Ok(HttpResponse::NotFound().json(id)),
+    };
+    access.write.check(&existing.employee)?;
+    check_if_match(&req, &existing)?;
+    let (version, employee, date) = (existing.version, existing.employee.clone(), existing.date);
+    let mut new_hours = json.into_inner().apply(existing);
+    access.write.check(&new_hours.employee)?;
+    new_hours.round(config.rounding);
+    connection
+        .lock_employees(&[&employee, &new_hours.employee])
+        .await?;
+    check_editable(&mut connection, &employee, date).await?;
+    check_editable(&mut connection, &new_hours.employee, new_hours.date).await?;
+    validate(&mut connection, &new_hours, Some(id), &config).await?;
+    let updated = connection
+        .update(id, new_hours, version, &access.write, &identity.subject)
+        .await?;
+    connection.commit().await?;
+    Ok(match updated {
+        Some(hours) => HttpResponse::Ok().insert_header(etag(&hours)).json(hours),
+        None => HttpResponse::NotFound().json(id),
+    })
+}
+
+pub async fn delete_logged_hours<D: Database>(
+    req: HttpRequest,
+    id: Path<Uuid>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.begin().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    let id = id.into_inner();
+    let existing = match connection.by_id(id, &access.read).await? {
+        Some(hours) => hours,
+        None => return Ok(HttpResponse::NotFound().json(id)),
+    };
+    access.write.check(&existing.employee)?;
+    check_if_match(&req, &existing)?;
+    connection.lock_employees(&[&existing.employee]).await?;
+    check_editable(&mut connection, &existing.employee, existing.date).await?;
+    let deleted = connection
+        .delete(id, existing.version, &access.write, &identity.subject)
+        .await?;
+    connection.commit().await?;
+    Ok(match deleted {
         true => HttpResponse::NoContent().finish(),
         false => HttpResponse::NotFound().json(id),
-    }
+    })
+}
+
+pub async fn list_trash<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    Ok(HttpResponse::Ok().json(connection.trash(&access.read).await?))
+}
+
+/// Takes a deleted entry out of the trash. It is validated again, the time it takes up may have
+/// been logged by another entry since.
+pub async fn restore_logged_hours<D: Database>(
+    req: HttpRequest,
+    id: Path<Uuid>,
+    db: Data<D>,
+    config: Data<HoursConfig>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.begin().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    let id = id.into_inner();
+    let trashed = match connection.trash_by_id(id, &access.read).await? {
+        Some(hours) => hours,
+        None => return Ok(HttpResponse::NotFound().json(id)),
+    };
+    access.write.check(&trashed.employee)?;
+    check_if_match(&req, &trashed)?;
+    let version = trashed.version;
+    connection.lock_employees(&[&trashed.employee]).await?;
+    check_editable(&mut connection, &trashed.employee, trashed.date).await?;
+    validate(&mut connection, &NewHours::from(trashed), None, &config).await?;
+    let restored = connection
+        .restore(id, version, &access.write, &identity.subject)
+        .await?;
+    connection.commit().await?;
+    Ok(match restored {
+        Some(hours) => HttpResponse::Ok().insert_header(etag(&hours)).json(hours),
+        None => HttpResponse::NotFound().json(id),
+    })
+}
+
+/// Every change made to the entry, including its deletion.
+pub async fn hours_history<D: Database>(
+    id: Path<Uuid>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    let id = id.into_inner();
+    let history = connection.history(id, &access.read).await?;
+    Ok(if history.is_empty() {
+        HttpResponse::NotFound().json(id)
+    } else {
+        HttpResponse::Ok().json(history)
+    })
 }
@@ -1,38 +1,236 @@
 use std::convert::TryFrom;
 
+use super::duration;
 use crate::error::{FieldValidationError, Validated};
-use chrono::NaiveDate;
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::postgres::PgRow;
 use sqlx::Row;
 use uuid::Uuid;
 
+/// A new hours entry. The logged time is given either as `hours`, which may be fractional, as
+/// an ISO 8601 `duration` or as the `start_time` and `end_time` of the work.
 #[derive(Debug, Deserialize)]
+#[serde(try_from = "NewHoursInput")]
 pub struct NewHours {
     pub employee: String,
     pub date: NaiveDate,
     pub project: String,
     pub story_id: Option<String>,
     pub description: String,
-    pub hours: i16,
+    /// Derived from `start_time` and `end_time` when they are given.
+    pub minutes: i32,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+}
+
+#[derive(Deserialize)]
+struct NewHoursInput {
+    #[serde(default)]
+    employee: String,
+    date: NaiveDate,
+    project: String,
+    story_id: Option<String>,
+    description: String,
+    hours: Option<f64>,
+    duration: Option<String>,
+    start_time: Option<NaiveTime>,
+    end_time: Option<NaiveTime>,
+}
+
+impl TryFrom<NewHoursInput> for NewHours {
+    type Error = String;
+
+    fn try_from(input: NewHoursInput) -> Result<Self, Self::Error> {
+        let time = LoggedTime::from_fields(
+            input.hours,
+            input.duration.as_deref(),
+            input.start_time,
+            input.end_time,
+        )?
+        .ok_or("missing field `hours`, `duration` or `start_time` and `end_time`")?;
+        let mut new_hours = NewHours {
+            employee: input.employee,
+            date: input.date,
+            project: input.project,
+            story_id: input.story_id,
+            description: input.description,
+            minutes: 0,
+            start_time: None,
+            end_time: None,
+        };
+        new_hours.set_time(time);
+        Ok(new_hours)
+    }
+}
+
+/// The logged time of an entry as the client stated it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoggedTime {
+    Minutes(i32),
+    Span(NaiveTime, NaiveTime),
+}
+
+impl LoggedTime {
+    /// Accepts exactly one of `hours`, `duration` or `start_time` together with `end_time`.
+    fn from_fields(
+        hours: Option<f64>,
+        duration: Option<&str>,
+        start_time: Option<NaiveTime>,
+        end_time: Option<NaiveTime>,
+    ) -> Result<Option<LoggedTime>, String> {
+        match (start_time, end_time) {
+            (None, None) => Ok(duration::minutes_from(hours, duration)?.map(LoggedTime::Minutes)),
+            (Some(start), Some(end)) if hours.is_none() && duration.is_none() => {
+                Ok(Some(LoggedTime::Span(start, end)))
+            }
+            (Some(_), Some(_)) => {
+                Err("hours or duration can not be given with start_time and end_time".to_owned())
+            }
+            _ => Err("start_time and end_time have to be given together".to_owned()),
+        }
+    }
+}
+
+impl NewHours {
+    pub fn set_time(&mut self, time: LoggedTime) {
+        match time {
+            LoggedTime::Minutes(minutes) => {
+                self.minutes = minutes;
+                self.start_time = None;
+                self.end_time = None;
+            }
+            LoggedTime::Span(start, end) => {
+                self.minutes = (end - start).num_minutes() as i32;
+                self.start_time = Some(start);
+                self.end_time = Some(end);
+            }
+        }
+    }
+
+    /// Rounds the logged time to the nearest multiple of `increment` minutes. Entries with a
+    /// start and end time are kept as they are so that the duration matches the times, and so are
+    /// times that do not fit in a day, for the validation to refuse.
+    pub fn round(&mut self, increment: u16) {
+        if self.start_time.is_some() || !(0..=duration::MINUTES_PER_DAY).contains(&self.minutes) {
+            return;
+        }
+        if let Some(minutes) = duration::round(self.minutes, increment) {
+            self.minutes = minutes;
+        }
+    }
+
+    /// Entries that do not name an employee are logged for `subject`, the caller.
+    pub fn default_employee(&mut self, subject: &str) {
+        if self.employee.is_empty() {
+            self.employee = subject.to_owned();
+        }
+    }
+
+    /// The start and end time of the entry, if they are known.
+    pub fn span(&self) -> Option<(NaiveTime, NaiveTime)> {
+        self.start_time.zip(self.end_time)
+    }
 }
 
 impl Validated for NewHours {
     fn validate(&self) -> Result<(), Vec<FieldValidationError>> {
-        match self.hours {
-            0 => Err(vec![FieldValidationError::new(
-                "hours".to_owned(),
-                "can not be zero".to_owned(),
-            )]),
-            1..=24 => Ok(()),
-            _ => Err(vec![FieldValidationError::new(
-                "hours".to_owned(),
-                "can not be larger than 24".to_owned(),
-            )]),
+        if let Some((start, end)) = self.span() {
+            if end <= start {
+                return Err(vec![FieldValidationError::new(
+                    "end_time".to_owned(),
+                    "must be after start_time".to_owned(),
+                )]);
+            }
+        }
+        let error = match self.minutes {
+            0 => "can not be zero",
+            m if m < 0 => "can not be negative",
+            m if m > duration::MINUTES_PER_DAY => "can not be larger than 24",
+            _ => return Ok(()),
+        };
+        Err(vec![FieldValidationError::new(
+            "hours".to_owned(),
+            error.to_owned(),
+        )])
+    }
+}
+
+/// A partial update of an hours entry. Fields that are left out keep their current value.
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "HoursPatchInput")]
+pub struct HoursPatch {
+    pub employee: Option<String>,
+    pub date: Option<NaiveDate>,
+    pub project: Option<String>,
+    pub story_id: Option<Option<String>>,
+    pub description: Option<String>,
+    pub time: Option<LoggedTime>,
+}
+
+#[derive(Deserialize)]
+struct HoursPatchInput {
+    employee: Option<String>,
+    date: Option<NaiveDate>,
+    project: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    story_id: Option<Option<String>>,
+    description: Option<String>,
+    hours: Option<f64>,
+    duration: Option<String>,
+    start_time: Option<NaiveTime>,
+    end_time: Option<NaiveTime>,
+}
+
+impl TryFrom<HoursPatchInput> for HoursPatch {
+    type Error = String;
+
+    fn try_from(input: HoursPatchInput) -> Result<Self, Self::Error> {
+        Ok(HoursPatch {
+            time: LoggedTime::from_fields(
+                input.hours,
+                input.duration.as_deref(),
+                input.start_time,
+                input.end_time,
+            )?,
+            employee: input.employee,
+            date: input.date,
+            project: input.project,
+            story_id: input.story_id,
+            description: input.description,
+        })
+    }
+}
+
+impl HoursPatch {
+    /// A new `hours` or `duration` replaces the start and end time of the entry.
+    pub fn apply(self, hours: Hours) -> NewHours {
+        let mut new_hours = NewHours {
+            employee: self.employee.unwrap_or(hours.employee),
+            date: self.date.unwrap_or(hours.date),
+            project: self.project.unwrap_or(hours.project),
+            story_id: self.story_id.unwrap_or(hours.story_id),
+            description: self.description.unwrap_or(hours.description),
+            minutes: hours.minutes,
+            start_time: hours.start_time,
+            end_time: hours.end_time,
+        };
+        if let Some(time) = self.time {
+            new_hours.set_time(time);
         }
+        new_hours
     }
 }
 
+/// Distinguishes an explicit `null` from a missing field.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Hours {
     pub id: Uuid,
@@ -41,11 +239,30 @@ pub struct Hours {
     pub project: String,
     pub story_id: Option<String>,
     pub description: String,
-    pub hours: i16,
+    #[serde(
+        rename = "hours",
+        serialize_with = "duration::serialize_as_hours",
+        deserialize_with = "duration::deserialize_hours"
+    )]
+    pub minutes: i32,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    /// When the entry was moved to the trash, only set on entries in it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Incremented on every change, the entity tag of the entry.
+    pub version: i32,
+    /// Who logged the entry on behalf of the employee, `None` when they logged it themselves.
+    #[serde(default)]
+    pub logged_by: Option<String>,
 }
 
 impl Hours {
-    pub fn new(new_hours: NewHours) -> Hours {
+    /// The entry as logged by `actor`.
+    pub fn new(new_hours: NewHours, actor: &str) -> Hours {
+        let logged_by = Some(actor)
+            .filter(|actor| *actor != new_hours.employee)
+            .map(str::to_owned);
         Hours {
             id: Uuid::new_v4(),
             employee: new_hours.employee,
@@ -53,7 +270,27 @@ impl Hours {
             project: new_hours.project,
             story_id: new_hours.story_id,
             description: new_hours.description,
-            hours: new_hours.hours,
+            minutes: new_hours.minutes,
+            start_time: new_hours.start_time,
+            end_time: new_hours.end_time,
+            deleted_at: None,
+            version: 1,
+            logged_by,
+        }
+    }
+}
+
+impl From<Hours> for NewHours {
+    fn from(hours: Hours) -> Self {
+        NewHours {
+            employee: hours.employee,
+            date: hours.date,
+            project: hours.project,
+            story_id: hours.story_id,
+            description: hours.description,
+            minutes: hours.minutes,
+            start_time: hours.start_time,
+            end_time: hours.end_time,
         }
     }
 }
@@ -69,7 +306,209 @@ impl TryFrom<PgRow> for Hours {
             project: value.try_get("project")?,
             story_id: value.try_get("story_id")?,
             description: value.try_get("description")?,
-            hours: value.try_get("hours")?,
+            minutes: value.try_get("minutes")?,
+            start_time: value.try_get("start_time")?,
+            end_time: value.try_get("end_time")?,
+            deleted_at: value.try_get("deleted_at")?,
+            version: value.try_get("version")?,
+            logged_by: value.try_get("logged_by")?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patch_keeps_missing_fields() {
+        let patch: HoursPatch = serde_json::from_str(r#"{"description": "changed"}"#).unwrap();
+
+        let result = patch.apply(get_hours());
+
+        assert_eq!(result.description, "changed");
+        assert_eq!(result.story_id, Some("story".to_owned()));
+        assert_eq!(result.minutes, 60);
+    }
+
+    #[test]
+    fn patch_accepts_duration() {
+        let patch: HoursPatch = serde_json::from_str(r#"{"duration": "PT30M"}"#).unwrap();
+
+        let result = patch.apply(get_hours());
+
+        assert_eq!(result.minutes, 30);
+    }
+
+    #[test]
+    fn new_hours_accepts_hours_or_duration() {
+        let integer: NewHours = serde_json::from_value(new_hours_json("hours", 2.into())).unwrap();
+        let decimal: NewHours =
+            serde_json::from_value(new_hours_json("hours", 1.5.into())).unwrap();
+        let duration: NewHours =
+            serde_json::from_value(new_hours_json("duration", "PT1H30M".into())).unwrap();
+
+        assert_eq!(integer.minutes, 120);
+        assert_eq!(decimal.minutes, 90);
+        assert_eq!(duration.minutes, 90);
+    }
+
+    #[test]
+    fn new_hours_requires_exactly_one_of_hours_and_duration() {
+        let mut both = new_hours_json("hours", 1.into());
+        both["duration"] = "PT1H".into();
+        let mut neither = new_hours_json("hours", 1.into());
+        neither.as_object_mut().unwrap().remove("hours");
+
+        assert!(serde_json::from_value::<NewHours>(both).is_err());
+        assert!(serde_json::from_value::<NewHours>(neither).is_err());
+    }
+
+    #[test]
+    fn new_hours_derives_minutes_from_times() {
+        let mut json = new_hours_json("start_time", "09:15:00".into());
+        json["end_time"] = "10:45:00".into();
+
+        let result: NewHours = serde_json::from_value(json).unwrap();
+
+        assert_eq!(result.minutes, 90);
+        assert_eq!(result.start_time, Some(NaiveTime::from_hms(9, 15, 0)));
+    }
+
+    #[test]
+    fn new_hours_requires_both_times() {
+        let only_start = new_hours_json("start_time", "09:15:00".into());
+        let mut with_hours = new_hours_json("hours", 1.into());
+        with_hours["start_time"] = "09:00:00".into();
+        with_hours["end_time"] = "10:00:00".into();
+
+        assert!(serde_json::from_value::<NewHours>(only_start).is_err());
+        assert!(serde_json::from_value::<NewHours>(with_hours).is_err());
+    }
+
+    #[test]
+    fn validate_end_time_after_start_time() {
+        let mut json = new_hours_json("start_time", "10:00:00".into());
+        json["end_time"] = "09:00:00".into();
+        let new_hours: NewHours = serde_json::from_value(json).unwrap();
+
+        assert_eq!(
+            new_hours.validate(),
+            Err(vec![FieldValidationError::new(
+                "end_time".to_owned(),
+                "must be after start_time".to_owned()
+            )])
+        );
+    }
+
+    #[test]
+    fn round_keeps_times() {
+        let mut json = new_hours_json("start_time", "09:00:00".into());
+        json["end_time"] = "09:52:00".into();
+        let mut new_hours: NewHours = serde_json::from_value(json).unwrap();
+
+        new_hours.round(15);
+
+        assert_eq!(new_hours.minutes, 52);
+    }
+
+    #[test]
+    fn round_leaves_more_than_a_day_to_validation() {
+        let mut new_hours: NewHours =
+            serde_json::from_value(new_hours_json("hours", 1.into())).unwrap();
+        new_hours.minutes = i32::MAX;
+
+        new_hours.round(15);
+
+        assert_eq!(new_hours.minutes, i32::MAX);
+        assert_eq!(
+            new_hours.validate(),
+            Err(vec![FieldValidationError::new(
+                "hours".to_owned(),
+                "can not be larger than 24".to_owned()
+            )])
+        );
+    }
+
+    #[test]
+    fn patch_with_hours_clears_times() {
+        let mut hours = get_hours();
+        hours.start_time = Some(NaiveTime::from_hms(9, 0, 0));
+        hours.end_time = Some(NaiveTime::from_hms(10, 0, 0));
+        let patch: HoursPatch = serde_json::from_str(r#"{"hours": 2}"#).unwrap();
+
+        let result = patch.apply(hours);
+
+        assert_eq!(result.minutes, 120);
+        assert_eq!(result.span(), None);
+    }
+
+    #[test]
+    fn hours_serialize_whole_hours_as_integers() {
+        let mut hours = get_hours();
+        assert_eq!(serde_json::to_value(&hours).unwrap()["hours"], 1);
+
+        hours.minutes = 90;
+        assert_eq!(serde_json::to_value(&hours).unwrap()["hours"], 1.5);
+    }
+
+    #[test]
+    fn validate_limits_logged_time() {
+        let mut new_hours: NewHours =
+            serde_json::from_value(new_hours_json("hours", 1.into())).unwrap();
+        assert!(new_hours.validate().is_ok());
+
+        for (minutes, error) in &[
+            (0, "can not be zero"),
+            (-30, "can not be negative"),
+            (24 * 60 + 1, "can not be larger than 24"),
+        ] {
+            new_hours.minutes = *minutes;
+            assert_eq!(
+                new_hours.validate(),
+                Err(vec![FieldValidationError::new(
+                    "hours".to_owned(),
+                    error.to_string()
+                )])
+            );
+        }
+    }
+
+    fn new_hours_json(field: &str, value: serde_json::Value) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "employee": "employee",
+            "date": "2021-10-09",
+            "project": "project",
+            "story_id": null,
+            "description": "description"
+        });
+        json[field] = value;
+        json
+    }
+
+    #[test]
+    fn patch_clears_story_id_on_null() {
+        let patch: HoursPatch = serde_json::from_str(r#"{"story_id": null}"#).unwrap();
+
+        let result = patch.apply(get_hours());
+
+        assert_eq!(result.story_id, None);
+    }
+
+    fn get_hours() -> Hours {
+        Hours {
+            id: Uuid::new_v4(),
+            employee: "employee".to_owned(),
+            date: NaiveDate::from_ymd(2021, 10, 9),
+            project: "project".to_owned(),
+            story_id: Some("story".to_owned()),
+            description: "description".to_owned(),
+            minutes: 60,
+            start_time: None,
+            end_time: None,
+            deleted_at: None,
+            version: 1,
+            logged_by: None,
+        }
+    }
+}
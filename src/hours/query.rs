@@ -0,0 +1,105 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::error::{FieldValidationError, Validated};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 100;
+pub const MAX_PAGE_SIZE: u32 = 1000;
+
+/// Query parameters accepted by the hours listing.
+///
+/// `from` and `to` are inclusive. A missing `limit` means "no limit" for the repository, the
+/// handler substitutes [`DEFAULT_PAGE_SIZE`] before it gets that far.
+#[derive(Debug, Default, Deserialize, Clone)]
+pub struct HoursQuery {
+    pub employee: Option<String>,
+    pub project: Option<String>,
+    pub story_id: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    pub sort: SortField,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub offset: u32,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    Date,
+    Employee,
+    Project,
+    StoryId,
+    Hours,
+}
+
+impl SortField {
+    pub fn column(self) -> &'static str {
+        match self {
+            SortField::Date => "date",
+            SortField::Employee => "employee",
+            SortField::Project => "project",
+            SortField::StoryId => "story_id",
+            SortField::Hours => "minutes",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn keyword(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+impl Validated for HoursQuery {
+    fn validate(&self) -> Result<(), Vec<FieldValidationError>> {
+        let mut errors = vec![];
+        if self.limit == Some(0) {
+            errors.push(FieldValidationError::new(
+                "limit".to_owned(),
+                "can not be zero".to_owned(),
+            ));
+        }
+        if matches!(self.limit, Some(limit) if limit > MAX_PAGE_SIZE) {
+            errors.push(FieldValidationError::new(
+                "limit".to_owned(),
+                format!("can not be larger than {}", MAX_PAGE_SIZE),
+            ));
+        }
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                errors.push(FieldValidationError::new(
+                    "to".to_owned(),
+                    "can not be before from".to_owned(),
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// A single page of results along with the number of rows matching the filters.
+#[derive(Debug, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+}
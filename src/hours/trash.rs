@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use actix_web::rt::time;
+use chrono::Utc;
+
+use crate::configuration::HoursConfig;
+use crate::db::{Database, HoursRepo};
+use crate::error::Error;
+
+/// Recorded as the actor of purges in the history of the entries.
+const PURGE_ACTOR: &str = "retention";
+
+/// The longest interval in minutes, a week. Much longer ones overflow when the next purge is
+/// scheduled.
+const MAX_INTERVAL: u64 = 7 * 24 * 60;
+
+/// Purges the entries that have been in the trash for longer than the retention period, and the
+/// idempotency keys that have expired, once every interval for as long as it runs.
+pub async fn purge_trash<D: Database>(db: D, config: HoursConfig) {
+    let mut interval = time::interval(Duration::from_secs(
+        config.trash.interval.clamp(1, MAX_INTERVAL) * 60,
+    ));
+    loop {
+        interval.tick().await;
+        match purge_expired(&db, config.trash.retention).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("Purged {} hours entries from the trash", count),
+            Err(e) => log::error!("Purging the trash failed: {}", e),
+        }
+        match purge_idempotency_keys(&db, config.idempotency.window).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("Purged {} expired idempotency keys", count),
+            Err(e) => log::error!("Purging the idempotency keys failed: {}", e),
+        }
+    }
+}
+
+async fn purge_expired<D: Database>(db: &D, retention: u32) -> Result<u64, Error> {
+    let deleted_before =
+        match Utc::now().checked_sub_signed(chrono::Duration::days(retention.into())) {
+            Some(deleted_before) => deleted_before,
+            // Nothing can have been deleted that long ago.
+            None => return Ok(0),
+        };
+    let mut connection = db.connection().await?;
+    connection.purge_trash(deleted_before, PURGE_ACTOR).await
+}
+
+async fn purge_idempotency_keys<D: Database>(db: &D, window: u32) -> Result<u64, Error> {
+    let used_before = match Utc::now().checked_sub_signed(chrono::Duration::hours(window.into())) {
+        Some(used_before) => used_before,
+        None => return Ok(0),
+    };
+    let mut connection = db.connection().await?;
+    connection.purge_idempotency_keys(used_before).await
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::db::MemoryDatabase;
+    use crate::hours::access::Scope;
+    use crate::hours::audit::Operation;
+    use crate::hours::NewHours;
+
+    #[actix_rt::test]
+    async fn purge_expired_keeps_recent_trash() {
+        let mut db = MemoryDatabase::default();
+        let hours = db.insert(get_hours(), "tester").await.unwrap();
+        db.delete(hours.id, 1, &Scope::All, "tester").await.unwrap();
+
+        assert_eq!(purge_expired(&db, 1).await.unwrap(), 0);
+        assert_eq!(purge_expired(&db, u32::MAX).await.unwrap(), 0);
+        assert_eq!(purge_expired(&db, 0).await.unwrap(), 1);
+
+        assert!(db.trash(&Scope::All).await.unwrap().is_empty());
+        let history = db.history(hours.id, &Scope::All).await.unwrap();
+        assert_eq!(history.last().unwrap().operation, Operation::Purge);
+        assert_eq!(history.last().unwrap().actor, PURGE_ACTOR);
+    }
+
+    #[actix_rt::test]
+    async fn purge_trash_with_a_huge_interval() {
+        let mut config = HoursConfig::default();
+        config.trash.interval = u64::MAX;
+        let task = actix_web::rt::spawn(purge_trash(MemoryDatabase::default(), config));
+
+        let finished = time::timeout(Duration::from_millis(50), task).await;
+
+        assert!(finished.is_err(), "the purge stopped: {:?}", finished);
+    }
+
+    #[actix_rt::test]
+    async fn purge_idempotency_keys_keeps_recent_keys() {
+        let mut db = MemoryDatabase::default();
+        let since = Utc::now() - chrono::Duration::hours(1);
+        db.insert_idempotent(get_hours(), "employee", "key", since, "tester")
+            .await
+            .unwrap();
+
+        assert_eq!(purge_idempotency_keys(&db, 1).await.unwrap(), 0);
+        assert_eq!(purge_idempotency_keys(&db, 0).await.unwrap(), 1);
+
+        assert_eq!(
+            db.idempotency_key("employee", "key", since).await.unwrap(),
+            None
+        );
+    }
+
+    fn get_hours() -> NewHours {
+        NewHours {
+            employee: "employee".to_owned(),
+            date: NaiveDate::from_ymd(2021, 10, 9),
+            project: "project".to_owned(),
+            story_id: None,
+            description: "description".to_owned(),
+            minutes: 60,
+            start_time: None,
+            end_time: None,
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use super::duration::{format_hours, MINUTES_PER_HOUR};
+use super::NewHours;
+use crate::configuration::{HoursConfig, LimitConfig};
+use crate::db::{EmployeeRepo, HoursRepo, PeriodRepo, ProjectRepo, TimesheetRepo};
+use crate::error::{Error, FieldValidationError, Validated};
+use crate::periods::check_unlocked;
+use crate::reports::model::Granularity;
+use crate::timesheets::check_open;
+
+/// Validates `new_hours` on its own and against the data that is already stored. `id` is the
+/// entry that `new_hours` replaces, if any, which it can not overlap with and which does not count
+/// towards the employee's totals.
+///
+/// Field errors are returned as [`Error::Validation`], anything else is a failure to check.
+pub async fn validate<C: HoursRepo + EmployeeRepo + ProjectRepo>(
+    connection: &mut C,
+    new_hours: &NewHours,
+    id: Option<Uuid>,
+    config: &HoursConfig,
+) -> Result<(), Error> {
+    let mut errors = new_hours.validate().err().unwrap_or_default();
+    errors.extend(
+        check_references(
+            connection,
+            &new_hours.employee,
+            &new_hours.project,
+            new_hours.date,
+        )
+        .await?,
+    );
+    if errors.is_empty() {
+        for other in connection.overlapping(new_hours, id).await? {
+            errors.push(overlap_error(&other.id.to_string()));
+        }
+    }
+    if errors.is_empty() {
+        let logged = Totals::logged(connection, new_hours, id).await?;
+        errors.extend(check_limits(new_hours, logged, &config.limit));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Validation(errors))
+    }
+}
+
+/// Refuses changes to the hours of `employee` on `date` in closed periods and in timesheets that
+/// have been submitted for review.
+pub async fn check_editable<C: PeriodRepo + TimesheetRepo>(
+    connection: &mut C,
+    employee: &str,
+    date: NaiveDate,
+) -> Result<(), Error> {
+    check_unlocked(connection, date).await?;
+    check_open(connection, employee, date).await
+}
+
+/// Checks that `employee` exists and that `project` exists and accepts hours on `date`.
+pub async fn check_references<C: EmployeeRepo + ProjectRepo>(
+    connection: &mut C,
+    employee: &str,
+    project: &str,
+    date: NaiveDate,
+) -> Result<Vec<FieldValidationError>, Error> {
+    let mut errors = vec![];
+    if connection.employee_by_username(employee).await?.is_none() {
+        errors.push(FieldValidationError::new(
+            "employee".to_owned(),
+            "does not exist".to_owned(),
+        ));
+    }
+    match connection.project_by_code(project).await? {
+        None => errors.push(FieldValidationError::new(
+            "project".to_owned(),
+            "does not exist".to_owned(),
+        )),
+        Some(project) => {
+            if let Err(error) = project.check_loggable(date) {
+                errors.push(error);
+            }
+        }
+    }
+    Ok(errors)
+}
+
+/// Minutes an employee has logged on the day and in the week of an entry.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Totals {
+    pub day: i64,
+    pub week: i64,
+}
+
+impl Totals {
+    /// Totals of the entries already stored, leaving out `exclude`.
+    pub async fn logged<C: HoursRepo>(
+        connection: &mut C,
+        new_hours: &NewHours,
+        exclude: Option<Uuid>,
+    ) -> Result<Totals, Error> {
+        let (monday, sunday) = week_of(new_hours.date);
+        let day = connection
+            .logged_minutes(&new_hours.employee, new_hours.date, new_hours.date, exclude)
+            .await?;
+        let week = connection
+            .logged_minutes(&new_hours.employee, monday, sunday, exclude)
+            .await?;
+        Ok(Totals { day, week })
+    }
+
+    /// Adds `other` to the totals if it counts towards the same day or week as `new_hours`.
+    pub fn add(&mut self, new_hours: &NewHours, other: &NewHours) {
+        if other.employee != new_hours.employee {
+            return;
+        }
+        if other.date == new_hours.date {
+            self.day += i64::from(other.minutes);
+        }
+        if week_of(other.date) == week_of(new_hours.date) {
+            self.week += i64::from(other.minutes);
+        }
+    }
+}
+
+/// Checks that adding `new_hours` to the `logged` totals keeps them within the limits.
+pub(crate) fn check_limits(
+    new_hours: &NewHours,
+    logged: Totals,
+    limit: &LimitConfig,
+) -> Vec<FieldValidationError> {
+    let minutes = i64::from(new_hours.minutes);
+    let mut errors = vec![];
+    if logged.day + minutes > limit_minutes(limit.day) {
+        errors.push(FieldValidationError::new(
+            "hours".to_owned(),
+            format!(
+                "would make {} hours on {}, the daily maximum is {}, {} hours are already logged",
+                format_hours(logged.day + minutes),
+                new_hours.date,
+                format_hours(limit_minutes(limit.day)),
+                format_hours(logged.day),
+            ),
+        ));
+    }
+    if logged.week + minutes > limit_minutes(limit.week) {
+        errors.push(FieldValidationError::new(
+            "hours".to_owned(),
+            format!(
+                "would make {} hours in the week starting {}, the weekly maximum is {}, {} hours are already logged",
+                format_hours(logged.week + minutes),
+                week_of(new_hours.date).0,
+                format_hours(limit_minutes(limit.week)),
+                format_hours(logged.week),
+            ),
+        ));
+    }
+    errors
+}
+
+fn limit_minutes(hours: f64) -> i64 {
+    (hours * f64::from(MINUTES_PER_HOUR)).round() as i64
+}
+
+/// The Monday and Sunday of the week `date` is in.
+fn week_of(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let monday = Granularity::Week.period_start(date);
+    (monday, monday + chrono::Duration::days(6))
+}
+
+pub(crate) fn overlap_error(other: &str) -> FieldValidationError {
+    FieldValidationError::new(
+        "start_time".to_owned(),
+        format!("overlaps with entry {}", other),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::*;
+    use crate::db::MemoryDatabase;
+    use crate::hours::model::LoggedTime;
+    use crate::projects::NewProject;
+    use crate::test_utils;
+
+    #[actix_rt::test]
+    async fn validate_unknown_references() {
+        let mut db = MemoryDatabase::default();
+
+        let result = validate(&mut db, &get_hours(25), None, &HoursConfig::default()).await;
+
+        match result {
+            Err(Error::Validation(errors)) => assert_eq!(
+                errors,
+                vec![
+                    FieldValidationError::new(
+                        "hours".to_owned(),
+                        "can not be larger than 24".to_owned()
+                    ),
+                    FieldValidationError::new("employee".to_owned(), "does not exist".to_owned()),
+                    FieldValidationError::new("project".to_owned(), "does not exist".to_owned()),
+                ]
+            ),
+            _ => panic!("Expected validation errors."),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn validate_known_references() {
+        let mut db = MemoryDatabase::default();
+        test_utils::internal::seed(&mut db).await;
+
+        assert!(
+            validate(&mut db, &get_hours(1), None, &HoursConfig::default())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn validate_archived_project() {
+        let mut db = MemoryDatabase::default();
+        test_utils::internal::seed(&mut db).await;
+        let project = db.project_by_code("project").await.unwrap().unwrap();
+        db.update_project(
+            project.id,
+            NewProject {
+                code: "project".to_owned(),
+                name: "Project".to_owned(),
+                start_date: None,
+                end_date: None,
+                archived: true,
+            },
+        )
+        .await
+        .unwrap();
+
+        let result = validate(&mut db, &get_hours(1), None, &HoursConfig::default()).await;
+
+        match result {
+            Err(Error::Validation(errors)) => assert_eq!(
+                errors,
+                vec![FieldValidationError::new(
+                    "project".to_owned(),
+                    "is archived".to_owned()
+                )]
+            ),
+            _ => panic!("Expected validation errors."),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn validate_overlapping_times() {
+        let mut db = MemoryDatabase::default();
+        test_utils::internal::seed(&mut db).await;
+        let existing = db.insert(get_span(9, 10), "tester").await.unwrap();
+        db.insert(get_hours(1), "tester").await.unwrap();
+
+        let result = validate(&mut db, &get_span(9, 11), None, &HoursConfig::default()).await;
+
+        match result {
+            Err(Error::Validation(errors)) => {
+                assert_eq!(errors, vec![overlap_error(&existing.id.to_string())])
+            }
+            _ => panic!("Expected validation errors."),
+        }
+        assert!(
+            validate(&mut db, &get_span(10, 11), None, &HoursConfig::default())
+                .await
+                .is_ok()
+        );
+        assert!(validate(
+            &mut db,
+            &get_span(9, 11),
+            Some(existing.id),
+            &HoursConfig::default()
+        )
+        .await
+        .is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn validate_daily_total() {
+        let mut db = MemoryDatabase::default();
+        test_utils::internal::seed(&mut db).await;
+        let existing = db.insert(get_hours(6), "tester").await.unwrap();
+        let mut other = get_hours(6);
+        other.employee = "other".to_owned();
+        db.insert(other, "tester").await.unwrap();
+        let config = get_config(8.0, 40.0);
+
+        let result = validate(&mut db, &get_hours(3), None, &config).await;
+
+        match result {
+            Err(Error::Validation(errors)) => assert_eq!(
+                errors,
+                vec![FieldValidationError::new(
+                    "hours".to_owned(),
+                    "would make 9 hours on 2021-10-09, the daily maximum is 8, 6 hours are already logged"
+                        .to_owned()
+                )]
+            ),
+            _ => panic!("Expected validation errors."),
+        }
+        assert!(validate(&mut db, &get_hours(2), None, &config)
+            .await
+            .is_ok());
+        assert!(validate(&mut db, &get_hours(8), Some(existing.id), &config)
+            .await
+            .is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn validate_weekly_total() {
+        let mut db = MemoryDatabase::default();
+        test_utils::internal::seed(&mut db).await;
+        for day in 4..=8 {
+            let mut new_hours = get_hours(8);
+            new_hours.date = NaiveDate::from_ymd(2021, 10, day);
+            db.insert(new_hours, "tester").await.unwrap();
+        }
+        let config = get_config(8.0, 40.5);
+
+        let result = validate(&mut db, &get_hours(1), None, &config).await;
+
+        match result {
+            Err(Error::Validation(errors)) => assert_eq!(
+                errors,
+                vec![FieldValidationError::new(
+                    "hours".to_owned(),
+                    "would make 41 hours in the week starting 2021-10-04, the weekly maximum is 40.5, \
+                     40 hours are already logged"
+                        .to_owned()
+                )]
+            ),
+            _ => panic!("Expected validation errors."),
+        }
+        let mut next_week = get_hours(8);
+        next_week.date = NaiveDate::from_ymd(2021, 10, 11);
+        assert!(validate(&mut db, &next_week, None, &config).await.is_ok());
+    }
+
+    fn get_config(day: f64, week: f64) -> HoursConfig {
+        HoursConfig {
+            limit: LimitConfig { day, week },
+            ..HoursConfig::default()
+        }
+    }
+
+    fn get_span(start: u32, end: u32) -> NewHours {
+        let mut new_hours = get_hours(1);
+        new_hours.set_time(LoggedTime::Span(
+            NaiveTime::from_hms(start, 0, 0),
+            NaiveTime::from_hms(end, 0, 0),
+        ));
+        new_hours
+    }
+
+    fn get_hours(hours: i32) -> NewHours {
+        NewHours {
+            employee: "employee".to_owned(),
+            date: NaiveDate::from_ymd(2021, 10, 9),
+            project: "project".to_owned(),
+            story_id: None,
+            description: "description".to_owned(),
+            minutes: hours * 60,
+            start_time: None,
+            end_time: None,
+        }
+    }
+}
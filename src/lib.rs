@@ -1,5 +1,6 @@
 use std::io;
 use std::net::TcpListener;
+use std::sync::Arc;
 
 use actix_files::Files;
 use actix_web::{
@@ -10,11 +11,30 @@ use actix_web::{
 };
 use sqlx::{migrate::MigrateError, PgPool};
 
+use configuration::Settings;
+use db::Database;
+use rate_limit::{RateLimit, RateLimiter};
+
+mod api_keys;
+mod auth;
 pub mod configuration;
 pub mod db;
+mod employees;
 pub mod error;
 mod hours;
+mod periods;
+mod projects;
+mod rate_limit;
+mod reports;
+mod sessions;
+#[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils;
+mod timers;
+mod timesheets;
+
+pub use api_keys::create_admin_key;
+pub use auth::{JwtVerifier, OidcClient};
+pub use hours::purge_trash;
 
 async fn redirect_to_api_doc() -> HttpResponse {
     HttpResponse::TemporaryRedirect()
@@ -31,24 +51,155 @@ pub async fn init_db(pool: &PgPool) -> Result<(), MigrateError> {
     Ok(())
 }
 
-pub fn run_server(pool: PgPool, listener: TcpListener) -> io::Result<Server> {
-    let db = Data::new(pool);
+pub fn run_server<D: Database>(
+    db: D,
+    listener: TcpListener,
+    settings: Settings,
+) -> io::Result<Server> {
+    let db = Data::new(db);
+    let hours_config = Data::new(settings.hours);
+    let jwt = Data::new(settings.jwt);
+    let oidc = Data::new(settings.oidc);
+    let rate_limiter = Arc::new(RateLimiter::new(settings.rate_limit));
     let server = HttpServer::new(move || {
         App::new()
             .app_data(db.clone())
+            .app_data(hours_config.clone())
+            .app_data(jwt.clone())
+            .app_data(oidc.clone())
             .route("/", web::get().to(redirect_to_api_doc))
             .service(
                 web::scope("/api")
                     .service(web::resource("/health_check").route(web::get().to(health_check)))
                     .service(
-                        web::resource("/hours")
-                            .route(web::get().to(hours::list_all_logged_hours))
-                            .route(web::post().to(hours::log_hours)),
+                        web::scope("")
+                            .wrap(RateLimit::new(Arc::clone(&rate_limiter)))
+                            .wrap(auth::Authentication::<D>::new())
+                            .wrap(RateLimit::failed_auth(Arc::clone(&rate_limiter)))
+                            .service(
+                                web::resource("/hours")
+                                    .route(web::get().to(hours::list_all_logged_hours::<D>))
+                                    .route(web::post().to(hours::log_hours::<D>)),
+                            )
+                            .service(
+                                web::resource("/hours/import")
+                                    .app_data(web::PayloadConfig::new(hours::MAX_IMPORT_SIZE))
+                                    .route(web::post().to(hours::import_hours::<D>)),
+                            )
+                            .service(
+                                web::resource("/hours/export")
+                                    .route(web::get().to(hours::export_logged_hours::<D>)),
+                            )
+                            .service(
+                                web::resource("/employees")
+                                    .route(web::get().to(employees::list_employees::<D>))
+                                    .route(web::post().to(employees::create_employee::<D>)),
+                            )
+                            .service(
+                                web::resource("/employees/{id}")
+                                    .route(web::get().to(employees::get_employee::<D>))
+                                    .route(web::put().to(employees::replace_employee::<D>))
+                                    .route(web::delete().to(employees::delete_employee::<D>)),
+                            )
+                            .service(
+                                web::resource("/projects")
+                                    .route(web::get().to(projects::list_projects::<D>))
+                                    .route(web::post().to(projects::create_project::<D>)),
+                            )
+                            .service(
+                                web::resource("/projects/{id}")
+                                    .route(web::get().to(projects::get_project::<D>))
+                                    .route(web::put().to(projects::replace_project::<D>))
+                                    .route(web::delete().to(projects::delete_project::<D>)),
+                            )
+                            .service(
+                                web::resource("/timers")
+                                    .route(web::get().to(timers::list_timers::<D>))
+                                    .route(web::post().to(timers::start_timer::<D>)),
+                            )
+                            .service(
+                                web::resource("/timers/{id}")
+                                    .route(web::get().to(timers::get_timer::<D>))
+                                    .route(web::delete().to(timers::delete_timer::<D>)),
+                            )
+                            .service(
+                                web::resource("/timers/{id}/stop")
+                                    .route(web::post().to(timers::stop_timer::<D>)),
+                            )
+                            .service(
+                                web::resource("/timesheets")
+                                    .route(web::get().to(timesheets::list_timesheets::<D>)),
+                            )
+                            .service(
+                                web::resource("/timesheets/{employee}/{week}")
+                                    .route(web::get().to(timesheets::get_timesheet::<D>)),
+                            )
+                            .service(
+                                web::resource("/timesheets/{employee}/{week}/submit")
+                                    .route(web::post().to(timesheets::submit_timesheet::<D>)),
+                            )
+                            .service(
+                                web::resource("/timesheets/{employee}/{week}/approve")
+                                    .route(web::post().to(timesheets::approve_timesheet::<D>)),
+                            )
+                            .service(
+                                web::resource("/timesheets/{employee}/{week}/reject")
+                                    .route(web::post().to(timesheets::reject_timesheet::<D>)),
+                            )
+                            .service(
+                                web::resource("/admin/period-lock")
+                                    .route(web::get().to(periods::get_period_lock::<D>))
+                                    .route(web::put().to(periods::set_period_lock::<D>)),
+                            )
+                            .service(
+                                web::resource("/admin/period-lock/history")
+                                    .route(web::get().to(periods::period_lock_history::<D>)),
+                            )
+                            .service(
+                                web::resource("/reports/summary")
+                                    .route(web::get().to(reports::summary::<D>)),
+                            )
+                            .service(
+                                web::resource("/hours/trash")
+                                    .route(web::get().to(hours::list_trash::<D>)),
+                            )
+                            .service(
+                                web::resource("/hours/{id}/restore")
+                                    .route(web::post().to(hours::restore_logged_hours::<D>)),
+                            )
+                            .service(
+                                web::resource("/hours/{id}/history")
+                                    .route(web::get().to(hours::hours_history::<D>)),
+                            )
+                            .service(
+                                web::resource("/hours/{id}")
+                                    .route(web::get().to(hours::get_single_hours_entry::<D>))
+                                    .route(web::put().to(hours::replace_logged_hours::<D>))
+                                    .route(web::patch().to(hours::update_logged_hours::<D>))
+                                    .route(web::delete().to(hours::delete_logged_hours::<D>)),
+                            )
+                            .service(
+                                web::resource("/admin/api-keys")
+                                    .route(web::get().to(api_keys::list_api_keys::<D>))
+                                    .route(web::post().to(api_keys::create_api_key::<D>)),
+                            )
+                            .service(
+                                web::resource("/admin/api-keys/{id}")
+                                    .route(web::delete().to(api_keys::revoke_api_key::<D>)),
+                            ),
+                    ),
+            )
+            .service(
+                web::scope("/auth")
+                    .wrap(RateLimit::new(Arc::clone(&rate_limiter)))
+                    .service(web::resource("/login").route(web::get().to(sessions::login::<D>)))
+                    .service(
+                        web::resource("/callback").route(web::get().to(sessions::callback::<D>)),
                     )
+                    .service(web::resource("/logout").route(web::post().to(sessions::logout::<D>)))
                     .service(
-                        web::resource("/hours/{id}")
-                            .route(web::get().to(hours::get_single_hours_entry))
-                            .route(web::delete().to(hours::delete_logged_hours)),
+                        web::resource("/session")
+                            .route(web::get().to(sessions::current_session::<D>)),
                     ),
             )
             .service(Files::new("/openapi", "./openapi/").index_file("index.html"))
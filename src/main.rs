@@ -4,6 +4,7 @@ use std::io;
 use std::net::TcpListener;
 
 use cb_rust_demo::configuration::{self, Config};
+use cb_rust_demo::db::MemoryDatabase;
 
 fn init_logger(config: &Config) {
     let logger_environment = env_logger::Env::default().default_filter_or(&config.logging.level);
@@ -15,16 +16,46 @@ async fn main() -> io::Result<()> {
     let config = configuration::get_configuration().expect("Unable to get configuration");
     init_logger(&config);
 
-    let pool = PgPool::connect(&config.database.connection_string())
-        .await
-        .expect("Unable to get a database connection");
-    cb_rust_demo::init_db(&pool)
-        .await
-        .expect("Unable to run migrations");
-
     let port = env::var("PORT").unwrap_or_else(|_| config.server.port.to_string());
     let bind_address = format!("0.0.0.0:{}", port);
     let listener = TcpListener::bind(bind_address)?;
-    let server = cb_rust_demo::run_server(pool, listener)?;
+
+    let mut settings = config.settings();
+    settings.jwt = cb_rust_demo::JwtVerifier::load(&config.auth)
+        .await
+        .expect("Unable to load the JWKS");
+    settings.oidc = cb_rust_demo::OidcClient::discover(&config.oidc)
+        .await
+        .expect("Unable to discover the OpenID Connect provider");
+    let hours = settings.hours.clone();
+    let server = if env::args().any(|arg| arg == "--demo") {
+        log::info!("Running in demo mode, data is kept in memory only");
+        let db = MemoryDatabase::default();
+        let admin_key = cb_rust_demo::create_admin_key(&db, "demo")
+            .await
+            .expect("Unable to create an API key");
+        log::info!("Admin API key for the demo: {}", admin_key.key);
+        actix_web::rt::spawn(cb_rust_demo::purge_trash(db.clone(), hours));
+        cb_rust_demo::run_server(db, listener, settings)?
+    } else {
+        let pool = PgPool::connect(&config.database.connection_string())
+            .await
+            .expect("Unable to get a database connection");
+        cb_rust_demo::init_db(&pool)
+            .await
+            .expect("Unable to run migrations");
+        if let Some(name) = env::args()
+            .skip_while(|arg| arg != "--create-admin-key")
+            .nth(1)
+        {
+            let admin_key = cb_rust_demo::create_admin_key(&pool, &name)
+                .await
+                .expect("Unable to create an API key");
+            println!("{}", admin_key.key);
+            return Ok(());
+        }
+        actix_web::rt::spawn(cb_rust_demo::purge_trash(pool.clone(), hours));
+        cb_rust_demo::run_server(pool, listener, settings)?
+    };
     server.await
 }
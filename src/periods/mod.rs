@@ -0,0 +1,63 @@
+use actix_web::{
+    web::{self, Data},
+    HttpResponse,
+};
+use chrono::NaiveDate;
+
+use crate::auth::Identity;
+use crate::db::{Database, PeriodRepo, Transaction};
+use crate::error::Error;
+
+mod model;
+
+pub use model::{NewPeriodLock, PeriodLock};
+
+pub async fn get_period_lock<D: Database>(db: Data<D>) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    Ok(match connection.current_lock().await? {
+        Some(lock) => HttpResponse::Ok().json(lock),
+        None => HttpResponse::NotFound().finish(),
+    })
+}
+
+/// Moves the cutoff, which only admins can do. Every change is recorded along with who made it,
+/// in the same transaction.
+pub async fn set_period_lock<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    json: web::Json<NewPeriodLock>,
+) -> Result<HttpResponse, Error> {
+    identity.require_admin()?;
+    let new_lock = json.into_inner();
+    let mut connection = db.begin().await?;
+    let current = connection.current_lock().await?;
+    new_lock.validate(current.as_ref())?;
+    let lock = PeriodLock::new(new_lock, identity.subject);
+    connection.insert_lock(&lock).await?;
+    connection.commit().await?;
+    Ok(HttpResponse::Ok().json(lock))
+}
+
+/// Everyone can see who has moved the cutoff and when, like they can see the cutoff itself.
+pub async fn period_lock_history<D: Database>(db: Data<D>) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    Ok(HttpResponse::Ok().json(connection.lock_history().await?))
+}
+
+/// Refuses changes to hours on `date` once the period it is in has been closed.
+pub async fn check_unlocked<C: PeriodRepo>(
+    connection: &mut C,
+    date: NaiveDate,
+) -> Result<(), Error> {
+    match connection
+        .current_lock()
+        .await?
+        .and_then(|lock| lock.locked_until)
+    {
+        Some(until) if date <= until => Err(Error::Conflict(format!(
+            "Hours on {} can not be changed, periods up to {} are closed",
+            date, until
+        ))),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,124 @@
+use std::convert::TryFrom;
+
+use chrono::{DateTime, NaiveDate, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::error::FieldValidationError;
+
+#[derive(Debug, Deserialize)]
+pub struct NewPeriodLock {
+    /// Hours on and before this date can not be changed. `None` reopens every period.
+    pub locked_until: Option<NaiveDate>,
+    pub reason: Option<String>,
+}
+
+impl NewPeriodLock {
+    /// Moving the cutoff back reopens closed periods, which has to be explained.
+    pub fn validate(&self, current: Option<&PeriodLock>) -> Result<(), Vec<FieldValidationError>> {
+        let reopens = match current.and_then(|lock| lock.locked_until) {
+            Some(current) => self.locked_until.is_none_or(|until| until < current),
+            None => false,
+        };
+        let has_reason = self
+            .reason
+            .as_deref()
+            .is_some_and(|reason| !reason.trim().is_empty());
+        if reopens && !has_reason {
+            Err(vec![FieldValidationError::new(
+                "reason".to_owned(),
+                "is required to reopen a closed period".to_owned(),
+            )])
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PeriodLock {
+    pub id: Uuid,
+    pub locked_until: Option<NaiveDate>,
+    pub reason: Option<String>,
+    pub changed_by: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl PeriodLock {
+    /// The change time is truncated to microseconds, the precision Postgres stores it with.
+    pub fn new(new_lock: NewPeriodLock, changed_by: String) -> PeriodLock {
+        PeriodLock {
+            id: Uuid::new_v4(),
+            locked_until: new_lock.locked_until,
+            reason: new_lock
+                .reason
+                .map(|reason| reason.trim().to_owned())
+                .filter(|reason| !reason.is_empty()),
+            changed_by,
+            changed_at: Utc::now().trunc_subsecs(6),
+        }
+    }
+}
+
+impl TryFrom<PgRow> for PeriodLock {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(PeriodLock {
+            id: value.try_get("id")?,
+            locked_until: value.try_get("locked_until")?,
+            reason: value.try_get("reason")?,
+            changed_by: value.try_get("changed_by")?,
+            changed_at: value.try_get("changed_at")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving_cutoff_forward_needs_no_reason() {
+        let current = get_lock(Some(NaiveDate::from_ymd(2021, 9, 30)));
+
+        let new_lock = NewPeriodLock {
+            locked_until: Some(NaiveDate::from_ymd(2021, 10, 31)),
+            reason: None,
+        };
+
+        assert_eq!(new_lock.validate(Some(&current)), Ok(()));
+        assert_eq!(new_lock.validate(None), Ok(()));
+    }
+
+    #[test]
+    fn reopening_needs_reason() {
+        let current = get_lock(Some(NaiveDate::from_ymd(2021, 10, 31)));
+
+        for locked_until in &[Some(NaiveDate::from_ymd(2021, 9, 30)), None] {
+            let new_lock = NewPeriodLock {
+                locked_until: *locked_until,
+                reason: Some(" ".to_owned()),
+            };
+            assert_eq!(
+                new_lock.validate(Some(&current)),
+                Err(vec![FieldValidationError::new(
+                    "reason".to_owned(),
+                    "is required to reopen a closed period".to_owned()
+                )])
+            );
+        }
+    }
+
+    fn get_lock(locked_until: Option<NaiveDate>) -> PeriodLock {
+        PeriodLock::new(
+            NewPeriodLock {
+                locked_until,
+                reason: None,
+            },
+            "admin".to_owned(),
+        )
+    }
+}
@@ -0,0 +1,74 @@
+use actix_web::{
+    web::{self, Data, Path, Query},
+    HttpResponse,
+};
+use uuid::Uuid;
+
+use crate::auth::Identity;
+use crate::db::{Database, ProjectRepo};
+use crate::error::{Error, Validated};
+
+mod model;
+
+pub use model::{NewProject, Project, ProjectQuery};
+
+pub async fn list_projects<D: Database>(
+    db: Data<D>,
+    query: Query<ProjectQuery>,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    Ok(HttpResponse::Ok().json(connection.list_projects(&query).await?))
+}
+
+pub async fn get_project<D: Database>(id: Path<Uuid>, db: Data<D>) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    let id = id.into_inner();
+    Ok(match connection.project_by_id(id).await? {
+        Some(project) => HttpResponse::Ok().json(project),
+        None => HttpResponse::NotFound().json(id),
+    })
+}
+
+pub async fn create_project<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    json: web::Json<NewProject>,
+) -> Result<HttpResponse, Error> {
+    identity.require_admin()?;
+    let new_project = json.into_inner();
+    new_project.validate()?;
+    let mut connection = db.connection().await?;
+    let project = connection.insert_project(new_project).await?;
+    Ok(HttpResponse::Created().json(project))
+}
+
+pub async fn replace_project<D: Database>(
+    id: Path<Uuid>,
+    db: Data<D>,
+    identity: Identity,
+    json: web::Json<NewProject>,
+) -> Result<HttpResponse, Error> {
+    identity.require_admin()?;
+    let new_project = json.into_inner();
+    new_project.validate()?;
+    let mut connection = db.connection().await?;
+    let id = id.into_inner();
+    Ok(match connection.update_project(id, new_project).await? {
+        Some(project) => HttpResponse::Ok().json(project),
+        None => HttpResponse::NotFound().json(id),
+    })
+}
+
+pub async fn delete_project<D: Database>(
+    id: Path<Uuid>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    identity.require_admin()?;
+    let mut connection = db.connection().await?;
+    let id = id.into_inner();
+    Ok(match connection.delete_project(id).await? {
+        true => HttpResponse::NoContent().finish(),
+        false => HttpResponse::NotFound().json(id),
+    })
+}
@@ -0,0 +1,200 @@
+use std::convert::TryFrom;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::error::{FieldValidationError, Validated};
+
+#[derive(Debug, Deserialize)]
+pub struct NewProject {
+    /// The name hours are logged under.
+    pub code: String,
+    pub name: String,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+impl Validated for NewProject {
+    fn validate(&self) -> Result<(), Vec<FieldValidationError>> {
+        let mut errors = vec![];
+        if self.code.trim().is_empty() {
+            errors.push(FieldValidationError::new(
+                "code".to_owned(),
+                "can not be empty".to_owned(),
+            ));
+        }
+        if self.name.trim().is_empty() {
+            errors.push(FieldValidationError::new(
+                "name".to_owned(),
+                "can not be empty".to_owned(),
+            ));
+        }
+        if let (Some(start), Some(end)) = (self.start_date, self.end_date) {
+            if end < start {
+                errors.push(FieldValidationError::new(
+                    "end_date".to_owned(),
+                    "can not be before start_date".to_owned(),
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Project {
+    pub id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub archived: bool,
+}
+
+impl Project {
+    pub fn new(new_project: NewProject) -> Project {
+        Project {
+            id: Uuid::new_v4(),
+            code: new_project.code.trim().to_owned(),
+            name: new_project.name.trim().to_owned(),
+            start_date: new_project.start_date,
+            end_date: new_project.end_date,
+            archived: new_project.archived,
+        }
+    }
+
+    /// Finds the reason hours can not be logged to this project on `date`, if there is one.
+    pub fn check_loggable(&self, date: NaiveDate) -> Result<(), FieldValidationError> {
+        if self.archived {
+            return Err(FieldValidationError::new(
+                "project".to_owned(),
+                "is archived".to_owned(),
+            ));
+        }
+        match (self.start_date, self.end_date) {
+            (Some(start), _) if date < start => Err(FieldValidationError::new(
+                "date".to_owned(),
+                format!("is before the project starts on {}", start),
+            )),
+            (_, Some(end)) if date > end => Err(FieldValidationError::new(
+                "date".to_owned(),
+                format!("is after the project ended on {}", end),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl TryFrom<PgRow> for Project {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(Project {
+            id: value.try_get("id")?,
+            code: value.try_get("code")?,
+            name: value.try_get("name")?,
+            start_date: value.try_get("start_date")?,
+            end_date: value.try_get("end_date")?,
+            archived: value.try_get("archived")?,
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ProjectQuery {
+    pub archived: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_loggable_within_window() {
+        let project = get_project();
+
+        assert_eq!(
+            project.check_loggable(NaiveDate::from_ymd(2021, 10, 1)),
+            Ok(())
+        );
+        assert_eq!(
+            project.check_loggable(NaiveDate::from_ymd(2021, 10, 31)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn check_loggable_outside_window() {
+        let project = get_project();
+
+        assert_eq!(
+            project.check_loggable(NaiveDate::from_ymd(2021, 9, 30)),
+            Err(FieldValidationError::new(
+                "date".to_owned(),
+                "is before the project starts on 2021-10-01".to_owned()
+            ))
+        );
+        assert_eq!(
+            project.check_loggable(NaiveDate::from_ymd(2021, 11, 1)),
+            Err(FieldValidationError::new(
+                "date".to_owned(),
+                "is after the project ended on 2021-10-31".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn check_loggable_archived() {
+        let project = Project {
+            archived: true,
+            ..get_project()
+        };
+
+        assert_eq!(
+            project.check_loggable(NaiveDate::from_ymd(2021, 10, 9)),
+            Err(FieldValidationError::new(
+                "project".to_owned(),
+                "is archived".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn validate_end_before_start() {
+        let project = NewProject {
+            code: "code".to_owned(),
+            name: "name".to_owned(),
+            start_date: Some(NaiveDate::from_ymd(2021, 10, 31)),
+            end_date: Some(NaiveDate::from_ymd(2021, 10, 1)),
+            archived: false,
+        };
+
+        assert_eq!(
+            project.validate(),
+            Err(vec![FieldValidationError::new(
+                "end_date".to_owned(),
+                "can not be before start_date".to_owned()
+            )])
+        );
+    }
+
+    fn get_project() -> Project {
+        Project {
+            id: Uuid::new_v4(),
+            code: "project".to_owned(),
+            name: "Project".to_owned(),
+            start_date: Some(NaiveDate::from_ymd(2021, 10, 1)),
+            end_date: Some(NaiveDate::from_ymd(2021, 10, 31)),
+            archived: false,
+        }
+    }
+}
@@ -0,0 +1,548 @@
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::net::SocketAddr;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{HeaderName, HeaderValue},
+        Method, StatusCode,
+    },
+    HttpMessage,
+};
+use futures::future::LocalBoxFuture;
+
+use crate::auth::Identity;
+use crate::configuration::{BucketConfig, RateLimitConfig};
+use crate::error::Error;
+
+/// Once there are this many buckets, the ones that have not been used for long are forgotten.
+const MAX_BUCKETS: usize = 10_000;
+
+/// Routes that share a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    Read,
+    Write,
+    Import,
+    /// Logging in and out of the web UI.
+    Auth,
+    /// Requests to `/api` that fail authentication, which are not told apart by route.
+    FailedAuth,
+}
+
+impl RouteGroup {
+    fn of(method: &Method, path: &str) -> RouteGroup {
+        if path.starts_with("/auth/") {
+            RouteGroup::Auth
+        } else if *method == Method::POST && path.ends_with("/hours/import") {
+            RouteGroup::Import
+        } else if *method == Method::GET || *method == Method::HEAD {
+            RouteGroup::Read
+        } else {
+            RouteGroup::Write
+        }
+    }
+}
+
+/// Where a client stands against the limit of a route group, after its latest request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitStatus {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the client is back at its full burst.
+    pub reset: u64,
+    /// Seconds until the client can make another request, if the latest one was over the limit.
+    pub retry_after: Option<u64>,
+}
+
+impl RateLimitStatus {
+    /// The `RateLimit-*` headers that tell the client about its limit.
+    pub fn headers(&self) -> [(HeaderName, HeaderValue); 3] {
+        [
+            (
+                HeaderName::from_static("ratelimit-limit"),
+                HeaderValue::from(self.limit),
+            ),
+            (
+                HeaderName::from_static("ratelimit-remaining"),
+                HeaderValue::from(self.remaining),
+            ),
+            (
+                HeaderName::from_static("ratelimit-reset"),
+                HeaderValue::from(self.reset),
+            ),
+        ]
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A token bucket for every client in every route group. Each request takes a token, the
+/// buckets hold up to `burst` of them and are refilled at `per_minute`.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(RouteGroup, String), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        RateLimiter {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn bucket_config(&self, group: RouteGroup) -> BucketConfig {
+        match group {
+            RouteGroup::Read => self.config.read,
+            RouteGroup::Write => self.config.write,
+            RouteGroup::Import => self.config.import,
+            RouteGroup::Auth => self.config.auth,
+            RouteGroup::FailedAuth => self.config.failed_auth,
+        }
+    }
+
+    /// Takes a token for a request of `client` to the routes of `group` at `now`. `None` when
+    /// the group is not limited.
+    pub fn check(&self, group: RouteGroup, client: &str, now: Instant) -> Option<RateLimitStatus> {
+        self.update(group, client, now, true)
+    }
+
+    /// Where `client` stands in `group` at `now`, without taking a token.
+    pub fn peek(&self, group: RouteGroup, client: &str, now: Instant) -> Option<RateLimitStatus> {
+        self.update(group, client, now, false)
+    }
+
+    fn update(
+        &self,
+        group: RouteGroup,
+        client: &str,
+        now: Instant,
+        take: bool,
+    ) -> Option<RateLimitStatus> {
+        let config = self.bucket_config(group);
+        if config.per_minute == 0 {
+            return None;
+        }
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        if buckets.len() >= MAX_BUCKETS {
+            self.evict(&mut buckets, now);
+        }
+        let bucket = buckets
+            .entry((group, client.to_owned()))
+            .or_insert_with(|| Bucket {
+                tokens: capacity(config),
+                updated: now,
+            });
+        refill(bucket, config, now);
+        let allowed = bucket.tokens >= 1.0;
+        if allowed && take {
+            bucket.tokens -= 1.0;
+        }
+        let per_second = per_second(config);
+        Some(RateLimitStatus {
+            limit: config.burst,
+            remaining: bucket.tokens as u32,
+            reset: ((capacity(config) - bucket.tokens) / per_second).ceil() as u64,
+            retry_after: if allowed {
+                None
+            } else {
+                Some(((1.0 - bucket.tokens) / per_second).ceil() as u64)
+            },
+        })
+    }
+
+    /// Forgets the buckets that have not been used for as long as it takes them to fill up, they
+    /// are no different from new ones. If there are still too many, the least recently used half
+    /// of them goes too.
+    fn evict(&self, buckets: &mut HashMap<(RouteGroup, String), Bucket>, now: Instant) {
+        buckets.retain(|(group, _), bucket| {
+            let config = self.bucket_config(*group);
+            let idle = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            idle < capacity(config) / per_second(config)
+        });
+        if buckets.len() >= MAX_BUCKETS {
+            let mut used: Vec<Instant> = buckets.values().map(|bucket| bucket.updated).collect();
+            let half = used.len() / 2;
+            let (_, &mut median, _) = used.select_nth_unstable(half);
+            buckets.retain(|_, bucket| bucket.updated > median);
+        }
+    }
+}
+
+fn capacity(config: BucketConfig) -> f64 {
+    f64::from(config.burst.max(1))
+}
+
+fn per_second(config: BucketConfig) -> f64 {
+    f64::from(config.per_minute) / 60.0
+}
+
+fn refill(bucket: &mut Bucket, config: BucketConfig, now: Instant) {
+    let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+    bucket.tokens = (bucket.tokens + elapsed * per_second(config)).min(capacity(config));
+    bucket.updated = now;
+}
+
+/// Answers requests of clients that are over the limit of the route group with
+/// `429 Too Many Requests`. Runs after the authentication, so that clients are told apart by who
+/// they are and not by whatever credentials they make up.
+pub struct RateLimit {
+    limiter: Arc<RateLimiter>,
+    failed_auth: bool,
+}
+
+impl RateLimit {
+    pub fn new(limiter: Arc<RateLimiter>) -> Self {
+        RateLimit {
+            limiter,
+            failed_auth: false,
+        }
+    }
+
+    /// Counts the requests that fail authentication against a limit of their IP address, and
+    /// refuses any more from it once it is used up. Wraps the authentication, so that it is not
+    /// even tried for them.
+    pub fn failed_auth(limiter: Arc<RateLimiter>) -> Self {
+        RateLimit {
+            limiter,
+            failed_auth: true,
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            limiter: Arc::clone(&self.limiter),
+            failed_auth: self.failed_auth,
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    limiter: Arc<RateLimiter>,
+    failed_auth: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if self.failed_auth {
+            return self.call_failed_auth(req);
+        }
+        let group = RouteGroup::of(req.method(), req.path());
+        let client = client(&req, group, self.limiter.config.trust_proxy);
+        let status = self.limiter.check(group, &client, Instant::now());
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            let status = match status {
+                Some(status) if status.retry_after.is_some() => {
+                    return Err(Error::TooManyRequests(status).into())
+                }
+                Some(status) => status,
+                None => return service.call(req).await,
+            };
+            let mut response = service.call(req).await?;
+            for (name, value) in status.headers().iter() {
+                response.headers_mut().insert(name.clone(), value.clone());
+            }
+            Ok(response)
+        })
+    }
+}
+
+impl<S, B> RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    fn call_failed_auth(
+        &self,
+        req: ServiceRequest,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse<B>, actix_web::Error>> {
+        let address = format!("ip:{}", address(&req, self.limiter.config.trust_proxy));
+        let status = self
+            .limiter
+            .peek(RouteGroup::FailedAuth, &address, Instant::now());
+        let service = Rc::clone(&self.service);
+        let limiter = Arc::clone(&self.limiter);
+        Box::pin(async move {
+            if let Some(status) = status {
+                if status.retry_after.is_some() {
+                    return Err(Error::TooManyRequests(status).into());
+                }
+            }
+            let result = service.call(req).await;
+            let status = match &result {
+                Ok(response) => response.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+            if status == StatusCode::UNAUTHORIZED {
+                limiter.check(RouteGroup::FailedAuth, &address, Instant::now());
+            }
+            result
+        })
+    }
+}
+
+/// Who a request counts against: the subject it was authenticated as, or the IP address it comes
+/// from without one. Logins are always counted against the IP address, a client could send a new
+/// cookie with every one of them.
+fn client(req: &ServiceRequest, group: RouteGroup, trust_proxy: bool) -> String {
+    if group != RouteGroup::Auth {
+        if let Some(identity) = req.extensions().get::<Identity>() {
+            return format!("subject:{}", identity.subject);
+        }
+    }
+    format!("ip:{}", address(req, trust_proxy))
+}
+
+fn address(req: &ServiceRequest, trust_proxy: bool) -> String {
+    let address = if trust_proxy {
+        req.connection_info().realip_remote_addr().map(|address| {
+            // Without a forwarding header it is the address of the peer, with its port.
+            address
+                .parse::<SocketAddr>()
+                .map(|address| address.ip().to_string())
+                .unwrap_or_else(|_| address.to_owned())
+        })
+    } else {
+        req.peer_addr().map(|address| address.ip().to_string())
+    };
+    address.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::{http::header, test, web, App, HttpResponse};
+
+    use super::*;
+
+    fn limiter(burst: u32, per_minute: u32) -> RateLimiter {
+        let bucket = BucketConfig { burst, per_minute };
+        RateLimiter::new(RateLimitConfig {
+            trust_proxy: false,
+            read: bucket,
+            write: bucket,
+            import: BucketConfig {
+                burst: 1,
+                per_minute: 0,
+            },
+            auth: bucket,
+            failed_auth: bucket,
+        })
+    }
+
+    #[test]
+    fn buckets_refill_over_time() {
+        let limiter = limiter(2, 30);
+        let start = Instant::now();
+
+        let first = limiter.check(RouteGroup::Write, "a", start).unwrap();
+        assert_eq!(
+            first,
+            RateLimitStatus {
+                limit: 2,
+                remaining: 1,
+                reset: 2,
+                retry_after: None,
+            }
+        );
+        assert_eq!(
+            limiter
+                .check(RouteGroup::Write, "a", start)
+                .unwrap()
+                .remaining,
+            0
+        );
+        let limited = limiter.check(RouteGroup::Write, "a", start).unwrap();
+        assert_eq!(limited.retry_after, Some(2));
+        assert_eq!(limited.reset, 4);
+
+        let later = start + Duration::from_secs(2);
+        assert_eq!(
+            limiter.check(RouteGroup::Write, "a", later).unwrap(),
+            RateLimitStatus {
+                limit: 2,
+                remaining: 0,
+                reset: 4,
+                retry_after: None,
+            }
+        );
+    }
+
+    #[test]
+    fn clients_and_groups_have_their_own_buckets() {
+        let limiter = limiter(1, 1);
+        let now = Instant::now();
+
+        assert!(limiter.check(RouteGroup::Write, "a", now).is_some());
+        assert!(limiter
+            .check(RouteGroup::Write, "a", now)
+            .unwrap()
+            .retry_after
+            .is_some());
+        assert_eq!(
+            limiter
+                .check(RouteGroup::Write, "b", now)
+                .unwrap()
+                .retry_after,
+            None
+        );
+        assert_eq!(
+            limiter
+                .check(RouteGroup::Read, "a", now)
+                .unwrap()
+                .retry_after,
+            None
+        );
+        assert_eq!(limiter.check(RouteGroup::Import, "a", now), None);
+    }
+
+    #[test]
+    fn buckets_are_forgotten_by_age() {
+        let limiter = limiter(1, 1);
+        let start = Instant::now();
+        for client in 0..MAX_BUCKETS - 1 {
+            limiter.check(RouteGroup::Write, &client.to_string(), start);
+        }
+        let later = start + Duration::from_secs(1);
+        limiter.check(RouteGroup::Write, "a", later);
+
+        // None have been idle for long enough, the least recently used ones go.
+        limiter.check(RouteGroup::Write, "b", later);
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 2);
+        assert!(limiter
+            .check(RouteGroup::Write, "a", later)
+            .unwrap()
+            .retry_after
+            .is_some());
+
+        for client in 0..MAX_BUCKETS - 2 {
+            limiter.check(RouteGroup::Write, &client.to_string(), later);
+        }
+        // A minute later all of them are full again.
+        limiter.check(RouteGroup::Write, "c", later + Duration::from_secs(60));
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn route_groups() {
+        assert_eq!(
+            RouteGroup::of(&Method::POST, "/api/hours/import"),
+            RouteGroup::Import
+        );
+        assert_eq!(
+            RouteGroup::of(&Method::POST, "/api/hours"),
+            RouteGroup::Write
+        );
+        assert_eq!(
+            RouteGroup::of(&Method::HEAD, "/api/hours"),
+            RouteGroup::Read
+        );
+        assert_eq!(
+            RouteGroup::of(&Method::GET, "/api/hours/import"),
+            RouteGroup::Read
+        );
+        assert_eq!(
+            RouteGroup::of(&Method::GET, "/auth/login"),
+            RouteGroup::Auth
+        );
+        assert_eq!(
+            RouteGroup::of(&Method::POST, "/auth/logout"),
+            RouteGroup::Auth
+        );
+    }
+
+    #[actix_rt::test]
+    async fn limited_requests_are_rejected() {
+        let app = test::init_service(
+            App::new().service(
+                web::scope("")
+                    .wrap(RateLimit::new(Arc::new(limiter(1, 1))))
+                    .route("/", web::post().to(HttpResponse::Ok)),
+            ),
+        )
+        .await;
+
+        let response = app
+            .call(test::TestRequest::post().uri("/").to_request())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("ratelimit-remaining").unwrap(), "0");
+
+        let error = app
+            .call(test::TestRequest::post().uri("/").to_request())
+            .await
+            .err()
+            .unwrap();
+        let response = error.as_response_error().error_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "60");
+        assert_eq!(response.headers().get("ratelimit-limit").unwrap(), "1");
+    }
+
+    #[actix_rt::test]
+    async fn failed_authentication_is_limited() {
+        let app = test::init_service(
+            App::new().service(
+                web::scope("")
+                    .wrap(RateLimit::failed_auth(Arc::new(limiter(1, 1))))
+                    .route("/", web::get().to(HttpResponse::Ok))
+                    .route(
+                        "/private",
+                        web::get().to(|| async {
+                            Err::<HttpResponse, _>(Error::Unauthorized("Who are you".to_owned()))
+                        }),
+                    ),
+            ),
+        )
+        .await;
+        let get = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+
+        let response = app.call(get("/")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.call(get("/private")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        for uri in &["/private", "/"] {
+            let error = app.call(get(uri)).await.err().unwrap();
+            assert_eq!(
+                error.as_response_error().status_code(),
+                StatusCode::TOO_MANY_REQUESTS
+            );
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use actix_web::{
+    web::{Data, Query},
+    HttpResponse,
+};
+
+use crate::auth::Identity;
+use crate::db::{Database, ReportRepo};
+use crate::error::{Error, Validated};
+use crate::hours::access::Access;
+
+pub(crate) mod model;
+
+pub use model::{Summary, SummaryQuery, SummaryRow};
+
+/// Only the hours the caller can see are summed up.
+pub async fn summary<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    query: Query<SummaryQuery>,
+) -> Result<HttpResponse, Error> {
+    let query = query.into_inner();
+    query.validate()?;
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    let rows = connection.summary(&query, &access.read).await?;
+    Ok(HttpResponse::Ok().json(Summary::from_rows(&query, rows)))
+}
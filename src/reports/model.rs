@@ -0,0 +1,249 @@
+use std::convert::TryFrom;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+use crate::error::{FieldValidationError, Validated};
+use crate::hours::duration::serialize_as_hours;
+
+#[derive(Debug, Deserialize)]
+pub struct SummaryQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub granularity: Granularity,
+    pub employee: Option<String>,
+    pub project: Option<String>,
+}
+
+impl Validated for SummaryQuery {
+    fn validate(&self) -> Result<(), Vec<FieldValidationError>> {
+        if self.from > self.to {
+            Err(vec![FieldValidationError::new(
+                "to".to_owned(),
+                "can not be before from".to_owned(),
+            )])
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Granularity {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// The field name Postgres `date_trunc` expects. Weeks are ISO weeks starting on Monday.
+    pub fn date_trunc_field(self) -> &'static str {
+        match self {
+            Granularity::Day => "day",
+            Granularity::Week => "week",
+            Granularity::Month => "month",
+        }
+    }
+
+    /// The first day of the period containing `date`.
+    pub fn period_start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => date,
+            Granularity::Week => {
+                date - Duration::days(date.weekday().num_days_from_monday().into())
+            }
+            Granularity::Month => date.with_day(1).unwrap(),
+        }
+    }
+}
+
+/// Minutes summed over a single period, employee, project and story.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryRow {
+    pub period: NaiveDate,
+    pub employee: String,
+    pub project: String,
+    pub story_id: Option<String>,
+    pub minutes: i64,
+}
+
+impl TryFrom<PgRow> for SummaryRow {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(SummaryRow {
+            period: value.try_get("period")?,
+            employee: value.try_get("employee")?,
+            project: value.try_get("project")?,
+            story_id: value.try_get("story_id")?,
+            minutes: value.try_get("minutes")?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Summary {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub granularity: Granularity,
+    #[serde(serialize_with = "serialize_as_hours")]
+    pub total: i64,
+    pub periods: Vec<PeriodSummary>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PeriodSummary {
+    pub start: NaiveDate,
+    #[serde(serialize_with = "serialize_as_hours")]
+    pub total: i64,
+    pub employees: Vec<EmployeeSummary>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EmployeeSummary {
+    pub employee: String,
+    #[serde(serialize_with = "serialize_as_hours")]
+    pub total: i64,
+    pub projects: Vec<ProjectSummary>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ProjectSummary {
+    pub project: String,
+    #[serde(serialize_with = "serialize_as_hours")]
+    pub total: i64,
+    pub stories: Vec<StorySummary>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct StorySummary {
+    pub story_id: Option<String>,
+    #[serde(serialize_with = "serialize_as_hours")]
+    pub total: i64,
+}
+
+impl Summary {
+    /// Builds the subtotals out of rows ordered by period, employee, project and story.
+    pub fn from_rows(query: &SummaryQuery, rows: Vec<SummaryRow>) -> Summary {
+        let mut periods: Vec<PeriodSummary> = vec![];
+        for row in rows {
+            let period = match periods.last_mut() {
+                Some(period) if period.start == row.period => period,
+                _ => {
+                    periods.push(PeriodSummary {
+                        start: row.period,
+                        total: 0,
+                        employees: vec![],
+                    });
+                    periods.last_mut().unwrap()
+                }
+            };
+            period.total += row.minutes;
+            let employee = match period.employees.last_mut() {
+                Some(employee) if employee.employee == row.employee => employee,
+                _ => {
+                    period.employees.push(EmployeeSummary {
+                        employee: row.employee,
+                        total: 0,
+                        projects: vec![],
+                    });
+                    period.employees.last_mut().unwrap()
+                }
+            };
+            employee.total += row.minutes;
+            let project = match employee.projects.last_mut() {
+                Some(project) if project.project == row.project => project,
+                _ => {
+                    employee.projects.push(ProjectSummary {
+                        project: row.project,
+                        total: 0,
+                        stories: vec![],
+                    });
+                    employee.projects.last_mut().unwrap()
+                }
+            };
+            project.total += row.minutes;
+            project.stories.push(StorySummary {
+                story_id: row.story_id,
+                total: row.minutes,
+            });
+        }
+        Summary {
+            from: query.from,
+            to: query.to,
+            granularity: query.granularity,
+            total: periods.iter().map(|p| p.total).sum(),
+            periods,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn period_start_of_iso_week_is_monday() {
+        let sunday = NaiveDate::from_ymd(2021, 10, 10);
+
+        assert_eq!(
+            Granularity::Week.period_start(sunday),
+            NaiveDate::from_ymd(2021, 10, 4)
+        );
+        assert_eq!(
+            Granularity::Month.period_start(sunday),
+            NaiveDate::from_ymd(2021, 10, 1)
+        );
+        assert_eq!(Granularity::Day.period_start(sunday), sunday);
+    }
+
+    #[test]
+    fn from_rows_adds_up_subtotals() {
+        let week = NaiveDate::from_ymd(2021, 10, 4);
+        let query = SummaryQuery {
+            from: week,
+            to: NaiveDate::from_ymd(2021, 10, 17),
+            granularity: Granularity::Week,
+            employee: None,
+            project: None,
+        };
+        let rows = vec![
+            row(week, "alice", "project", Some("story"), 3),
+            row(week, "alice", "project", None, 2),
+            row(week, "bob", "project", None, 4),
+            row(NaiveDate::from_ymd(2021, 10, 11), "bob", "other", None, 1),
+        ];
+
+        let summary = Summary::from_rows(&query, rows);
+
+        assert_eq!(summary.total, 10);
+        assert_eq!(summary.periods.len(), 2);
+        let first = &summary.periods[0];
+        assert_eq!(first.total, 9);
+        assert_eq!(first.employees[0].total, 5);
+        assert_eq!(first.employees[0].projects[0].stories.len(), 2);
+        assert_eq!(first.employees[1].total, 4);
+        assert_eq!(summary.periods[1].total, 1);
+    }
+
+    fn row(
+        period: NaiveDate,
+        employee: &str,
+        project: &str,
+        story_id: Option<&str>,
+        minutes: i64,
+    ) -> SummaryRow {
+        SummaryRow {
+            period,
+            employee: employee.to_owned(),
+            project: project.to_owned(),
+            story_id: story_id.map(str::to_owned),
+            minutes,
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use actix_web::{
+    cookie::{Cookie, SameSite},
+    http::header,
+    web::{Data, Query},
+    HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::api_keys::hash;
+use crate::auth::{Identity, OidcClient};
+use crate::db::{Database, SessionRepo};
+use crate::error::{Error, ErrorResponse};
+
+mod model;
+
+pub use model::{LoginAttempt, Session};
+
+/// Carries the ID of the session. It is only sent along with requests from the app itself.
+pub const SESSION_COOKIE: &str = "session";
+/// Carries the state of the login to the callback, so that a login can only be completed in the
+/// browser that started it.
+const LOGIN_COOKIE: &str = "login_state";
+/// How long the identity provider has to send the user back.
+const LOGIN_MINUTES: i64 = 10;
+/// Where users end up after logging in.
+const HOME: &str = "/openapi/";
+
+#[derive(Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+/// Sends the user to log in at the identity provider.
+pub async fn login<D: Database>(
+    db: Data<D>,
+    oidc: Data<Option<OidcClient>>,
+) -> Result<HttpResponse, Error> {
+    let oidc = match oidc.as_ref() {
+        Some(oidc) => oidc,
+        None => return Ok(not_configured()),
+    };
+    let login = LoginAttempt::generate();
+    let mut connection = db.connection().await?;
+    connection.insert_login(&login, login_since()).await?;
+    Ok(HttpResponse::Found()
+        .insert_header((header::LOCATION, oidc.authorization_url(&login)))
+        .cookie(
+            Cookie::build(LOGIN_COOKIE, login.state)
+                .path("/auth")
+                .secure(true)
+                .http_only(true)
+                .same_site(SameSite::Lax)
+                .finish(),
+        )
+        .finish())
+}
+
+/// Where the identity provider sends the user back to. Starts a session for who they logged in
+/// as.
+pub async fn callback<D: Database>(
+    req: HttpRequest,
+    db: Data<D>,
+    oidc: Data<Option<OidcClient>>,
+    query: Query<CallbackQuery>,
+) -> Result<HttpResponse, Error> {
+    let oidc = match oidc.as_ref() {
+        Some(oidc) => oidc,
+        None => return Ok(not_configured()),
+    };
+    let query = query.into_inner();
+    if let Some(error) = query.error {
+        return Ok(login_failed(&format!(
+            "The identity provider refused the login: {}",
+            error
+        )));
+    }
+    let (code, state) = match (query.code, query.state) {
+        (Some(code), Some(state)) => (code, state),
+        _ => {
+            return Ok(login_failed(
+                "The code or the state of the login is missing",
+            ))
+        }
+    };
+    if req
+        .cookie(LOGIN_COOKIE)
+        .is_none_or(|cookie| cookie.value() != state)
+    {
+        return Ok(login_failed("The login was started in another browser"));
+    }
+    let mut connection = db.connection().await?;
+    let login = match connection.take_login(&state, login_since()).await? {
+        Some(login) => login,
+        None => return Ok(login_failed("The login has expired, please try again")),
+    };
+    let claims = oidc.exchange(&code, &login).await.map_err(|e| {
+        log::warn!("Rejected a login: {}", e);
+        Error::Unauthorized("The login could not be verified".to_owned())
+    })?;
+    let identity = Identity {
+        subject: claims.sub,
+        role: claims.role,
+    };
+    let (id, session) = Session::generate(identity, oidc.session_ttl);
+    connection.insert_session(&session).await?;
+    Ok(HttpResponse::Found()
+        .insert_header((header::LOCATION, HOME))
+        .cookie(
+            Cookie::build(SESSION_COOKIE, id)
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .same_site(SameSite::Strict)
+                .finish(),
+        )
+        .cookie(removal(LOGIN_COOKIE, "/auth"))
+        .finish())
+}
+
+/// Ends the session of the request, if it has one.
+pub async fn logout<D: Database>(req: HttpRequest, db: Data<D>) -> Result<HttpResponse, Error> {
+    if let Some(cookie) = req.cookie(SESSION_COOKIE) {
+        let mut connection = db.connection().await?;
+        connection.delete_session(&hash(cookie.value())).await?;
+    }
+    Ok(HttpResponse::NoContent()
+        .cookie(removal(SESSION_COOKIE, "/"))
+        .finish())
+}
+
+/// Who the user of the web UI is logged in as.
+pub async fn current_session<D: Database>(
+    req: HttpRequest,
+    db: Data<D>,
+    oidc: Data<Option<OidcClient>>,
+) -> Result<HttpResponse, Error> {
+    if oidc.is_none() {
+        return Ok(not_configured());
+    }
+    match find_session(&req, db.get_ref()).await? {
+        Some(session) => Ok(HttpResponse::Ok().json(session)),
+        None => Err(Error::Unauthorized("Not logged in".to_owned())),
+    }
+}
+
+/// The unexpired session that the cookie of the request is for.
+pub(crate) async fn find_session<D: Database>(
+    req: &HttpRequest,
+    db: &D,
+) -> Result<Option<Session>, Error> {
+    let cookie = match req.cookie(SESSION_COOKIE) {
+        Some(cookie) => cookie,
+        None => return Ok(None),
+    };
+    let mut connection = db.connection().await?;
+    connection
+        .session_by_hash(&hash(cookie.value()), Utc::now())
+        .await
+}
+
+/// Logins started before this have expired.
+fn login_since() -> DateTime<Utc> {
+    Utc::now() - chrono::Duration::minutes(LOGIN_MINUTES)
+}
+
+fn removal(name: &str, path: &str) -> Cookie<'static> {
+    let mut cookie = Cookie::build(name.to_owned(), "")
+        .path(path.to_owned())
+        .secure(true)
+        .http_only(true)
+        .finish();
+    cookie.make_removal();
+    cookie
+}
+
+fn login_failed(message: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(ErrorResponse::new(message.to_owned()))
+}
+
+fn not_configured() -> HttpResponse {
+    HttpResponse::NotFound().json(ErrorResponse::new(
+        "Logging in is not configured".to_owned(),
+    ))
+}
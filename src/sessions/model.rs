@@ -0,0 +1,104 @@
+use std::convert::TryFrom;
+
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use rand::RngCore;
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+use crate::api_keys::hash;
+use crate::auth::{Identity, Role};
+
+/// A login that has been sent to the identity provider and has not come back yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginAttempt {
+    /// Ties the callback to the login, and to the browser that started it through a cookie.
+    pub state: String,
+    /// The PKCE secret, only its challenge is sent through the browser.
+    pub code_verifier: String,
+    pub nonce: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LoginAttempt {
+    pub fn generate() -> LoginAttempt {
+        LoginAttempt {
+            state: random_token(),
+            code_verifier: random_token(),
+            nonce: random_token(),
+            created_at: Utc::now().trunc_subsecs(6),
+        }
+    }
+}
+
+/// A user of the web UI that has logged in. The ID of the session is only in the cookie of the
+/// browser, the session is found by its hash.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Session {
+    #[serde(skip)]
+    pub hash: String,
+    pub subject: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Session {
+    /// Starts a session for `identity` that lasts for `ttl`, returned with its ID.
+    pub fn generate(identity: Identity, ttl: Duration) -> (String, Session) {
+        let id = random_token();
+        let created_at = Utc::now().trunc_subsecs(6);
+        let session = Session {
+            hash: hash(&id),
+            subject: identity.subject,
+            role: identity.role,
+            created_at,
+            expires_at: created_at + ttl,
+        };
+        (id, session)
+    }
+
+    pub fn identity(&self) -> Identity {
+        Identity {
+            subject: self.subject.clone(),
+            role: self.role,
+        }
+    }
+}
+
+/// 256 random bits, URL-safe.
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+impl TryFrom<PgRow> for LoginAttempt {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(LoginAttempt {
+            state: value.try_get("state")?,
+            code_verifier: value.try_get("code_verifier")?,
+            nonce: value.try_get("nonce")?,
+            created_at: value.try_get("created_at")?,
+        })
+    }
+}
+
+impl TryFrom<PgRow> for Session {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(Session {
+            hash: value.try_get("hash")?,
+            subject: value.try_get("subject")?,
+            role: value
+                .try_get::<String, _>("role")?
+                .parse()
+                .map_err(|e: String| sqlx::Error::Decode(e.into()))?,
+            created_at: value.try_get("created_at")?,
+            expires_at: value.try_get("expires_at")?,
+        })
+    }
+}
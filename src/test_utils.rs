@@ -1,7 +1,13 @@
 use sqlx::{Connection, Executor, PgConnection, PgPool, Pool, Postgres};
 use uuid::Uuid;
 
+use crate::api_keys::{ApiKey, NewApiKey};
+use crate::auth::Role;
 use crate::configuration;
+use crate::db::{ApiKeyRepo, Database};
+
+/// An admin key that [`add_test_api_key`] makes valid.
+pub const TEST_API_KEY: &str = "cbk_test";
 
 pub async fn get_db_pool() -> Pool<Postgres> {
     let mut config = configuration::get_configuration().unwrap();
@@ -21,11 +27,51 @@ pub async fn get_db_pool() -> Pool<Postgres> {
     pool
 }
 
+/// Adds [`TEST_API_KEY`] to the database, for tests that go through the authenticated API.
+pub async fn add_test_api_key<D: Database>(db: &D) {
+    let created = ApiKey::from_key(
+        TEST_API_KEY.to_owned(),
+        NewApiKey {
+            name: "test".to_owned(),
+            role: Role::Admin,
+        },
+    );
+    let mut connection = db.connection().await.unwrap();
+    connection.insert_api_key(&created.api_key).await.unwrap();
+}
+
 #[cfg(test)]
 pub mod internal {
     use sqlx::{pool::PoolConnection, Postgres};
 
+    use crate::db::{EmployeeRepo, ProjectRepo};
+    use crate::employees::NewEmployee;
+    use crate::projects::NewProject;
+
     pub(crate) async fn get_db_connection() -> PoolConnection<Postgres> {
         super::get_db_pool().await.acquire().await.unwrap()
     }
+
+    /// Adds the employees `employee` and `other` and the project `project` that hours in tests
+    /// are logged for.
+    pub(crate) async fn seed<C: EmployeeRepo + ProjectRepo>(db: &mut C) {
+        for username in &["employee", "other"] {
+            db.insert_employee(NewEmployee {
+                username: username.to_string(),
+                name: username.to_string(),
+                manager: None,
+            })
+            .await
+            .unwrap();
+        }
+        db.insert_project(NewProject {
+            code: "project".to_owned(),
+            name: "Project".to_owned(),
+            start_date: None,
+            end_date: None,
+            archived: false,
+        })
+        .await
+        .unwrap();
+    }
 }
@@ -0,0 +1,132 @@
+use actix_web::{
+    web::{self, Data, Path, Query},
+    HttpResponse,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::auth::Identity;
+use crate::configuration::HoursConfig;
+use crate::db::{Database, TimerRepo, Transaction};
+use crate::error::Error;
+use crate::hours::access::Access;
+use crate::hours::validation::{check_editable, check_references, validate};
+
+mod model;
+
+pub use model::{NewTimer, StopQuery, Timer, TimerQuery};
+
+pub async fn list_timers<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    query: Query<TimerQuery>,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    Ok(HttpResponse::Ok().json(connection.list_timers(&query, &access.read).await?))
+}
+
+pub async fn get_timer<D: Database>(
+    id: Path<Uuid>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    let id = id.into_inner();
+    Ok(match connection.timer_by_id(id).await? {
+        Some(timer) => {
+            access.read.check(&timer.employee)?;
+            HttpResponse::Ok().json(timer)
+        }
+        None => HttpResponse::NotFound().json(id),
+    })
+}
+
+/// The timer is started for the caller unless it names an employee, which only admins can do for
+/// anyone else.
+pub async fn start_timer<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    json: web::Json<NewTimer>,
+) -> Result<HttpResponse, Error> {
+    let mut new_timer = json.into_inner();
+    new_timer.default_employee(&identity.subject);
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    access.write.check(&new_timer.employee)?;
+    let today = Utc::now().naive_utc().date();
+    let errors = check_references(
+        &mut connection,
+        &new_timer.employee,
+        &new_timer.project,
+        today,
+    )
+    .await?;
+    if !errors.is_empty() {
+        return Err(Error::Validation(errors));
+    }
+    let timer = connection.insert_timer(new_timer).await?;
+    Ok(HttpResponse::Created().json(timer))
+}
+
+/// Stops the timer and logs the time it ran as an hours entry, or the time given in the query.
+///
+/// The entry is checked and validated like one logged directly, a timer that fails validation
+/// keeps running. Stopping the timer of someone else records the caller in `logged_by`. The timer
+/// is removed along with inserting the entry, so that two concurrent stops can not both log it
+/// and a failed insert does not lose the time.
+pub async fn stop_timer<D: Database>(
+    id: Path<Uuid>,
+    db: Data<D>,
+    config: Data<HoursConfig>,
+    identity: Identity,
+    query: Query<StopQuery>,
+) -> Result<HttpResponse, Error> {
+    let minutes = query.minutes()?;
+    let mut connection = db.begin().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    let id = id.into_inner();
+    let timer = match connection.timer_by_id(id).await? {
+        Some(timer) => timer,
+        None => return Ok(HttpResponse::NotFound().json(id)),
+    };
+    let mut new_hours = timer.to_hours(Utc::now());
+    if let Some(minutes) = minutes {
+        new_hours.minutes = minutes;
+    }
+    new_hours.default_employee(&identity.subject);
+    access.write.check(&new_hours.employee)?;
+    new_hours.round(config.rounding);
+    connection.lock_employees(&[&new_hours.employee]).await?;
+    check_editable(&mut connection, &new_hours.employee, new_hours.date).await?;
+    validate(&mut connection, &new_hours, None, &config).await?;
+    let stopped = connection
+        .stop_timer(id, new_hours, &identity.subject)
+        .await?;
+    connection.commit().await?;
+    Ok(match stopped {
+        Some(hours_entry) => HttpResponse::Created().json(hours_entry),
+        None => HttpResponse::NotFound().json(id),
+    })
+}
+
+/// Discards a running timer without logging anything.
+pub async fn delete_timer<D: Database>(
+    id: Path<Uuid>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    let id = id.into_inner();
+    let timer = match connection.timer_by_id(id).await? {
+        Some(timer) => timer,
+        None => return Ok(HttpResponse::NotFound().json(id)),
+    };
+    access.write.check(&timer.employee)?;
+    Ok(match connection.delete_timer(id).await? {
+        Some(_) => HttpResponse::NoContent().finish(),
+        None => HttpResponse::NotFound().json(id),
+    })
+}
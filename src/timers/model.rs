@@ -0,0 +1,149 @@
+use std::convert::TryFrom;
+
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::error::{Error, FieldValidationError};
+use crate::hours::{duration, NewHours};
+
+#[derive(Debug, Deserialize)]
+pub struct NewTimer {
+    /// Left empty to start the timer of the caller.
+    #[serde(default)]
+    pub employee: String,
+    pub project: String,
+    pub story_id: Option<String>,
+    /// Can be left empty while the timer runs and filled in on the hours entry later.
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Timer {
+    pub id: Uuid,
+    pub employee: String,
+    pub project: String,
+    pub story_id: Option<String>,
+    pub description: String,
+    pub started_at: DateTime<Utc>,
+}
+
+impl NewTimer {
+    /// Timers that do not name an employee are started for `subject`, the caller.
+    pub fn default_employee(&mut self, subject: &str) {
+        if self.employee.is_empty() {
+            self.employee = subject.to_owned();
+        }
+    }
+}
+
+impl Timer {
+    /// Starts the timer now. The start time is truncated to microseconds, the precision Postgres
+    /// stores it with.
+    pub fn new(new_timer: NewTimer) -> Timer {
+        Timer {
+            id: Uuid::new_v4(),
+            employee: new_timer.employee,
+            project: new_timer.project,
+            story_id: new_timer.story_id,
+            description: new_timer.description,
+            started_at: Utc::now().trunc_subsecs(6),
+        }
+    }
+
+    /// The hours entry for the time between the start of the timer and `stopped_at`, rounded to
+    /// the nearest minute. The entry is dated on the (UTC) day the timer was started.
+    pub fn to_hours(&self, stopped_at: DateTime<Utc>) -> NewHours {
+        let seconds = (stopped_at - self.started_at).num_seconds().max(0);
+        NewHours {
+            employee: self.employee.clone(),
+            date: self.started_at.naive_utc().date(),
+            project: self.project.clone(),
+            story_id: self.story_id.clone(),
+            description: self.description.clone(),
+            minutes: i32::try_from((seconds + 30) / 60).unwrap_or(i32::MAX),
+            start_time: None,
+            end_time: None,
+        }
+    }
+}
+
+impl TryFrom<PgRow> for Timer {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(Timer {
+            id: value.try_get("id")?,
+            employee: value.try_get("employee")?,
+            project: value.try_get("project")?,
+            story_id: value.try_get("story_id")?,
+            description: value.try_get("description")?,
+            started_at: value.try_get("started_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TimerQuery {
+    pub employee: Option<String>,
+}
+
+/// The time to log when stopping a timer, as `hours` or as an ISO 8601 `duration`, instead of the
+/// time it ran. A timer that was left running for longer than a day can only be stopped with it.
+#[derive(Debug, Default, Deserialize)]
+pub struct StopQuery {
+    pub hours: Option<f64>,
+    pub duration: Option<String>,
+}
+
+impl StopQuery {
+    pub fn minutes(&self) -> Result<Option<i32>, Error> {
+        duration::minutes_from(self.hours, self.duration.as_deref()).map_err(|error| {
+            Error::Validation(vec![FieldValidationError::new("hours".to_owned(), error)])
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, TimeZone};
+
+    use super::*;
+
+    #[test]
+    fn to_hours_rounds_to_minutes() {
+        let timer = Timer {
+            id: Uuid::new_v4(),
+            employee: "employee".to_owned(),
+            project: "project".to_owned(),
+            story_id: None,
+            description: "standup".to_owned(),
+            started_at: Utc.ymd(2021, 10, 9).and_hms(23, 50, 0),
+        };
+
+        let hours = timer.to_hours(timer.started_at + Duration::seconds(29 * 60 + 30));
+
+        assert_eq!(hours.minutes, 30);
+        assert_eq!(hours.date, NaiveDate::from_ymd(2021, 10, 9));
+        assert_eq!(hours.description, "standup");
+    }
+
+    #[test]
+    fn stop_query_minutes() {
+        let query = StopQuery {
+            hours: None,
+            duration: Some("PT7H30M".to_owned()),
+        };
+        assert_eq!(query.minutes().unwrap(), Some(450));
+        assert_eq!(StopQuery::default().minutes().unwrap(), None);
+
+        let both = StopQuery {
+            hours: Some(7.5),
+            duration: Some("PT7H30M".to_owned()),
+        };
+        assert!(matches!(both.minutes(), Err(Error::Validation(_))));
+    }
+}
@@ -0,0 +1,146 @@
+use actix_web::{
+    web::{self, Data, Path, Query},
+    HttpResponse,
+};
+use chrono::NaiveDate;
+
+use crate::auth::Identity;
+use crate::db::{Database, EmployeeRepo, TimesheetRepo, Transaction};
+use crate::error::{Error, Validated};
+use crate::hours::access::Access;
+use crate::reports::model::Granularity;
+
+pub(crate) mod model;
+
+use model::Transition;
+pub use model::{Rejection, Timesheet, TimesheetPath, TimesheetQuery};
+
+pub async fn list_timesheets<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    query: Query<TimesheetQuery>,
+) -> Result<HttpResponse, Error> {
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    Ok(HttpResponse::Ok().json(connection.list_timesheets(&query, &access.read).await?))
+}
+
+pub async fn get_timesheet<D: Database>(
+    path: Path<TimesheetPath>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    let path = path.into_inner();
+    path.validate()?;
+    let mut connection = db.connection().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    access.read.check(&path.employee)?;
+    Ok(match current(&mut connection, &path).await? {
+        Some(timesheet) => HttpResponse::Ok().json(timesheet),
+        None => HttpResponse::NotFound().json(path.employee),
+    })
+}
+
+/// Employees submit their own timesheets, admins anyone's.
+pub async fn submit_timesheet<D: Database>(
+    path: Path<TimesheetPath>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    transition(db, identity, path.into_inner(), Transition::Submit).await
+}
+
+pub async fn approve_timesheet<D: Database>(
+    path: Path<TimesheetPath>,
+    db: Data<D>,
+    identity: Identity,
+) -> Result<HttpResponse, Error> {
+    transition(db, identity, path.into_inner(), Transition::Approve).await
+}
+
+pub async fn reject_timesheet<D: Database>(
+    path: Path<TimesheetPath>,
+    db: Data<D>,
+    identity: Identity,
+    json: web::Json<Rejection>,
+) -> Result<HttpResponse, Error> {
+    let rejection = json.into_inner();
+    rejection.validate()?;
+    let reason = rejection.reason.trim().to_owned();
+    transition(db, identity, path.into_inner(), Transition::Reject(reason)).await
+}
+
+/// Changes the state of the timesheet while holding the lock on the hours of the employee, so
+/// that hours can not be logged into it while it is submitted or approved.
+async fn transition<D: Database>(
+    db: Data<D>,
+    identity: Identity,
+    path: TimesheetPath,
+    transition: Transition,
+) -> Result<HttpResponse, Error> {
+    path.validate()?;
+    let mut connection = db.begin().await?;
+    let access = Access::of(&mut connection, &identity).await?;
+    match transition {
+        Transition::Submit => access.write.check(&path.employee)?,
+        Transition::Approve | Transition::Reject(_) => {
+            check_reviewer(&identity, &access, &path.employee)?
+        }
+    }
+    connection.lock_employees(&[&path.employee]).await?;
+    let (from, timesheet) = match current(&mut connection, &path).await? {
+        Some(timesheet) => (timesheet.state, timesheet.transition(transition)?),
+        None => return Ok(HttpResponse::NotFound().json(path.employee)),
+    };
+    connection.save_timesheet(&timesheet, from).await?;
+    connection.commit().await?;
+    Ok(HttpResponse::Ok().json(timesheet))
+}
+
+/// Timesheets are reviewed by the manager of the employee or by an admin, never by the employee
+/// themselves. The team of a manager is all they can see besides their own hours.
+fn check_reviewer(identity: &Identity, access: &Access, employee: &str) -> Result<(), Error> {
+    if identity.subject == employee {
+        return Err(Error::Forbidden(
+            "You can not review your own timesheet".to_owned(),
+        ));
+    }
+    access.read.check(employee)
+}
+
+/// The stored timesheet or, if there is none, a draft. `None` if the employee does not exist.
+async fn current<C: EmployeeRepo + TimesheetRepo>(
+    connection: &mut C,
+    path: &TimesheetPath,
+) -> Result<Option<Timesheet>, Error> {
+    if connection
+        .employee_by_username(&path.employee)
+        .await?
+        .is_none()
+    {
+        return Ok(None);
+    }
+    Ok(Some(
+        connection
+            .timesheet(&path.employee, path.week)
+            .await?
+            .unwrap_or_else(|| Timesheet::draft(path.employee.clone(), path.week)),
+    ))
+}
+
+/// Refuses changes to the hours of `employee` on `date` once the timesheet of that week has been
+/// submitted for review.
+pub async fn check_open<C: TimesheetRepo>(
+    connection: &mut C,
+    employee: &str,
+    date: NaiveDate,
+) -> Result<(), Error> {
+    let week = Granularity::Week.period_start(date);
+    match connection.timesheet(employee, week).await? {
+        Some(timesheet) if timesheet.state.is_locked() => Err(Error::Conflict(format!(
+            "The timesheet of {} for the week starting {} is {}",
+            employee, week, timesheet.state
+        ))),
+        _ => Ok(()),
+    }
+}
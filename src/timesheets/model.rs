@@ -0,0 +1,223 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+use crate::error::{Error, FieldValidationError, Validated};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimesheetState {
+    Draft,
+    Submitted,
+    Approved,
+    Rejected,
+}
+
+impl TimesheetState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimesheetState::Draft => "draft",
+            TimesheetState::Submitted => "submitted",
+            TimesheetState::Approved => "approved",
+            TimesheetState::Rejected => "rejected",
+        }
+    }
+
+    /// Hours in submitted and approved timesheets can not be changed.
+    pub fn is_locked(self) -> bool {
+        matches!(self, TimesheetState::Submitted | TimesheetState::Approved)
+    }
+}
+
+impl fmt::Display for TimesheetState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TimesheetState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(TimesheetState::Draft),
+            "submitted" => Ok(TimesheetState::Submitted),
+            "approved" => Ok(TimesheetState::Approved),
+            "rejected" => Ok(TimesheetState::Rejected),
+            other => Err(format!("unknown timesheet state {}", other)),
+        }
+    }
+}
+
+/// The hours of one employee for one week, which starts on Monday.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Timesheet {
+    pub employee: String,
+    pub week: NaiveDate,
+    pub state: TimesheetState,
+    /// Why the timesheet was rejected, only set while it is.
+    pub reason: Option<String>,
+}
+
+pub enum Transition {
+    Submit,
+    Approve,
+    Reject(String),
+}
+
+impl Timesheet {
+    pub fn draft(employee: String, week: NaiveDate) -> Timesheet {
+        Timesheet {
+            employee,
+            week,
+            state: TimesheetState::Draft,
+            reason: None,
+        }
+    }
+
+    /// Drafts and rejected timesheets can be submitted, submitted ones approved or rejected.
+    pub fn transition(self, transition: Transition) -> Result<Timesheet, Error> {
+        let (state, reason) = match (self.state, transition) {
+            (TimesheetState::Draft, Transition::Submit)
+            | (TimesheetState::Rejected, Transition::Submit) => (TimesheetState::Submitted, None),
+            (TimesheetState::Submitted, Transition::Approve) => (TimesheetState::Approved, None),
+            (TimesheetState::Submitted, Transition::Reject(reason)) => {
+                (TimesheetState::Rejected, Some(reason))
+            }
+            (state, transition) => {
+                let (action, allowed) = match transition {
+                    Transition::Submit => ("submitted", "draft or rejected"),
+                    Transition::Approve => ("approved", "submitted"),
+                    Transition::Reject(_) => ("rejected", "submitted"),
+                };
+                return Err(Error::Conflict(format!(
+                    "The timesheet is {}, only {} timesheets can be {}",
+                    state, allowed, action
+                )));
+            }
+        };
+        Ok(Timesheet {
+            state,
+            reason,
+            ..self
+        })
+    }
+}
+
+impl TryFrom<PgRow> for Timesheet {
+    type Error = sqlx::Error;
+
+    fn try_from(value: PgRow) -> Result<Self, Self::Error> {
+        Ok(Timesheet {
+            employee: value.try_get("employee")?,
+            week: value.try_get("week")?,
+            state: value
+                .try_get::<String, _>("state")?
+                .parse()
+                .map_err(|e: String| sqlx::Error::Decode(e.into()))?,
+            reason: value.try_get("reason")?,
+        })
+    }
+}
+
+/// Identifies a timesheet in the path.
+#[derive(Debug, Deserialize)]
+pub struct TimesheetPath {
+    pub employee: String,
+    pub week: NaiveDate,
+}
+
+impl Validated for TimesheetPath {
+    fn validate(&self) -> Result<(), Vec<FieldValidationError>> {
+        if self.week.weekday() == Weekday::Mon {
+            Ok(())
+        } else {
+            Err(vec![FieldValidationError::new(
+                "week".to_owned(),
+                "must be a Monday".to_owned(),
+            )])
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Rejection {
+    pub reason: String,
+}
+
+impl Validated for Rejection {
+    fn validate(&self) -> Result<(), Vec<FieldValidationError>> {
+        if self.reason.trim().is_empty() {
+            Err(vec![FieldValidationError::new(
+                "reason".to_owned(),
+                "can not be empty".to_owned(),
+            )])
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TimesheetQuery {
+    pub employee: Option<String>,
+    pub state: Option<TimesheetState>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transition_through_review() {
+        let timesheet = get_timesheet();
+
+        let submitted = timesheet.transition(Transition::Submit).unwrap();
+        assert_eq!(submitted.state, TimesheetState::Submitted);
+        let rejected = submitted
+            .transition(Transition::Reject("missing friday".to_owned()))
+            .unwrap();
+        assert_eq!(rejected.state, TimesheetState::Rejected);
+        assert_eq!(rejected.reason, Some("missing friday".to_owned()));
+        let resubmitted = rejected.transition(Transition::Submit).unwrap();
+        assert_eq!(resubmitted.reason, None);
+        let approved = resubmitted.transition(Transition::Approve).unwrap();
+        assert_eq!(approved.state, TimesheetState::Approved);
+    }
+
+    #[test]
+    fn transition_not_allowed() {
+        match get_timesheet().transition(Transition::Approve) {
+            Err(Error::Conflict(message)) => assert_eq!(
+                message,
+                "The timesheet is draft, only submitted timesheets can be approved"
+            ),
+            _ => panic!("Expected a conflict."),
+        }
+    }
+
+    #[test]
+    fn path_week_must_start_on_monday() {
+        let path = TimesheetPath {
+            employee: "employee".to_owned(),
+            week: NaiveDate::from_ymd(2021, 10, 9),
+        };
+
+        assert_eq!(
+            path.validate(),
+            Err(vec![FieldValidationError::new(
+                "week".to_owned(),
+                "must be a Monday".to_owned()
+            )])
+        );
+    }
+
+    fn get_timesheet() -> Timesheet {
+        Timesheet::draft("employee".to_owned(), NaiveDate::from_ymd(2021, 10, 4))
+    }
+}
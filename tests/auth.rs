@@ -0,0 +1,143 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use cb_rust_demo::configuration::Settings;
+use cb_rust_demo::error::ErrorResponse;
+use cb_rust_demo::test_utils;
+
+#[derive(Deserialize)]
+struct CreatedApiKey {
+    id: Uuid,
+    name: String,
+    prefix: String,
+    role: String,
+    key: String,
+}
+
+#[actix_rt::test]
+async fn auth_health_check_is_public() {
+    let address = spawn_app().await;
+
+    let response = Client::new()
+        .get(format!("{}/api/health_check", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[actix_rt::test]
+async fn auth_requests_without_valid_key_are_rejected() {
+    let address = spawn_app().await;
+
+    let client = Client::new();
+    for authorization in &[None, Some("ApiKey cbk_wrong"), Some("cbk_test")] {
+        let mut request = client.get(format!("{}/api/hours", address));
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, *authorization);
+        }
+        let response = request.send().await.expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), 401);
+        assert_eq!(response.headers()["WWW-Authenticate"], "ApiKey, Bearer");
+        let result: ErrorResponse = response.json().await.unwrap();
+        assert_eq!(
+            result,
+            ErrorResponse::new("A valid API key or bearer token is required".into())
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn auth_create_use_and_revoke_key() {
+    let address = spawn_app().await;
+
+    let client = Client::new();
+    let response = client
+        .post(format!("{}/api/admin/api-keys", address))
+        .header(AUTHORIZATION, admin_authorization())
+        .json(&json!({"name": "mobile"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+    let created: CreatedApiKey = response.json().await.unwrap();
+    assert_eq!(created.name, "mobile");
+    assert_eq!(created.role, "employee");
+    assert!(created.key.starts_with(&created.prefix));
+    let authorization = format!("ApiKey {}", created.key);
+
+    let response = client
+        .get(format!("{}/api/hours", address))
+        .header(AUTHORIZATION, &authorization)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = client
+        .get(format!("{}/api/admin/api-keys", address))
+        .header(AUTHORIZATION, &authorization)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = client
+        .get(format!("{}/api/admin/api-keys", address))
+        .header(AUTHORIZATION, admin_authorization())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let keys: Vec<serde_json::Value> = response.json().await.unwrap();
+    assert_eq!(keys.len(), 2);
+    assert!(keys.iter().all(|key| key.get("key").is_none()));
+
+    let response = client
+        .delete(format!("{}/api/admin/api-keys/{}", address, created.id))
+        .header(AUTHORIZATION, admin_authorization())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = client
+        .get(format!("{}/api/hours", address))
+        .header(AUTHORIZATION, &authorization)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .delete(format!("{}/api/admin/api-keys/{}", address, created.id))
+        .header(AUTHORIZATION, admin_authorization())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+fn admin_authorization() -> String {
+    format!("ApiKey {}", test_utils::TEST_API_KEY)
+}
+
+async fn spawn_app() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let server = cb_rust_demo::run_server(pool, listener, Settings::default())
+        .expect("Server failed to start");
+    tokio::spawn(server);
+    format!("http://127.0.0.1:{}", port)
+}
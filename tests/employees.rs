@@ -0,0 +1,184 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use cb_rust_demo::configuration::Settings;
+use cb_rust_demo::error::{ErrorResponse, FieldValidationError};
+use cb_rust_demo::test_utils;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Employee {
+    id: Uuid,
+    username: String,
+    name: String,
+}
+
+#[actix_rt::test]
+async fn employees_create_update_and_list() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/employees", address))
+        .json(&json!({"username": " Kaarel ", "name": "Kaarel Ratas"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+    let created: Employee = response.json().await.unwrap();
+    assert_eq!(created.username, "kaarel");
+
+    let response = client
+        .put(format!("{}/api/employees/{}", address, created.id))
+        .json(&json!({"username": "kratas", "name": "Kaarel Ratas"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = client
+        .get(format!("{}/api/employees", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    let result: Vec<Employee> = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        vec![Employee {
+            id: created.id,
+            username: "kratas".to_owned(),
+            name: "Kaarel Ratas".to_owned(),
+        }]
+    );
+}
+
+#[actix_rt::test]
+async fn employees_duplicate_username() {
+    let address = spawn_app().await;
+
+    let client = client();
+    for expected_status in &[201, 409] {
+        let response = client
+            .post(format!("{}/api/employees", address))
+            .json(&json!({"username": "kaarel", "name": "Kaarel Ratas"}))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), *expected_status);
+    }
+}
+
+#[actix_rt::test]
+async fn employees_hours_require_existing_employee() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("nobody"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "employee".into(),
+                "does not exist".into()
+            )]
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn employees_delete_with_hours() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/employees", address))
+        .json(&json!({"username": "kaarel", "name": "Kaarel Ratas"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let employee: Employee = response.json().await.unwrap();
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("kaarel"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .delete(format!("{}/api/employees/{}", address, employee.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[actix_rt::test]
+async fn employees_delete_nonexistent() {
+    let address = spawn_app().await;
+
+    let response = client()
+        .delete(format!("{}/api/employees/{}", address, Uuid::new_v4()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+fn hours(employee: &str) -> serde_json::Value {
+    json!({
+        "employee": employee,
+        "date": "2021-10-09",
+        "project": "project",
+        "story_id": null,
+        "description": "description",
+        "hours": 1
+    })
+}
+
+/// A client that authenticates with the test API key.
+fn client() -> Client {
+    let authorization = format!("ApiKey {}", test_utils::TEST_API_KEY);
+    let headers = std::iter::once((AUTHORIZATION, authorization.parse().unwrap())).collect();
+    Client::builder().default_headers(headers).build().unwrap()
+}
+
+async fn spawn_app() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let server = cb_rust_demo::run_server(pool, listener, Settings::default())
+        .expect("Server failed to start");
+    tokio::spawn(server);
+    let address = format!("http://127.0.0.1:{}", port);
+    let response = client()
+        .post(format!("{}/api/projects", address))
+        .json(&json!({"code": "project", "name": "Project"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    address
+}
@@ -3,6 +3,7 @@ use std::net::TcpListener;
 
 use reqwest::Client;
 
+use cb_rust_demo::configuration::Settings;
 use cb_rust_demo::test_utils;
 
 #[actix_rt::test]
@@ -24,8 +25,12 @@ async fn health_check_works() {
 async fn spawn_app() -> String {
     let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
     let port = listener.local_addr().unwrap().port();
-    let server = cb_rust_demo::run_server(test_utils::get_db_pool().await, listener)
-        .expect("Server failed to start");
+    let server = cb_rust_demo::run_server(
+        test_utils::get_db_pool().await,
+        listener,
+        Settings::default(),
+    )
+    .expect("Server failed to start");
     tokio::spawn(server);
     format!("http://127.0.0.1:{}", port)
 }
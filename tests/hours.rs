@@ -2,10 +2,13 @@
 use std::net::TcpListener;
 
 use cb_rust_demo::error::{ErrorResponse, FieldValidationError};
+use reqwest::header::AUTHORIZATION;
 use reqwest::Client;
 use serde::Deserialize;
+use serde_json::json;
 use uuid::Uuid;
 
+use cb_rust_demo::configuration::Settings;
 use cb_rust_demo::test_utils;
 
 const HOURS: &str = r#"{
@@ -17,6 +20,18 @@ const HOURS: &str = r#"{
     "hours": 1
 }"#;
 
+#[derive(Deserialize, PartialEq, Debug)]
+struct RowErrors {
+    row: usize,
+    errors: Vec<FieldValidationError>,
+}
+
+#[derive(Deserialize)]
+struct ImportReport {
+    inserted: Vec<Hours>,
+    errors: Vec<RowErrors>,
+}
+
 #[derive(Deserialize)]
 #[allow(dead_code)]
 struct Hours {
@@ -32,7 +47,7 @@ struct Hours {
 async fn hours_from_empty_db() {
     let address = spawn_app().await;
 
-    let client = Client::new();
+    let client = client();
 
     let response = client
         .get(format!("{}/api/hours", address))
@@ -48,7 +63,7 @@ async fn hours_from_empty_db() {
 async fn hours_insert_and_retrieve() {
     let address = spawn_app().await;
 
-    let client = Client::new();
+    let client = client();
 
     let response = client
         .post(format!("{}/api/hours", address))
@@ -77,7 +92,7 @@ async fn hours_insert_and_retrieve() {
 async fn hours_insert_too_many_hours() {
     let address = spawn_app().await;
 
-    let client = Client::new();
+    let client = client();
 
     let response = client
         .post(format!("{}/api/hours", address))
@@ -111,11 +126,238 @@ async fn hours_insert_too_many_hours() {
     );
 }
 
+#[actix_rt::test]
+async fn hours_insert_fractional_hours_and_duration() {
+    let address = spawn_app().await;
+
+    let client = client();
+    for (field, value) in &[("hours", json!(1.5)), ("duration", json!("PT1H30M"))] {
+        let mut body = json!({
+            "employee": "employee",
+            "date": "2021-10-09",
+            "project": "project",
+            "story_id": null,
+            "description": "standup"
+        });
+        body[field] = value.clone();
+
+        let response = client
+            .post(format!("{}/api/hours", address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), 201);
+        let result: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(result["hours"], json!(1.5));
+    }
+}
+
+#[actix_rt::test]
+async fn hours_insert_rounded_to_increment() {
+    let mut settings = Settings::default();
+    settings.hours.rounding = 15;
+    let address = spawn_app_with(settings).await;
+
+    let response = client()
+        .post(format!("{}/api/hours", address))
+        .json(&json!({
+            "employee": "employee",
+            "date": "2021-10-09",
+            "project": "project",
+            "story_id": null,
+            "description": "standup",
+            "duration": "PT52M"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+    let result: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(result["hours"], json!(0.75));
+}
+
+#[actix_rt::test]
+async fn hours_insert_too_large_to_round() {
+    let mut settings = Settings::default();
+    settings.hours.rounding = 16;
+    let address = spawn_app_with(settings).await;
+
+    let response = client()
+        .post(format!("{}/api/hours", address))
+        .json(&json!({
+            "employee": "employee",
+            "date": "2021-10-09",
+            "project": "project",
+            "story_id": null,
+            "description": "standup",
+            "hours": f64::from(i32::MAX) / 60.0
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "hours".into(),
+                "can not be larger than 24".into()
+            )]
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn hours_insert_overlapping_times() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let entry = |start: &str, end: &str| {
+        json!({
+            "employee": "employee",
+            "date": "2021-10-09",
+            "project": "project",
+            "story_id": null,
+            "description": "meeting",
+            "start_time": start,
+            "end_time": end
+        })
+    };
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&entry("09:00:00", "10:00:00"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let Hours { id, .. } = response.json().await.unwrap();
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&entry("09:30:00", "10:30:00"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "start_time".into(),
+                format!("overlaps with entry {}", id)
+            )]
+        )
+    );
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&entry("10:00:00", "10:30:00"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let result: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(result["hours"], json!(0.5));
+}
+
+#[actix_rt::test]
+async fn hours_insert_over_daily_limit() {
+    let mut settings = Settings::default();
+    settings.hours.limit.day = 8.0;
+    let address = spawn_app_with(settings).await;
+
+    let client = client();
+    let entry = |hours: u8| {
+        json!({
+            "employee": "employee",
+            "date": "2021-10-09",
+            "project": "project",
+            "story_id": null,
+            "description": "description",
+            "hours": hours
+        })
+    };
+    for _ in 0..2 {
+        let response = client
+            .post(format!("{}/api/hours", address))
+            .json(&entry(3))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status().as_u16(), 201);
+    }
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&entry(3))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "hours".into(),
+                "would make 9 hours on 2021-10-09, the daily maximum is 8, 6 hours are already logged"
+                    .into()
+            )]
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn hours_concurrent_inserts_stay_within_daily_limit() {
+    let mut settings = Settings::default();
+    settings.hours.limit.day = 8.0;
+    let address = spawn_app_with(settings).await;
+
+    let client = client();
+    let requests = (0..6).map(|_| {
+        client
+            .post(format!("{}/api/hours", address))
+            .json(&json!({
+                "employee": "employee",
+                "date": "2021-10-09",
+                "project": "project",
+                "story_id": null,
+                "description": "description",
+                "hours": 3
+            }))
+            .send()
+    });
+    let mut statuses: Vec<u16> = futures::future::join_all(requests)
+        .await
+        .into_iter()
+        .map(|response| {
+            response
+                .expect("Failed to execute request.")
+                .status()
+                .as_u16()
+        })
+        .collect();
+    statuses.sort_unstable();
+
+    assert_eq!(statuses, vec![201, 201, 400, 400, 400, 400]);
+}
+
 #[actix_rt::test]
 async fn hours_insert_and_retrieve_list() {
     let address = spawn_app().await;
 
-    let client = Client::new();
+    let client = client();
 
     let response = client
         .post(format!("{}/api/hours", address))
@@ -137,14 +379,219 @@ async fn hours_insert_and_retrieve_list() {
     assert!(response.status().is_success());
     let result: Vec<Hours> = serde_json::from_str(&response.text().await.unwrap()).unwrap();
     assert_eq!(result.len(), 1);
-    assert_eq!(result.get(0).unwrap().id, id);
+    assert_eq!(result.first().unwrap().id, id);
+}
+
+#[actix_rt::test]
+async fn hours_list_filtered_and_paginated() {
+    let address = spawn_app().await;
+
+    let client = client();
+    for employee in &["alice", "bob", "bob", "bob"] {
+        let response = client
+            .post(format!("{}/api/hours", address))
+            .body(HOURS.replace(
+                "\"employee\": \"employee\"",
+                &format!("\"employee\": \"{}\"", employee),
+            ))
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert!(response.status().is_success());
+    }
+
+    let response = client
+        .get(format!(
+            "{}/api/hours?employee=bob&sort=date&order=desc&limit=2&offset=0",
+            address
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert!(response.status().is_success());
+    assert_eq!(
+        response
+            .headers()
+            .get("X-Total-Count")
+            .unwrap()
+            .to_str()
+            .unwrap(),
+        "3"
+    );
+    let result: Vec<Hours> = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(result.len(), 2);
+    assert!(result.iter().all(|hours| hours.employee == "bob"));
+}
+
+#[actix_rt::test]
+async fn hours_list_limit_too_large() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .get(format!("{}/api/hours?limit=100000", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "limit".into(),
+                "can not be larger than 1000".into()
+            )]
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn hours_export_csv() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let mut ids = vec![];
+    for employee in &["alice", "bob"] {
+        let response = client
+            .post(format!("{}/api/hours", address))
+            .body(HOURS.replace(
+                "\"employee\": \"employee\"",
+                &format!("\"employee\": \"{}\"", employee),
+            ))
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        let Hours { id, .. } = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        ids.push(id);
+    }
+
+    let response = client
+        .get(format!("{}/api/hours/export?employee=bob", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    assert_eq!(
+        response.text().await.unwrap(),
+        format!(
+            "id,date,employee,project,story_id,description,hours,start_time,end_time\r\n\
+             {},2021-10-09,bob,project,,description,1,,\r\n",
+            ids[1]
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn hours_import_csv() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours/import", address))
+        .body(
+            "employee,date,project,story_id,description,hours\r\n\
+             employee,2021-10-09,project,,description,1\r\n\
+             employee,2021-10-10,project,story,description,2\r\n",
+        )
+        .header("Content-Type", "text/csv")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+    let result: ImportReport = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(result.inserted.len(), 2);
+    assert!(result.errors.is_empty());
+
+    let response = client
+        .get(format!("{}/api/hours", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let result: Vec<Hours> = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(result.len(), 2);
+}
+
+#[actix_rt::test]
+async fn hours_import_atomic_rejects_everything() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours/import", address))
+        .body(format!(
+            "[{}, {}]",
+            HOURS,
+            HOURS.replace("\"hours\": 1", "\"hours\": 0")
+        ))
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ImportReport = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert!(result.inserted.is_empty());
+    assert_eq!(
+        result.errors,
+        vec![RowErrors {
+            row: 2,
+            errors: vec![FieldValidationError::new(
+                "hours".into(),
+                "can not be zero".into()
+            )]
+        }]
+    );
+
+    let response = client
+        .get(format!("{}/api/hours", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.text().await.unwrap(), "[]");
+}
+
+#[actix_rt::test]
+async fn hours_import_skip_invalid() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours/import?mode=skip_invalid", address))
+        .body(format!(
+            "[{}, {}]",
+            HOURS.replace("\"hours\": 1", "\"hours\": 0"),
+            HOURS
+        ))
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+    let result: ImportReport = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(result.inserted.len(), 1);
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].row, 1);
 }
 
 #[actix_rt::test]
 async fn hours_insert_and_delete() {
     let address = spawn_app().await;
 
-    let client = Client::new();
+    let client = client();
     let response = client
         .post(format!("{}/api/hours", address))
         .body(HOURS)
@@ -158,6 +605,7 @@ async fn hours_insert_and_delete() {
 
     let response = client
         .delete(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
         .send()
         .await
         .expect("Failed to execute request.");
@@ -175,11 +623,452 @@ async fn hours_insert_and_delete() {
     assert!(result.is_empty());
 }
 
-async fn spawn_app() -> String {
+#[actix_rt::test]
+async fn hours_retried_with_idempotency_key() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let mut ids = vec![];
+    for replayed in &[false, true] {
+        let response = client
+            .post(format!("{}/api/hours", address))
+            .header("Idempotency-Key", "retried")
+            .json(&serde_json::from_str::<serde_json::Value>(HOURS).unwrap())
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), 201);
+        assert_eq!(
+            response.headers().contains_key("Idempotent-Replayed"),
+            *replayed
+        );
+        let Hours { id, .. } = response.json().await.unwrap();
+        ids.push(id);
+    }
+    assert_eq!(ids[0], ids[1]);
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .header("Idempotency-Key", "another")
+        .json(&serde_json::from_str::<serde_json::Value>(HOURS).unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .get(format!("{}/api/hours", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let result: Vec<Hours> = response.json().await.unwrap();
+    assert_eq!(result.len(), 2);
+}
+
+#[actix_rt::test]
+async fn hours_history_of_deleted_entry() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&serde_json::from_str::<serde_json::Value>(HOURS).unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let Hours { id, .. } = response.json().await.unwrap();
+
+    let response = client
+        .delete(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = client
+        .get(format!("{}/api/hours/{}/history", address, id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    let history: Vec<serde_json::Value> = response.json().await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["operation"], "insert");
+    assert_eq!(history[0]["actor"], "test");
+    assert_eq!(history[0]["after"]["description"], "description");
+    assert_eq!(history[1]["operation"], "delete");
+    assert_eq!(history[1]["actor"], "test");
+    assert_eq!(history[1]["before"], history[0]["after"]);
+    assert_eq!(history[1]["after"], serde_json::Value::Null);
+
+    let response = client
+        .get(format!("{}/api/hours/{}/history", address, Uuid::new_v4()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[actix_rt::test]
+async fn hours_delete_to_trash_and_restore() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&serde_json::from_str::<serde_json::Value>(HOURS).unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let Hours { id, .. } = response.json().await.unwrap();
+
+    let response = client
+        .delete(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = client
+        .get(format!("{}/api/hours/{}", address, id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 404);
+
+    let response = client
+        .get(format!("{}/api/hours/trash", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let trash: Vec<serde_json::Value> = response.json().await.unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0]["id"], id.to_string());
+    assert!(trash[0]["deleted_at"].is_string());
+
+    let response = client
+        .post(format!("{}/api/hours/{}/restore", address, id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 428);
+
+    let response = client
+        .post(format!("{}/api/hours/{}/restore", address, id))
+        .header("If-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 412);
+
+    let response = client
+        .post(format!("{}/api/hours/{}/restore", address, id))
+        .header("If-Match", "\"2\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let restored: serde_json::Value = response.json().await.unwrap();
+    assert!(restored.get("deleted_at").is_none());
+
+    let response = client
+        .get(format!("{}/api/hours/{}", address, id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = client
+        .post(format!("{}/api/hours/{}/restore", address, id))
+        .header("If-Match", "*")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[actix_rt::test]
+async fn hours_insert_and_replace() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .body(HOURS)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert!(response.status().is_success());
+    let Hours { id, .. } = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+
+    let response = client
+        .put(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .body(
+            r#"{
+    "employee": "employee",
+    "date": "2021-10-09",
+    "project": "other project",
+    "story_id": "story",
+    "description": "fixed typo",
+    "hours": 2
+}"#,
+        )
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    let result: Hours = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(result.id, id);
+    assert_eq!(result.project, "other project");
+    assert_eq!(result.story_id, Some("story".to_owned()));
+    assert_eq!(result.description, "fixed typo");
+    assert_eq!(result.hours, 2);
+}
+
+#[actix_rt::test]
+async fn hours_stale_changes_are_rejected() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&serde_json::from_str::<serde_json::Value>(HOURS).unwrap())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.headers()["ETag"], "\"1\"");
+    let Hours { id, .. } = response.json().await.unwrap();
+
+    let response = client
+        .get(format!("{}/api/hours/{}", address, id))
+        .header("If-None-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 304);
+
+    let response = client
+        .patch(format!("{}/api/hours/{}", address, id))
+        .json(&json!({"description": "without a version"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 428);
+
+    let response = client
+        .patch(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .json(&json!({"description": "first"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["ETag"], "\"2\"");
+
+    let response = client
+        .patch(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .json(&json!({"description": "second"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 412);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::new("The hours entry has been changed since it was read".into())
+    );
+
+    let response = client
+        .get(format!("{}/api/hours/{}", address, id))
+        .header("If-None-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let result: Hours = response.json().await.unwrap();
+    assert_eq!(result.description, "first");
+
+    let response = client
+        .delete(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "*")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 204);
+}
+
+#[actix_rt::test]
+async fn hours_replace_nonexistent() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .put(format!("{}/api/hours/{}", address, Uuid::new_v4()))
+        .header("If-Match", "\"1\"")
+        .body(HOURS)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[actix_rt::test]
+async fn hours_insert_and_patch() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .body(HOURS)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert!(response.status().is_success());
+    let Hours { id, .. } = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+
+    let response = client
+        .patch(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .body(r#"{"description": "fixed typo"}"#)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    let result: Hours = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(result.id, id);
+    assert_eq!(result.employee, "employee");
+    assert_eq!(result.description, "fixed typo");
+    assert_eq!(result.hours, 1);
+}
+
+#[actix_rt::test]
+async fn hours_patch_too_many_hours() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .body(HOURS)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert!(response.status().is_success());
+    let Hours { id, .. } = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+
+    let response = client
+        .patch(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .body(r#"{"hours": 100}"#)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "hours".into(),
+                "can not be larger than 24".into()
+            )]
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn hours_database_unavailable() {
     let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
     let port = listener.local_addr().unwrap().port();
-    let server = cb_rust_demo::run_server(test_utils::get_db_pool().await, listener)
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let server = cb_rust_demo::run_server(pool.clone(), listener, Settings::default())
         .expect("Server failed to start");
     tokio::spawn(server);
-    format!("http://127.0.0.1:{}", port)
+    pool.close().await;
+
+    let client = client();
+    let response = client
+        .get(format!("http://127.0.0.1:{}/api/hours", port))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 503);
+    let result: ErrorResponse = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::new("Service temporarily unavailable".into())
+    );
+}
+
+/// A client that authenticates with the test API key.
+fn client() -> Client {
+    let authorization = format!("ApiKey {}", test_utils::TEST_API_KEY);
+    let headers = std::iter::once((AUTHORIZATION, authorization.parse().unwrap())).collect();
+    Client::builder().default_headers(headers).build().unwrap()
+}
+
+async fn spawn_app() -> String {
+    spawn_app_with(Settings::default()).await
+}
+
+async fn spawn_app_with(settings: Settings) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let server =
+        cb_rust_demo::run_server(pool, listener, settings).expect("Server failed to start");
+    tokio::spawn(server);
+    let address = format!("http://127.0.0.1:{}", port);
+    for username in &["employee", "alice", "bob"] {
+        create_employee(&address, username).await;
+    }
+    for code in &["project", "other project"] {
+        create_project(&address, code).await;
+    }
+    address
+}
+
+async fn create_employee(address: &str, username: &str) {
+    let response = client()
+        .post(format!("{}/api/employees", address))
+        .json(&serde_json::json!({ "username": username, "name": username }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+async fn create_project(address: &str, code: &str) {
+    let response = client()
+        .post(format!("{}/api/projects", address))
+        .json(&serde_json::json!({ "code": code, "name": code }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
 }
@@ -0,0 +1,197 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use actix_web::{web, App, HttpServer};
+use chrono::Utc;
+use jsonwebtoken::{EncodingKey, Header};
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use cb_rust_demo::configuration::{AuthConfig, Settings};
+use cb_rust_demo::{test_utils, JwtVerifier};
+
+const ISSUER: &str = "https://login.example.com/";
+const AUDIENCE: &str = "cb-rust-demo";
+
+#[derive(Deserialize)]
+struct PeriodLock {
+    changed_by: String,
+}
+
+/// A P-256 key pair, with the public half as a JWK.
+struct TestKey {
+    jwk: Value,
+    encoding_key: EncodingKey,
+}
+
+impl TestKey {
+    fn generate(kid: &str) -> TestKey {
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+                .unwrap();
+        let pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref()).unwrap();
+        // An uncompressed point, 0x04 followed by the coordinates.
+        let point = pair.public_key().as_ref();
+        TestKey {
+            jwk: json!({
+                "kty": "EC",
+                "crv": "P-256",
+                "x": base64::encode_config(&point[1..33], base64::URL_SAFE_NO_PAD),
+                "y": base64::encode_config(&point[33..], base64::URL_SAFE_NO_PAD),
+                "kid": kid,
+                "alg": "ES256",
+                "use": "sig",
+            }),
+            encoding_key: EncodingKey::from_ec_der(pkcs8.as_ref()),
+        }
+    }
+
+    fn jwks(&self) -> Value {
+        json!({ "keys": [self.jwk] })
+    }
+
+    fn sign(&self, claims: Value) -> String {
+        let mut header = Header::new(jsonwebtoken::Algorithm::ES256);
+        header.kid = self.jwk["kid"].as_str().map(str::to_owned);
+        jsonwebtoken::encode(&header, &claims, &self.encoding_key).unwrap()
+    }
+}
+
+fn claims(subject: &str) -> Value {
+    json!({
+        "sub": subject,
+        "iss": ISSUER,
+        "aud": AUDIENCE,
+        "exp": Utc::now().timestamp() + 600,
+    })
+}
+
+#[actix_rt::test]
+async fn jwt_subject_is_the_actor() {
+    let key = TestKey::generate("key-1");
+    let address = spawn_app(jwks_file(&key)).await;
+    let mut admin = claims("alice");
+    admin["role"] = json!("admin");
+
+    let response = Client::new()
+        .put(format!("{}/api/admin/period-lock", address))
+        .bearer_auth(key.sign(admin))
+        .json(&json!({"locked_until": "2021-10-31"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    let lock: PeriodLock = response.json().await.unwrap();
+    assert_eq!(lock.changed_by, "alice");
+}
+
+#[actix_rt::test]
+async fn jwt_invalid_tokens_are_rejected() {
+    let key = TestKey::generate("key-1");
+    let address = spawn_app(jwks_file(&key)).await;
+    let now = Utc::now().timestamp();
+
+    let mut expired = claims("alice");
+    expired["exp"] = json!(now - 600);
+    let mut other_issuer = claims("alice");
+    other_issuer["iss"] = json!("https://evil.example.com/");
+    let mut other_audience = claims("alice");
+    other_audience["aud"] = json!("other-service");
+    let mut without_subject = claims("alice");
+    without_subject.as_object_mut().unwrap().remove("sub");
+    let forged = TestKey::generate("key-1").sign(claims("alice"));
+    let unknown_key = TestKey::generate("key-2").sign(claims("alice"));
+
+    let client = Client::new();
+    for token in &[
+        key.sign(expired),
+        key.sign(other_issuer),
+        key.sign(other_audience),
+        key.sign(without_subject),
+        forged,
+        unknown_key,
+        "not.a.token".to_owned(),
+    ] {
+        let response = client
+            .get(format!("{}/api/hours", address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = client
+        .get(format!("{}/api/hours", address))
+        .bearer_auth(key.sign(claims("alice")))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[actix_rt::test]
+async fn jwt_jwks_from_url() {
+    let key = TestKey::generate("key-1");
+    let jwks = key.jwks();
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let idp = HttpServer::new(move || {
+        let jwks = jwks.clone();
+        App::new().route(
+            "/.well-known/jwks.json",
+            web::get().to(move || {
+                let jwks = jwks.clone();
+                async move { web::Json(jwks) }
+            }),
+        )
+    })
+    .listen(listener)
+    .unwrap()
+    .run();
+    tokio::spawn(idp);
+
+    let address = spawn_app(format!("http://127.0.0.1:{}/.well-known/jwks.json", port)).await;
+
+    let response = Client::new()
+        .get(format!("{}/api/hours", address))
+        .header(AUTHORIZATION, format!("Bearer {}", key.sign(claims("bob"))))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+/// Writes the key set of `key` to a temporary file and returns its path.
+fn jwks_file(key: &TestKey) -> String {
+    let path = std::env::temp_dir().join(format!("{}.json", Uuid::new_v4()));
+    std::fs::write(&path, key.jwks().to_string()).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+async fn spawn_app(jwks: String) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    let config = AuthConfig {
+        jwks: Some(jwks),
+        issuer: ISSUER.to_owned(),
+        audience: AUDIENCE.to_owned(),
+    };
+    let settings = Settings {
+        jwt: JwtVerifier::load(&config).await.unwrap(),
+        ..Settings::default()
+    };
+    let server =
+        cb_rust_demo::run_server(pool, listener, settings).expect("Server failed to start");
+    tokio::spawn(server);
+    format!("http://127.0.0.1:{}", port)
+}
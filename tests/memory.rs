@@ -0,0 +1,108 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use cb_rust_demo::configuration::Settings;
+use cb_rust_demo::db::MemoryDatabase;
+use cb_rust_demo::test_utils;
+
+const HOURS: &str = r#"{
+    "employee": "employee",
+    "date": "2021-10-09",
+    "project": "project",
+    "story_id": null,
+    "description": "description",
+    "hours": 1
+}"#;
+
+#[derive(Deserialize)]
+struct Hours {
+    id: Uuid,
+}
+
+#[actix_rt::test]
+async fn hours_insert_retrieve_and_delete() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/employees", address))
+        .body(r#"{"username": "employee", "name": "Employee"}"#)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .post(format!("{}/api/projects", address))
+        .body(r#"{"code": "project", "name": "Project"}"#)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .body(HOURS)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+    let Hours { id } = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+
+    let response = client
+        .get(format!("{}/api/hours", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert!(response.status().is_success());
+    let result: Vec<Hours> = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result.first().unwrap().id, id);
+
+    let response = client
+        .delete(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = client
+        .get(format!("{}/api/hours/{}", address, id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+/// A client that authenticates with the test API key.
+fn client() -> Client {
+    let authorization = format!("ApiKey {}", test_utils::TEST_API_KEY);
+    let headers = std::iter::once((AUTHORIZATION, authorization.parse().unwrap())).collect();
+    Client::builder().default_headers(headers).build().unwrap()
+}
+
+async fn spawn_app() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let db = MemoryDatabase::default();
+    test_utils::add_test_api_key(&db).await;
+    let server = cb_rust_demo::run_server(db, listener, Settings::default())
+        .expect("Server failed to start");
+    tokio::spawn(server);
+    format!("http://127.0.0.1:{}", port)
+}
@@ -0,0 +1,403 @@
+#![cfg(test)]
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::Mutex;
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use chrono::Utc;
+use jsonwebtoken::{EncodingKey, Header};
+use reqwest::header::{COOKIE, LOCATION, SET_COOKIE};
+use reqwest::{redirect, Client, Response};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use cb_rust_demo::configuration::{OidcConfig, Settings};
+use cb_rust_demo::error::ErrorResponse;
+use cb_rust_demo::{test_utils, OidcClient};
+
+const CLIENT_ID: &str = "cb-rust-demo";
+
+#[derive(Deserialize)]
+struct Session {
+    subject: String,
+    role: String,
+}
+
+/// What the mock identity provider remembers of an authorization request until its code is
+/// redeemed.
+struct Grant {
+    code_challenge: String,
+    nonce: String,
+    redirect_uri: String,
+}
+
+#[derive(Deserialize)]
+struct AuthorizationRequest {
+    response_type: String,
+    client_id: String,
+    redirect_uri: String,
+    state: String,
+    nonce: String,
+    code_challenge: String,
+    code_challenge_method: String,
+}
+
+#[derive(Deserialize)]
+struct TokenRequest {
+    grant_type: String,
+    code: String,
+    redirect_uri: String,
+    client_id: String,
+    code_verifier: String,
+}
+
+/// An identity provider that logs everyone in as `alice`, a manager, without asking. It checks
+/// the PKCE verifier when the code is redeemed.
+struct MockProvider {
+    issuer: String,
+    key_pair: EcdsaKeyPair,
+    pkcs8: Vec<u8>,
+    grants: Mutex<HashMap<String, Grant>>,
+}
+
+impl MockProvider {
+    fn jwks(&self) -> Value {
+        // An uncompressed point, 0x04 followed by the coordinates.
+        let point = self.key_pair.public_key().as_ref();
+        json!({
+            "keys": [{
+                "kty": "EC",
+                "crv": "P-256",
+                "x": base64::encode_config(&point[1..33], base64::URL_SAFE_NO_PAD),
+                "y": base64::encode_config(&point[33..], base64::URL_SAFE_NO_PAD),
+                "kid": "key-1",
+                "alg": "ES256",
+                "use": "sig",
+            }]
+        })
+    }
+
+    fn authorize(&self, request: AuthorizationRequest) -> HttpResponse {
+        assert_eq!(request.response_type, "code");
+        assert_eq!(request.client_id, CLIENT_ID);
+        assert_eq!(request.code_challenge_method, "S256");
+        let code = uuid::Uuid::new_v4().to_string();
+        let location = format!(
+            "{}?code={}&state={}",
+            request.redirect_uri, code, request.state
+        );
+        self.grants.lock().unwrap().insert(
+            code,
+            Grant {
+                code_challenge: request.code_challenge,
+                nonce: request.nonce,
+                redirect_uri: request.redirect_uri,
+            },
+        );
+        HttpResponse::Found()
+            .insert_header((LOCATION.as_str(), location))
+            .finish()
+    }
+
+    fn token(&self, request: TokenRequest) -> HttpResponse {
+        let grant = match self.grants.lock().unwrap().remove(&request.code) {
+            Some(grant) => grant,
+            None => return HttpResponse::BadRequest().json(json!({"error": "invalid_grant"})),
+        };
+        let challenge = base64::encode_config(
+            Sha256::digest(request.code_verifier.as_bytes()),
+            base64::URL_SAFE_NO_PAD,
+        );
+        if request.grant_type != "authorization_code"
+            || request.client_id != CLIENT_ID
+            || request.redirect_uri != grant.redirect_uri
+            || challenge != grant.code_challenge
+        {
+            return HttpResponse::BadRequest().json(json!({"error": "invalid_grant"}));
+        }
+        let claims = json!({
+            "sub": "alice",
+            "role": "manager",
+            "iss": self.issuer,
+            "aud": CLIENT_ID,
+            "exp": Utc::now().timestamp() + 600,
+            "nonce": grant.nonce,
+        });
+        let mut header = Header::new(jsonwebtoken::Algorithm::ES256);
+        header.kid = Some("key-1".to_owned());
+        let id_token =
+            jsonwebtoken::encode(&header, &claims, &EncodingKey::from_ec_der(&self.pkcs8)).unwrap();
+        HttpResponse::Ok().json(json!({
+            "access_token": "opaque",
+            "token_type": "Bearer",
+            "id_token": id_token,
+        }))
+    }
+}
+
+async fn authorize(
+    provider: web::Data<MockProvider>,
+    request: web::Query<AuthorizationRequest>,
+) -> HttpResponse {
+    provider.authorize(request.into_inner())
+}
+
+async fn token(
+    provider: web::Data<MockProvider>,
+    request: web::Form<TokenRequest>,
+) -> HttpResponse {
+    provider.token(request.into_inner())
+}
+
+/// Starts the mock identity provider and returns its issuer URL.
+fn spawn_provider() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let issuer = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+    let pkcs8 =
+        EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+            .unwrap();
+    let provider = web::Data::new(MockProvider {
+        issuer: issuer.clone(),
+        key_pair: EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref())
+            .unwrap(),
+        pkcs8: pkcs8.as_ref().to_vec(),
+        grants: Mutex::new(HashMap::new()),
+    });
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(provider.clone())
+            .route(
+                "/.well-known/openid-configuration",
+                web::get().to(|provider: web::Data<MockProvider>| async move {
+                    web::Json(json!({
+                        "issuer": provider.issuer,
+                        "authorization_endpoint": format!("{}/authorize", provider.issuer),
+                        "token_endpoint": format!("{}/token", provider.issuer),
+                        "jwks_uri": format!("{}/jwks", provider.issuer),
+                    }))
+                }),
+            )
+            .route(
+                "/jwks",
+                web::get().to(|provider: web::Data<MockProvider>| async move {
+                    web::Json(provider.jwks())
+                }),
+            )
+            .route("/authorize", web::get().to(authorize))
+            .route("/token", web::post().to(token))
+    })
+    .listen(listener)
+    .unwrap()
+    .run();
+    tokio::spawn(server);
+    issuer
+}
+
+#[actix_rt::test]
+async fn oidc_login_starts_a_session_until_logout() {
+    let address = spawn_app().await;
+    let client = no_redirects();
+
+    let response = client
+        .get(format!("{}/auth/login", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 302);
+    let login_state = cookie(&response, "login_state").unwrap();
+
+    let response = client
+        .get(location(&response))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 302);
+    let callback = location(&response);
+
+    let response = client
+        .get(&callback)
+        .header(COOKIE, format!("login_state={}", login_state))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 302);
+    assert_eq!(location(&response), "/openapi/");
+    let session_cookie = set_cookie(&response, "session").unwrap();
+    for attribute in &["Secure", "HttpOnly", "SameSite=Strict", "Path=/"] {
+        assert!(session_cookie.contains(attribute), "{}", session_cookie);
+    }
+    let session = format!("session={}", cookie(&response, "session").unwrap());
+
+    let response = client
+        .get(format!("{}/auth/session", address))
+        .header(COOKIE, &session)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let current: Session = response.json().await.unwrap();
+    assert_eq!(current.subject, "alice");
+    assert_eq!(current.role, "manager");
+
+    let response = client
+        .get(format!("{}/api/hours", address))
+        .header(COOKIE, &session)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The login can only be completed once.
+    let response = client
+        .get(&callback)
+        .header(COOKIE, format!("login_state={}", login_state))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = client
+        .post(format!("{}/auth/logout", address))
+        .header(COOKIE, &session)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 204);
+    assert!(set_cookie(&response, "session")
+        .unwrap()
+        .contains("Max-Age=0"));
+
+    let response = client
+        .get(format!("{}/api/hours", address))
+        .header(COOKIE, &session)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[actix_rt::test]
+async fn oidc_login_is_completed_in_the_browser_that_started_it() {
+    let address = spawn_app().await;
+    let client = no_redirects();
+
+    let response = client
+        .get(format!("{}/auth/login", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let response = client
+        .get(location(&response))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let callback = location(&response);
+
+    for login_state in &[None, Some("login_state=forged")] {
+        let mut request = client.get(&callback);
+        if let Some(login_state) = login_state {
+            request = request.header(COOKIE, *login_state);
+        }
+        let response = request.send().await.expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), 400);
+        assert!(set_cookie(&response, "session").is_none());
+        let result: ErrorResponse = response.json().await.unwrap();
+        assert_eq!(
+            result,
+            ErrorResponse::new("The login was started in another browser".into())
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn oidc_provider_errors_are_reported() {
+    let address = spawn_app().await;
+
+    let response = no_redirects()
+        .get(format!(
+            "{}/auth/callback?error=access_denied&state=state",
+            address
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::new("The identity provider refused the login: access_denied".into())
+    );
+}
+
+#[actix_rt::test]
+async fn oidc_login_is_off_without_a_provider() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    let server = cb_rust_demo::run_server(pool, listener, Settings::default())
+        .expect("Server failed to start");
+    tokio::spawn(server);
+
+    let response = no_redirects()
+        .get(format!("http://127.0.0.1:{}/auth/login", port))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+fn no_redirects() -> Client {
+    Client::builder()
+        .redirect(redirect::Policy::none())
+        .build()
+        .unwrap()
+}
+
+fn location(response: &Response) -> String {
+    response.headers()[LOCATION].to_str().unwrap().to_owned()
+}
+
+/// The `Set-Cookie` header of the response for the cookie `name`.
+fn set_cookie(response: &Response, name: &str) -> Option<String> {
+    response
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find(|value| value.starts_with(&format!("{}=", name)))
+        .map(str::to_owned)
+}
+
+/// The value that the response sets the cookie `name` to.
+fn cookie(response: &Response, name: &str) -> Option<String> {
+    let set_cookie = set_cookie(response, name)?;
+    let value = set_cookie[name.len() + 1..].split(';').next()?;
+    Some(value.to_owned())
+}
+
+async fn spawn_app() -> String {
+    let issuer = spawn_provider();
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let address = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+    let pool = test_utils::get_db_pool().await;
+    let config = OidcConfig {
+        issuer: Some(issuer),
+        client_id: CLIENT_ID.to_owned(),
+        redirect_url: format!("{}/auth/callback", address),
+        ..OidcConfig::default()
+    };
+    let settings = Settings {
+        oidc: OidcClient::discover(&config).await.unwrap(),
+        ..Settings::default()
+    };
+    let server =
+        cb_rust_demo::run_server(pool, listener, settings).expect("Server failed to start");
+    tokio::spawn(server);
+    address
+}
@@ -0,0 +1,209 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use cb_rust_demo::configuration::Settings;
+use cb_rust_demo::error::{ErrorResponse, FieldValidationError};
+use cb_rust_demo::test_utils;
+
+#[derive(Deserialize, Debug)]
+struct PeriodLock {
+    locked_until: Option<String>,
+    reason: Option<String>,
+    changed_by: String,
+}
+
+#[derive(Deserialize)]
+struct Hours {
+    id: Uuid,
+}
+
+#[actix_rt::test]
+async fn periods_locked_dates_refuse_changes() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("2021-10-29"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let Hours { id } = response.json().await.unwrap();
+
+    let response = client
+        .put(format!("{}/api/admin/period-lock", address))
+        .json(&json!({"locked_until": "2021-10-31"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let lock: PeriodLock = response.json().await.unwrap();
+    assert_eq!(lock.locked_until, Some("2021-10-31".to_owned()));
+    assert_eq!(lock.changed_by, "test");
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("2021-10-31"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 409);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::new(
+            "Hours on 2021-10-31 can not be changed, periods up to 2021-10-31 are closed".into()
+        )
+    );
+
+    let response = client
+        .patch(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .json(&json!({"date": "2021-11-01"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 409);
+
+    let response = client
+        .delete(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 409);
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("2021-11-01"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+#[actix_rt::test]
+async fn periods_reopen_requires_reason_and_is_recorded() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .put(format!("{}/api/admin/period-lock", address))
+        .json(&json!({"locked_until": "2021-10-31"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = client
+        .put(format!("{}/api/admin/period-lock", address))
+        .json(&json!({"locked_until": "2021-09-30"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "reason".into(),
+                "is required to reopen a closed period".into()
+            )]
+        )
+    );
+
+    let response = client
+        .put(format!("{}/api/admin/period-lock", address))
+        .json(&json!({"locked_until": "2021-09-30", "reason": "Late invoice"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("2021-10-29"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .get(format!("{}/api/admin/period-lock/history", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let history: Vec<PeriodLock> = response.json().await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].reason, Some("Late invoice".to_owned()));
+    assert_eq!(history[0].changed_by, "test");
+    assert_eq!(history[1].changed_by, "test");
+}
+
+#[actix_rt::test]
+async fn periods_never_locked() {
+    let address = spawn_app().await;
+
+    let response = client()
+        .get(format!("{}/api/admin/period-lock", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+fn hours(date: &str) -> serde_json::Value {
+    json!({
+        "employee": "employee",
+        "date": date,
+        "project": "project",
+        "story_id": null,
+        "description": "description",
+        "hours": 1
+    })
+}
+
+/// A client that authenticates with the test API key.
+fn client() -> Client {
+    let authorization = format!("ApiKey {}", test_utils::TEST_API_KEY);
+    let headers = std::iter::once((AUTHORIZATION, authorization.parse().unwrap())).collect();
+    Client::builder().default_headers(headers).build().unwrap()
+}
+
+async fn spawn_app() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let server = cb_rust_demo::run_server(pool, listener, Settings::default())
+        .expect("Server failed to start");
+    tokio::spawn(server);
+    let address = format!("http://127.0.0.1:{}", port);
+    let client = client();
+    let response = client
+        .post(format!("{}/api/employees", address))
+        .json(&json!({"username": "employee", "name": "Employee"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let response = client
+        .post(format!("{}/api/projects", address))
+        .json(&json!({"code": "project", "name": "Project"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    address
+}
@@ -0,0 +1,281 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use cb_rust_demo::configuration::Settings;
+use cb_rust_demo::error::{ErrorResponse, FieldValidationError};
+use cb_rust_demo::test_utils;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Project {
+    id: Uuid,
+    code: String,
+    name: String,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    archived: bool,
+}
+
+#[actix_rt::test]
+async fn projects_create_update_and_list() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/projects", address))
+        .json(&json!({"code": " website ", "name": "Website", "start_date": "2021-01-01"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+    let created: Project = response.json().await.unwrap();
+    assert_eq!(created.code, "website");
+    assert_eq!(created.start_date, Some("2021-01-01".to_owned()));
+    assert!(!created.archived);
+
+    let response = client
+        .put(format!("{}/api/projects/{}", address, created.id))
+        .json(&json!({"code": "website", "name": "Website", "archived": true}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    let updated: Project = response.json().await.unwrap();
+    assert!(updated.archived);
+
+    let response = client
+        .get(format!("{}/api/projects?archived=true", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert!(response.status().is_success());
+    let result: Vec<Project> = response.json().await.unwrap();
+    assert_eq!(result, vec![updated]);
+
+    let response = client
+        .get(format!("{}/api/projects?archived=false", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    let result: Vec<Project> = response.json().await.unwrap();
+    assert_eq!(
+        result.iter().map(|p| p.code.as_str()).collect::<Vec<_>>(),
+        vec!["project"]
+    );
+}
+
+#[actix_rt::test]
+async fn projects_duplicate_code() {
+    let address = spawn_app().await;
+
+    let response = client()
+        .post(format!("{}/api/projects", address))
+        .json(&json!({"code": "project", "name": "Again"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[actix_rt::test]
+async fn projects_hours_require_existing_project() {
+    let address = spawn_app().await;
+
+    let response = client()
+        .post(format!("{}/api/hours", address))
+        .json(&hours("nothing", "2021-10-09"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "project".into(),
+                "does not exist".into()
+            )]
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn projects_hours_rejected_for_archived_project() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/projects", address))
+        .json(&json!({"code": "old", "name": "Old", "archived": true}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("old", "2021-10-09"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "project".into(),
+                "is archived".into()
+            )]
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn projects_hours_rejected_outside_project_dates() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/projects", address))
+        .json(&json!({
+            "code": "sprint",
+            "name": "Sprint",
+            "start_date": "2021-10-01",
+            "end_date": "2021-10-14"
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("sprint", "2021-10-09"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("sprint", "2021-10-15"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "date".into(),
+                "is after the project ended on 2021-10-14".into()
+            )]
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn projects_delete_with_hours() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("project", "2021-10-09"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .get(format!("{}/api/projects", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let projects: Vec<Project> = response.json().await.unwrap();
+
+    let response = client
+        .delete(format!("{}/api/projects/{}", address, projects[0].id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 409);
+}
+
+#[actix_rt::test]
+async fn projects_delete_nonexistent() {
+    let address = spawn_app().await;
+
+    let response = client()
+        .delete(format!("{}/api/projects/{}", address, Uuid::new_v4()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+fn hours(project: &str, date: &str) -> serde_json::Value {
+    json!({
+        "employee": "employee",
+        "date": date,
+        "project": project,
+        "story_id": null,
+        "description": "description",
+        "hours": 1
+    })
+}
+
+/// A client that authenticates with the test API key.
+fn client() -> Client {
+    let authorization = format!("ApiKey {}", test_utils::TEST_API_KEY);
+    let headers = std::iter::once((AUTHORIZATION, authorization.parse().unwrap())).collect();
+    Client::builder().default_headers(headers).build().unwrap()
+}
+
+async fn spawn_app() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let server = cb_rust_demo::run_server(pool, listener, Settings::default())
+        .expect("Server failed to start");
+    tokio::spawn(server);
+    let address = format!("http://127.0.0.1:{}", port);
+    let client = client();
+    let response = client
+        .post(format!("{}/api/employees", address))
+        .json(&json!({"username": "employee", "name": "Employee"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let response = client
+        .post(format!("{}/api/projects", address))
+        .json(&json!({"code": "project", "name": "Project"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    address
+}
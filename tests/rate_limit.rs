@@ -0,0 +1,180 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use reqwest::header::{AUTHORIZATION, RETRY_AFTER};
+use reqwest::{Client, Response};
+use serde::Deserialize;
+use serde_json::json;
+
+use cb_rust_demo::configuration::{BucketConfig, RateLimitConfig, Settings};
+use cb_rust_demo::error::ErrorResponse;
+use cb_rust_demo::test_utils;
+
+#[derive(Deserialize)]
+struct CreatedApiKey {
+    key: String,
+}
+
+#[actix_rt::test]
+async fn rate_limit_applies_per_client_and_route_group() {
+    let address = spawn_app().await;
+    let client = Client::new();
+    let admin = format!("ApiKey {}", test_utils::TEST_API_KEY);
+
+    let response = client
+        .post(format!("{}/api/admin/api-keys", address))
+        .header(AUTHORIZATION, &admin)
+        .json(&json!({"name": "import script", "role": "admin"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    assert_eq!(header(&response, "RateLimit-Remaining"), "1");
+    let CreatedApiKey { key } = response.json().await.unwrap();
+    let script = format!("ApiKey {}", key);
+
+    let create_project = |authorization: &str, code: &str| {
+        client
+            .post(format!("{}/api/projects", address))
+            .header(AUTHORIZATION, authorization)
+            .json(&json!({"code": code, "name": code}))
+            .send()
+    };
+    let response = create_project(&admin, "first")
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    assert_eq!(header(&response, "RateLimit-Remaining"), "0");
+
+    let response = create_project(&admin, "second")
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(header(&response, RETRY_AFTER.as_str()), "60");
+    assert_eq!(header(&response, "RateLimit-Limit"), "2");
+    assert_eq!(header(&response, "RateLimit-Remaining"), "0");
+    assert_eq!(header(&response, "RateLimit-Reset"), "120");
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::new("Too many requests, try again in 60 seconds".into())
+    );
+
+    // Reads have a limit of their own.
+    let response = client
+        .get(format!("{}/api/projects", address))
+        .header(AUTHORIZATION, &admin)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    // And so do other clients.
+    let response = create_project(&script, "second")
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+#[actix_rt::test]
+async fn rate_limit_applies_to_logins_per_address() {
+    let address = spawn_app().await;
+    let client = Client::new();
+
+    // Logging in is not configured, which is answered after the limit is checked.
+    for (session, expected) in &[("first", 404), ("second", 404), ("third", 429)] {
+        let response = client
+            .get(format!("{}/auth/login", address))
+            .header("Cookie", format!("session={}", session))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status().as_u16(), *expected);
+        assert_eq!(header(&response, "RateLimit-Limit"), "2");
+    }
+}
+
+#[actix_rt::test]
+async fn rate_limit_of_logins_does_not_apply_to_authenticated_requests() {
+    let address = spawn_app().await;
+    let client = Client::new();
+
+    for expected in &[404, 404, 429] {
+        let response = client
+            .get(format!("{}/auth/session", address))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status().as_u16(), *expected);
+    }
+
+    let response = client
+        .get(format!("{}/api/projects", address))
+        .header(
+            AUTHORIZATION,
+            format!("ApiKey {}", test_utils::TEST_API_KEY),
+        )
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[actix_rt::test]
+async fn rate_limit_applies_to_failed_authentication_per_address() {
+    let address = spawn_app().await;
+    let client = Client::new();
+    let list_projects = |authorization: &str| {
+        client
+            .get(format!("{}/api/projects", address))
+            .header(AUTHORIZATION, authorization)
+            .send()
+    };
+
+    for key in &["first", "second"] {
+        let response = list_projects(&format!("ApiKey {}", key))
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    // Once they are used up, even valid credentials are not tried from the address.
+    let response = list_projects(&format!("ApiKey {}", test_utils::TEST_API_KEY))
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(header(&response, "RateLimit-Limit"), "2");
+}
+
+fn header<'a>(response: &'a Response, name: &str) -> &'a str {
+    response.headers()[name].to_str().unwrap()
+}
+
+async fn spawn_app() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let settings = Settings {
+        rate_limit: RateLimitConfig {
+            write: BucketConfig {
+                burst: 2,
+                per_minute: 1,
+            },
+            auth: BucketConfig {
+                burst: 2,
+                per_minute: 1,
+            },
+            failed_auth: BucketConfig {
+                burst: 2,
+                per_minute: 1,
+            },
+            ..RateLimitConfig::default()
+        },
+        ..Settings::default()
+    };
+    let server =
+        cb_rust_demo::run_server(pool, listener, settings).expect("Server failed to start");
+    tokio::spawn(server);
+    format!("http://127.0.0.1:{}", port)
+}
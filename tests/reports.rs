@@ -0,0 +1,116 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use cb_rust_demo::configuration::Settings;
+use cb_rust_demo::test_utils;
+
+#[actix_rt::test]
+async fn summary_by_week() {
+    let address = spawn_app().await;
+
+    let client = client();
+    for (employee, date, hours) in &[
+        ("alice", "2021-10-04", 2),
+        ("alice", "2021-10-08", 3),
+        ("bob", "2021-10-05", 4),
+        ("bob", "2021-10-11", 1),
+    ] {
+        let response = client
+            .post(format!("{}/api/hours", address))
+            .json(&json!({
+                "employee": employee,
+                "date": date,
+                "project": "project",
+                "story_id": null,
+                "description": "description",
+                "hours": hours
+            }))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert!(response.status().is_success());
+    }
+
+    let response = client
+        .get(format!(
+            "{}/api/reports/summary?from=2021-10-01&to=2021-10-31&granularity=week",
+            address
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert!(response.status().is_success());
+    let result: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(result["total"], 10);
+    assert_eq!(result["periods"][0]["start"], "2021-10-04");
+    assert_eq!(result["periods"][0]["total"], 9);
+    assert_eq!(result["periods"][0]["employees"][0]["employee"], "alice");
+    assert_eq!(result["periods"][0]["employees"][0]["total"], 5);
+    assert_eq!(result["periods"][0]["employees"][1]["total"], 4);
+    assert_eq!(result["periods"][1]["start"], "2021-10-11");
+    assert_eq!(result["periods"][1]["total"], 1);
+}
+
+#[actix_rt::test]
+async fn summary_requires_date_range() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .get(format!("{}/api/reports/summary?granularity=day", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+/// A client that authenticates with the test API key.
+fn client() -> Client {
+    let authorization = format!("ApiKey {}", test_utils::TEST_API_KEY);
+    let headers = std::iter::once((AUTHORIZATION, authorization.parse().unwrap())).collect();
+    Client::builder().default_headers(headers).build().unwrap()
+}
+
+async fn spawn_app() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let server = cb_rust_demo::run_server(pool, listener, Settings::default())
+        .expect("Server failed to start");
+    tokio::spawn(server);
+    let address = format!("http://127.0.0.1:{}", port);
+    for username in &["alice", "bob"] {
+        create_employee(&address, username).await;
+    }
+    for code in &["project"] {
+        create_project(&address, code).await;
+    }
+    address
+}
+
+async fn create_employee(address: &str, username: &str) {
+    let response = client()
+        .post(format!("{}/api/employees", address))
+        .json(&serde_json::json!({ "username": username, "name": username }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+async fn create_project(address: &str, code: &str) {
+    let response = client()
+        .post(format!("{}/api/projects", address))
+        .json(&serde_json::json!({ "code": code, "name": code }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+}
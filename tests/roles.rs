@@ -0,0 +1,444 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use cb_rust_demo::configuration::Settings;
+use cb_rust_demo::error::ErrorResponse;
+use cb_rust_demo::test_utils;
+
+#[derive(Deserialize)]
+struct Hours {
+    id: Uuid,
+    employee: String,
+    logged_by: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Timer {
+    id: Uuid,
+    employee: String,
+}
+
+#[derive(Deserialize)]
+struct CreatedApiKey {
+    key: String,
+}
+
+/// The app with `boss` managing `alice`, `bob` on their own and an entry logged for each of them
+/// by the admin.
+struct TestApp {
+    address: String,
+    alice: Uuid,
+    bob: Uuid,
+}
+
+#[actix_rt::test]
+async fn roles_employees_reach_only_their_own_hours() {
+    let app = spawn_app().await;
+    let alice = as_user(&app, "alice", "employee").await;
+
+    let response = alice(Client::new().get(format!("{}/api/hours", app.address)))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let hours: Vec<Hours> = response.json().await.unwrap();
+    assert_eq!(hours.len(), 1);
+    assert_eq!(hours[0].employee, "alice");
+
+    let client = Client::new();
+    for request in [
+        client.get(format!("{}/api/hours/{}", app.address, app.bob)),
+        client.get(format!("{}/api/hours/{}/history", app.address, app.bob)),
+        client
+            .patch(format!("{}/api/hours/{}", app.address, app.bob))
+            .header("If-Match", "\"1\"")
+            .json(&json!({"description": "mine now"})),
+        client
+            .delete(format!("{}/api/hours/{}", app.address, app.bob))
+            .header("If-Match", "\"1\""),
+        client
+            .post(format!("{}/api/hours", app.address))
+            .json(&hours_of("bob")),
+        client
+            .patch(format!("{}/api/hours/{}", app.address, app.alice))
+            .header("If-Match", "\"1\"")
+            .json(&json!({"employee": "bob"})),
+    ] {
+        let response = alice(request)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), 403);
+        let result: ErrorResponse = response.json().await.unwrap();
+        assert_eq!(
+            result,
+            ErrorResponse::new("You are not allowed to access the hours of this employee".into())
+        );
+    }
+
+    let response = alice(client.delete(format!("{}/api/hours/{}", app.address, app.alice)))
+        .header("If-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 204);
+}
+
+#[actix_rt::test]
+async fn roles_managers_see_the_hours_of_their_team() {
+    let app = spawn_app().await;
+    let boss = as_user(&app, "boss", "manager").await;
+
+    let client = Client::new();
+    let response = boss(client.get(format!("{}/api/hours", app.address)))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let hours: Vec<Hours> = response.json().await.unwrap();
+    assert_eq!(hours.len(), 1);
+    assert_eq!(hours[0].id, app.alice);
+
+    let response = boss(client.get(format!("{}/api/hours/{}", app.address, app.alice)))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = boss(client.get(format!("{}/api/hours/{}", app.address, app.bob)))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = boss(client.delete(format!("{}/api/hours/{}", app.address, app.alice)))
+        .header("If-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[actix_rt::test]
+async fn roles_hours_are_logged_for_the_caller() {
+    let app = spawn_app().await;
+    let alice = as_user(&app, "alice", "employee").await;
+
+    let mut own_hours = hours_of("alice");
+    own_hours.as_object_mut().unwrap().remove("employee");
+    let response = alice(Client::new().post(format!("{}/api/hours", app.address)))
+        .json(&own_hours)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let hours: Hours = response.json().await.unwrap();
+    assert_eq!(hours.employee, "alice");
+    assert_eq!(hours.logged_by, None);
+
+    let response = admin()
+        .get(format!("{}/api/hours/{}", app.address, app.alice))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let hours: Hours = response.json().await.unwrap();
+    assert_eq!(hours.employee, "alice");
+    assert_eq!(hours.logged_by.as_deref(), Some("test"));
+}
+
+#[actix_rt::test]
+async fn roles_only_admins_manage_employees_projects_and_periods() {
+    let app = spawn_app().await;
+    let boss = as_user(&app, "boss", "manager").await;
+
+    let client = Client::new();
+    let employee = json!({"username": "carol", "name": "Carol"});
+    let project = json!({"code": "other", "name": "Other"});
+    let id = Uuid::new_v4();
+    for request in [
+        client
+            .post(format!("{}/api/employees", app.address))
+            .json(&employee),
+        client
+            .put(format!("{}/api/employees/{}", app.address, id))
+            .json(&employee),
+        client.delete(format!("{}/api/employees/{}", app.address, id)),
+        client
+            .post(format!("{}/api/projects", app.address))
+            .json(&project),
+        client
+            .put(format!("{}/api/projects/{}", app.address, id))
+            .json(&project),
+        client.delete(format!("{}/api/projects/{}", app.address, id)),
+        client
+            .put(format!("{}/api/admin/period-lock", app.address))
+            .json(&json!({"locked_until": "2021-10-31"})),
+    ] {
+        let response = boss(request)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), 403);
+        let result: ErrorResponse = response.json().await.unwrap();
+        assert_eq!(
+            result,
+            ErrorResponse::new("The admin role is required".into())
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn roles_timers_of_others_are_out_of_reach() {
+    let app = spawn_app().await;
+    let alice = as_user(&app, "alice", "employee").await;
+    let boss = as_user(&app, "boss", "manager").await;
+
+    let client = Client::new();
+    let response = alice(client.post(format!("{}/api/timers", app.address)))
+        .json(&timer_of("bob"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let mut timers = vec![];
+    for employee in &["alice", "bob"] {
+        let response = admin()
+            .post(format!("{}/api/timers", app.address))
+            .json(&timer_of(employee))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status().as_u16(), 201);
+        let Timer { id, .. } = response.json().await.unwrap();
+        timers.push(id);
+    }
+    let (own, bobs) = (timers[0], timers[1]);
+
+    let response = alice(client.get(format!("{}/api/timers", app.address)))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let listed: Vec<Timer> = response.json().await.unwrap();
+    assert_eq!(listed.iter().map(|t| t.id).collect::<Vec<_>>(), vec![own]);
+
+    for request in [
+        alice(client.get(format!("{}/api/timers/{}", app.address, bobs))),
+        alice(client.post(format!("{}/api/timers/{}/stop", app.address, bobs))),
+        alice(client.delete(format!("{}/api/timers/{}", app.address, bobs))),
+        boss(client.post(format!("{}/api/timers/{}/stop", app.address, own))),
+        boss(client.delete(format!("{}/api/timers/{}", app.address, own))),
+    ] {
+        let response = request.send().await.expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), 403);
+    }
+
+    let response = alice(client.delete(format!("{}/api/timers/{}", app.address, own)))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 204);
+}
+
+#[actix_rt::test]
+async fn roles_timers_are_started_for_the_caller() {
+    let app = spawn_app().await;
+    let alice = as_user(&app, "alice", "employee").await;
+
+    let client = Client::new();
+    let mut own_timer = timer_of("alice");
+    own_timer.as_object_mut().unwrap().remove("employee");
+    let response = alice(client.post(format!("{}/api/timers", app.address)))
+        .json(&own_timer)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let timer: Timer = response.json().await.unwrap();
+    assert_eq!(timer.employee, "alice");
+
+    // The timer ran for less than a minute, so it passes the checks and fails validation.
+    let response = alice(client.post(format!("{}/api/timers/{}/stop", app.address, timer.id)))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[actix_rt::test]
+async fn roles_timesheets_are_reviewed_by_the_manager() {
+    let app = spawn_app().await;
+    let alice = as_user(&app, "alice", "employee").await;
+    let boss = as_user(&app, "boss", "manager").await;
+
+    let client = Client::new();
+    let sheet = |employee: &str, action: &str| {
+        format!(
+            "{}/api/timesheets/{}/2021-10-04/{}",
+            app.address, employee, action
+        )
+    };
+    let response = alice(client.post(sheet("bob", "submit")))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+    for employee in &["alice", "bob", "boss"] {
+        let response = admin()
+            .post(sheet(employee, "submit"))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    for request in [
+        alice(client.post(sheet("alice", "approve"))),
+        alice(
+            client
+                .post(sheet("alice", "reject"))
+                .json(&json!({"reason": "reason"})),
+        ),
+        boss(client.post(sheet("boss", "approve"))),
+        boss(client.post(sheet("bob", "approve"))),
+    ] {
+        let response = request.send().await.expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), 403);
+    }
+
+    let response = boss(client.post(sheet("alice", "approve")))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[actix_rt::test]
+async fn roles_reports_sum_up_only_visible_hours() {
+    let app = spawn_app().await;
+    let alice = as_user(&app, "alice", "employee").await;
+
+    let response = alice(Client::new().get(format!(
+        "{}/api/reports/summary?from=2021-10-01&to=2021-10-31&granularity=week",
+        app.address
+    )))
+    .send()
+    .await
+    .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    let result: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(result["total"], 1);
+    assert_eq!(result["periods"][0]["employees"][0]["employee"], "alice");
+    assert_eq!(
+        result["periods"][0]["employees"].as_array().unwrap().len(),
+        1
+    );
+}
+
+fn hours_of(employee: &str) -> serde_json::Value {
+    json!({
+        "employee": employee,
+        "date": "2021-10-09",
+        "project": "project",
+        "story_id": null,
+        "description": "description",
+        "hours": 1
+    })
+}
+
+fn timer_of(employee: &str) -> serde_json::Value {
+    json!({
+        "employee": employee,
+        "project": "project",
+        "story_id": null,
+        "description": "description"
+    })
+}
+
+/// Creates a key for `name` with `role` and returns a function that sends requests with it.
+async fn as_user(
+    app: &TestApp,
+    name: &str,
+    role: &str,
+) -> impl Fn(RequestBuilder) -> RequestBuilder {
+    let response = admin()
+        .post(format!("{}/api/admin/api-keys", app.address))
+        .json(&json!({"name": name, "role": role}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let created: CreatedApiKey = response.json().await.unwrap();
+    let authorization = format!("ApiKey {}", created.key);
+    move |request: RequestBuilder| request.header(AUTHORIZATION, authorization.clone())
+}
+
+fn admin() -> Client {
+    let authorization = format!("ApiKey {}", test_utils::TEST_API_KEY);
+    let headers = std::iter::once((AUTHORIZATION, authorization.parse().unwrap())).collect();
+    Client::builder().default_headers(headers).build().unwrap()
+}
+
+async fn spawn_app() -> TestApp {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let server = cb_rust_demo::run_server(pool, listener, Settings::default())
+        .expect("Server failed to start");
+    tokio::spawn(server);
+    let address = format!("http://127.0.0.1:{}", port);
+
+    let client = admin();
+    for employee in &[
+        json!({"username": "boss", "name": "Boss"}),
+        json!({"username": "alice", "name": "Alice", "manager": "boss"}),
+        json!({"username": "bob", "name": "Bob"}),
+    ] {
+        let response = client
+            .post(format!("{}/api/employees", address))
+            .json(employee)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status().as_u16(), 201);
+    }
+    let response = client
+        .post(format!("{}/api/projects", address))
+        .json(&json!({"code": "project", "name": "Project"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let mut ids = vec![];
+    for employee in &["alice", "bob"] {
+        let response = client
+            .post(format!("{}/api/hours", address))
+            .json(&hours_of(employee))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status().as_u16(), 201);
+        let Hours { id, .. } = response.json().await.unwrap();
+        ids.push(id);
+    }
+    TestApp {
+        address,
+        alice: ids[0],
+        bob: ids[1],
+    }
+}
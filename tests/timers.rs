@@ -0,0 +1,271 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use cb_rust_demo::configuration::Settings;
+use cb_rust_demo::error::{ErrorResponse, FieldValidationError};
+use cb_rust_demo::test_utils;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Timer {
+    id: Uuid,
+    employee: String,
+    project: String,
+    story_id: Option<String>,
+    started_at: String,
+}
+
+#[derive(Deserialize)]
+struct Hours {
+    id: Uuid,
+    employee: String,
+    project: String,
+    story_id: Option<String>,
+    hours: f64,
+    logged_by: Option<String>,
+}
+
+#[actix_rt::test]
+async fn timers_start_and_stop() {
+    let (address, pool) = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/timers", address))
+        .json(&timer("employee"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+    let started: Timer = response.json().await.unwrap();
+
+    sqlx::query("UPDATE timers SET started_at = started_at - INTERVAL '90 minutes'")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let response = client
+        .post(format!("{}/api/timers/{}/stop", address, started.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 201);
+    let hours: Hours = response.json().await.unwrap();
+    assert_eq!(hours.employee, "employee");
+    assert_eq!(hours.project, "project");
+    assert_eq!(hours.story_id, Some("story".to_owned()));
+    assert_eq!(hours.hours, 1.5);
+    assert_eq!(hours.logged_by.as_deref(), Some("test"));
+
+    let response = client
+        .get(format!("{}/api/hours/{}", address, hours.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = client
+        .get(format!("{}/api/timers/{}", address, started.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[actix_rt::test]
+async fn timers_one_running_per_employee() {
+    let (address, _) = spawn_app().await;
+
+    let client = client();
+    for expected in &[201, 409] {
+        let response = client
+            .post(format!("{}/api/timers", address))
+            .json(&timer("employee"))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        assert_eq!(response.status().as_u16(), *expected);
+    }
+
+    let response = client
+        .get(format!("{}/api/timers?employee=employee", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    let result: Vec<Timer> = response.json().await.unwrap();
+    assert_eq!(result.len(), 1);
+}
+
+#[actix_rt::test]
+async fn timers_require_existing_employee() {
+    let (address, _) = spawn_app().await;
+
+    let response = client()
+        .post(format!("{}/api/timers", address))
+        .json(&timer("nobody"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "employee".into(),
+                "does not exist".into()
+            )]
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn timers_stopped_too_soon_keep_running() {
+    let (address, _) = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/timers", address))
+        .json(&timer("employee"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let started: Timer = response.json().await.unwrap();
+
+    let response = client
+        .post(format!("{}/api/timers/{}/stop", address, started.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = client
+        .get(format!("{}/api/timers/{}", address, started.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let result: Timer = response.json().await.unwrap();
+    assert_eq!(result, started);
+
+    let response = client
+        .delete(format!("{}/api/timers/{}", address, started.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = client
+        .get(format!("{}/api/hours", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.text().await.unwrap(), "[]");
+}
+
+#[actix_rt::test]
+async fn timers_left_running_are_stopped_with_the_time_to_log() {
+    let (address, pool) = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/timers", address))
+        .json(&timer("employee"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let started: Timer = response.json().await.unwrap();
+
+    sqlx::query("UPDATE timers SET started_at = started_at - INTERVAL '30 hours'")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let response = client
+        .post(format!("{}/api/timers/{}/stop", address, started.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = client
+        .post(format!(
+            "{}/api/timers/{}/stop?duration=PT7H30M",
+            address, started.id
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let hours: Hours = response.json().await.unwrap();
+    assert_eq!(hours.hours, 7.5);
+}
+
+#[actix_rt::test]
+async fn timers_stop_nonexistent() {
+    let (address, _) = spawn_app().await;
+
+    let response = client()
+        .post(format!("{}/api/timers/{}/stop", address, Uuid::new_v4()))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+fn timer(employee: &str) -> serde_json::Value {
+    json!({
+        "employee": employee,
+        "project": "project",
+        "story_id": "story",
+        "description": "description"
+    })
+}
+
+/// A client that authenticates with the test API key.
+fn client() -> Client {
+    let authorization = format!("ApiKey {}", test_utils::TEST_API_KEY);
+    let headers = std::iter::once((AUTHORIZATION, authorization.parse().unwrap())).collect();
+    Client::builder().default_headers(headers).build().unwrap()
+}
+
+async fn spawn_app() -> (String, PgPool) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let server = cb_rust_demo::run_server(pool.clone(), listener, Settings::default())
+        .expect("Server failed to start");
+    tokio::spawn(server);
+    let address = format!("http://127.0.0.1:{}", port);
+    let client = client();
+    let response = client
+        .post(format!("{}/api/employees", address))
+        .json(&json!({"username": "employee", "name": "Employee"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let response = client
+        .post(format!("{}/api/projects", address))
+        .json(&json!({"code": "project", "name": "Project"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    (address, pool)
+}
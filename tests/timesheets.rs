@@ -0,0 +1,311 @@
+#![cfg(test)]
+use std::net::TcpListener;
+
+use reqwest::header::AUTHORIZATION;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use cb_rust_demo::configuration::Settings;
+use cb_rust_demo::error::{ErrorResponse, FieldValidationError};
+use cb_rust_demo::test_utils;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Timesheet {
+    employee: String,
+    week: String,
+    state: String,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Hours {
+    id: Uuid,
+}
+
+#[actix_rt::test]
+async fn timesheets_draft_by_default() {
+    let address = spawn_app().await;
+
+    let response = client()
+        .get(format!("{}/api/timesheets/employee/2021-10-04", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 200);
+    let result: Timesheet = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        Timesheet {
+            employee: "employee".to_owned(),
+            week: "2021-10-04".to_owned(),
+            state: "draft".to_owned(),
+            reason: None,
+        }
+    );
+}
+
+#[actix_rt::test]
+async fn timesheets_submitted_week_refuses_changes() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("2021-10-09"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let Hours { id } = response.json().await.unwrap();
+
+    let response = client
+        .post(format!(
+            "{}/api/timesheets/employee/2021-10-04/submit",
+            address
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let result: Timesheet = response.json().await.unwrap();
+    assert_eq!(result.state, "submitted");
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("2021-10-08"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 409);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::new(
+            "The timesheet of employee for the week starting 2021-10-04 is submitted".into()
+        )
+    );
+
+    let response = client
+        .delete(format!("{}/api/hours/{}", address, id))
+        .header("If-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 409);
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("2021-10-11"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+#[actix_rt::test]
+async fn timesheets_rejected_with_reason_can_be_changed() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let url = format!("{}/api/timesheets/employee/2021-10-04", address);
+    let response = client
+        .post(format!("{}/submit", url))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = client
+        .post(format!("{}/reject", url))
+        .json(&json!({"reason": " "}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+    let result: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(
+        result,
+        ErrorResponse::with_validation_errors(
+            "Validation errors".into(),
+            vec![FieldValidationError::new(
+                "reason".into(),
+                "can not be empty".into()
+            )]
+        )
+    );
+
+    let response = client
+        .post(format!("{}/reject", url))
+        .json(&json!({"reason": "Friday is missing"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let result: Timesheet = response.json().await.unwrap();
+    assert_eq!(result.state, "rejected");
+    assert_eq!(result.reason, Some("Friday is missing".to_owned()));
+
+    let response = client
+        .post(format!("{}/api/hours", address))
+        .json(&hours("2021-10-08"))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let response = client
+        .get(format!("{}/api/timesheets?state=rejected", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let result: Vec<Timesheet> = response.json().await.unwrap();
+    assert_eq!(result.len(), 1);
+}
+
+#[actix_rt::test]
+async fn timesheets_approve_requires_submission() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let url = format!("{}/api/timesheets/employee/2021-10-04", address);
+    let response = client
+        .post(format!("{}/approve", url))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 409);
+
+    for action in &["submit", "approve"] {
+        let response = client
+            .post(format!("{}/{}", url, action))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let result: Timesheet = response.json().await.unwrap();
+    assert_eq!(result.state, "approved");
+}
+
+#[actix_rt::test]
+async fn timesheets_concurrent_reviews_only_one_succeeds() {
+    let address = spawn_app().await;
+
+    let client = client();
+    let url = format!("{}/api/timesheets/employee/2021-10-04", address);
+    let response = client
+        .post(format!("{}/submit", url))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let requests = (0..3).flat_map(|_| {
+        vec![
+            client.post(format!("{}/approve", url)).send(),
+            client
+                .post(format!("{}/reject", url))
+                .json(&json!({"reason": "reason"}))
+                .send(),
+        ]
+    });
+    let mut statuses: Vec<u16> = futures::future::join_all(requests)
+        .await
+        .into_iter()
+        .map(|response| {
+            response
+                .expect("Failed to execute request.")
+                .status()
+                .as_u16()
+        })
+        .collect();
+    statuses.sort_unstable();
+
+    assert_eq!(statuses, vec![200, 409, 409, 409, 409, 409]);
+}
+
+#[actix_rt::test]
+async fn timesheets_week_must_be_monday() {
+    let address = spawn_app().await;
+
+    let response = client()
+        .post(format!(
+            "{}/api/timesheets/employee/2021-10-09/submit",
+            address
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[actix_rt::test]
+async fn timesheets_unknown_employee() {
+    let address = spawn_app().await;
+
+    let response = client()
+        .post(format!(
+            "{}/api/timesheets/nobody/2021-10-04/submit",
+            address
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+fn hours(date: &str) -> serde_json::Value {
+    json!({
+        "employee": "employee",
+        "date": date,
+        "project": "project",
+        "story_id": null,
+        "description": "description",
+        "hours": 1
+    })
+}
+
+/// A client that authenticates with the test API key.
+fn client() -> Client {
+    let authorization = format!("ApiKey {}", test_utils::TEST_API_KEY);
+    let headers = std::iter::once((AUTHORIZATION, authorization.parse().unwrap())).collect();
+    Client::builder().default_headers(headers).build().unwrap()
+}
+
+async fn spawn_app() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Port not open");
+    let port = listener.local_addr().unwrap().port();
+    let pool = test_utils::get_db_pool().await;
+    test_utils::add_test_api_key(&pool).await;
+    let server = cb_rust_demo::run_server(pool, listener, Settings::default())
+        .expect("Server failed to start");
+    tokio::spawn(server);
+    let address = format!("http://127.0.0.1:{}", port);
+    let client = client();
+    let response = client
+        .post(format!("{}/api/employees", address))
+        .json(&json!({"username": "employee", "name": "Employee"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    let response = client
+        .post(format!("{}/api/projects", address))
+        .json(&json!({"code": "project", "name": "Project"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 201);
+    address
+}